@@ -2,18 +2,24 @@
 //mod tests;
 mod state;
 
-use lend_shared::core;
+use lend_shared::core::{self, AccrualMode, Breakpoint, InterestRateModel};
 use lend_shared::fadroma::{
     admin,
     admin::{assert_admin, Admin},
     cosmwasm_std,
-    cosmwasm_std::{HandleResponse, HumanAddr, InitResponse, StdResult},
+    cosmwasm_std::{
+        Api, Extern, HandleResponse, HumanAddr, InitResponse, Querier, StdError, StdResult,
+        Storage,
+    },
     derive_contract::*,
-    require_admin, Decimal256,
+    require_admin, Canonize, Decimal256,
 };
-use lend_shared::interfaces::interest_model::ConfigResponse;
+use lend_shared::interfaces::interest_model::{AccrualRateResponse, ConfigResponse};
 
-use state::{load_interest_model, save_interest_model};
+use state::{
+    load_accrual_mode, load_interest_model, load_rate_at_target, save_accrual_mode,
+    save_interest_model, save_rate_at_target,
+};
 
 #[contract_impl(
     entry,
@@ -29,15 +35,26 @@ pub trait InterestModel {
         jump_multiplier_year: Decimal256,
         jump_threshold: Decimal256,
         blocks_year: Option<u64>,
+        stable_premium_year: Option<Decimal256>,
+        accrual_mode: Option<AccrualMode>,
     ) -> StdResult<InitResponse> {
-        let interest_model = core::JumpRateInterest::v1(
+        let accrual_mode = accrual_mode.unwrap_or_default();
+        let periods_year = Some(accrual_mode.periods_per_year(blocks_year));
+
+        let mut interest_model = core::JumpRateInterest::v1(
             base_rate_year,
             multiplier_year,
             jump_multiplier_year,
             jump_threshold,
-            blocks_year,
+            periods_year,
         )?;
-        save_interest_model(&mut deps.storage, &interest_model)?;
+
+        if let Some(premium) = stable_premium_year {
+            interest_model.set_stable_premium(premium, periods_year)?;
+        }
+
+        save_interest_model(&mut deps.storage, &InterestRateModel::JumpRate(interest_model))?;
+        save_accrual_mode(&mut deps.storage, &accrual_mode)?;
 
         admin::DefaultImpl.new(admin, deps, env)
     }
@@ -50,15 +67,118 @@ pub trait InterestModel {
         jump_multiplier_year: Decimal256,
         jump_threshold: Decimal256,
         blocks_year: Option<u64>,
+        stable_premium_year: Option<Decimal256>,
+        accrual_mode: Option<AccrualMode>,
     ) -> StdResult<HandleResponse> {
-        let new_interest_model = core::JumpRateInterest::v1(
+        let accrual_mode = match accrual_mode {
+            Some(mode) => mode,
+            None => load_accrual_mode(&deps.storage)?,
+        };
+        let periods_year = Some(accrual_mode.periods_per_year(blocks_year));
+
+        let mut new_interest_model = core::JumpRateInterest::v1(
             base_rate_year,
             multiplier_year,
             jump_multiplier_year,
             jump_threshold,
-            blocks_year,
+            periods_year,
         )?;
-        save_interest_model(&mut deps.storage, &new_interest_model)?;
+
+        if let Some(premium) = stable_premium_year {
+            new_interest_model.set_stable_premium(premium, periods_year)?;
+        }
+
+        save_interest_model(&mut deps.storage, &InterestRateModel::JumpRate(new_interest_model))?;
+        save_accrual_mode(&mut deps.storage, &accrual_mode)?;
+
+        Ok(HandleResponse::default())
+    }
+
+    #[handle]
+    #[require_admin]
+    fn update_kinked_config(
+        breakpoints: Vec<Breakpoint>,
+        blocks_year: Option<u64>,
+        stable_premium_year: Option<Decimal256>,
+        accrual_mode: Option<AccrualMode>,
+    ) -> StdResult<HandleResponse> {
+        let accrual_mode = match accrual_mode {
+            Some(mode) => mode,
+            None => load_accrual_mode(&deps.storage)?,
+        };
+        let periods_year = Some(accrual_mode.periods_per_year(blocks_year));
+
+        let mut new_interest_model = core::KinkedInterest::new(breakpoints, periods_year)?;
+
+        if let Some(premium) = stable_premium_year {
+            new_interest_model.set_stable_premium(premium, periods_year)?;
+        }
+
+        save_interest_model(&mut deps.storage, &InterestRateModel::Kinked(new_interest_model))?;
+        save_accrual_mode(&mut deps.storage, &accrual_mode)?;
+
+        Ok(HandleResponse::default())
+    }
+
+    #[handle]
+    #[require_admin]
+    fn update_adaptive_config(
+        target_utilization: Decimal256,
+        initial_rate_year: Decimal256,
+        min_rate_year: Decimal256,
+        max_rate_year: Decimal256,
+        adjustment_speed_year: Decimal256,
+        curve_steepness: Decimal256,
+        blocks_year: Option<u64>,
+        stable_premium_year: Option<Decimal256>,
+        accrual_mode: Option<AccrualMode>,
+    ) -> StdResult<HandleResponse> {
+        // The adjustment speed is converted along with the rates, since
+        // markets that accrue by time report the elapsed seconds in `on_accrue`.
+        let accrual_mode = match accrual_mode {
+            Some(mode) => mode,
+            None => load_accrual_mode(&deps.storage)?,
+        };
+        let periods_year = Some(accrual_mode.periods_per_year(blocks_year));
+
+        let mut new_interest_model = core::AdaptiveInterest::new(
+            target_utilization,
+            initial_rate_year,
+            min_rate_year,
+            max_rate_year,
+            adjustment_speed_year,
+            curve_steepness,
+            periods_year,
+        )?;
+
+        if let Some(premium) = stable_premium_year {
+            new_interest_model.set_stable_premium(premium, periods_year)?;
+        }
+
+        save_interest_model(&mut deps.storage, &InterestRateModel::Adaptive(new_interest_model))?;
+        save_accrual_mode(&mut deps.storage, &accrual_mode)?;
+
+        Ok(HandleResponse::default())
+    }
+
+    #[handle]
+    fn on_accrue(
+        market_size: Decimal256,
+        num_borrows: Decimal256,
+        reserves: Decimal256,
+        blocks: u64,
+    ) -> StdResult<HandleResponse> {
+        // Static models don't need to react. Since every caller can only
+        // move its own rate, there is no need to restrict who calls this.
+        if let InterestRateModel::Adaptive(mut model) =
+            load_model_for(deps, Some(&env.message.sender))?
+        {
+            model.adjust(market_size, num_borrows, reserves, blocks)?;
+
+            let sender = env.message.sender.canonize(&deps.api)?;
+            save_rate_at_target(&mut deps.storage, &sender, &model.rate_at_target)?;
+        }
+
         Ok(HandleResponse::default())
     }
 
@@ -66,11 +186,55 @@ pub trait InterestModel {
     fn config() -> StdResult<ConfigResponse> {
         let config = state::load_interest_model(&deps.storage)?;
 
-        Ok(ConfigResponse {
-            multiplier_block: config.multiplier_block,
-            jump_multiplier_block: config.jump_multiplier_block,
-            base_rate_block: config.base_rate_block,
-            jump_threshold: config.jump_threshold,
+        Ok(match config {
+            InterestRateModel::JumpRate(config) => ConfigResponse::JumpRate {
+                multiplier_block: config.multiplier_block,
+                jump_multiplier_block: config.jump_multiplier_block,
+                base_rate_block: config.base_rate_block,
+                jump_threshold: config.jump_threshold,
+                stable_premium_block: config.stable_premium_block,
+            },
+            InterestRateModel::Kinked(config) => ConfigResponse::Kinked {
+                breakpoints: config.breakpoints,
+                stable_premium_block: config.stable_premium_block,
+            },
+            InterestRateModel::Adaptive(config) => ConfigResponse::Adaptive {
+                target_utilization: config.target_utilization,
+                initial_rate_at_target: config.rate_at_target,
+                min_rate_at_target: config.min_rate_at_target,
+                max_rate_at_target: config.max_rate_at_target,
+                adjustment_speed_block: config.adjustment_speed_block,
+                curve_steepness: config.curve_steepness,
+                stable_premium_block: config.stable_premium_block,
+            },
+        })
+    }
+
+    #[query]
+    fn accrual_mode() -> StdResult<AccrualMode> {
+        load_accrual_mode(&deps.storage)
+    }
+
+    #[query]
+    fn rate_at_target(market: HumanAddr) -> StdResult<Decimal256> {
+        match load_model_for(deps, Some(&market))? {
+            InterestRateModel::Adaptive(model) => Ok(model.rate_at_target),
+            _ => Err(StdError::generic_err("The interest model is not adaptive.")),
+        }
+    }
+
+    #[query]
+    fn accrual_rate(
+        market: HumanAddr,
+        market_size: Decimal256,
+        num_borrows: Decimal256,
+        reserves: Decimal256,
+    ) -> StdResult<AccrualRateResponse> {
+        let interest_model = load_model_for(deps, Some(&market))?;
+
+        Ok(AccrualRateResponse {
+            borrow_rate: interest_model.borrow_rate(market_size, num_borrows, reserves)?,
+            notify: interest_model.is_adaptive(),
         })
     }
 
@@ -79,21 +243,54 @@ pub trait InterestModel {
         market_size: Decimal256,
         num_borrows: Decimal256,
         reserves: Decimal256,
+        market: Option<HumanAddr>,
     ) -> StdResult<Decimal256> {
-        let interest_model = load_interest_model(&deps.storage)?;
+        let interest_model = load_model_for(deps, market.as_ref())?;
 
         Ok(interest_model.borrow_rate(market_size, num_borrows, reserves)?)
     }
 
+    #[query]
+    fn stable_borrow_rate(
+        market_size: Decimal256,
+        num_borrows: Decimal256,
+        reserves: Decimal256,
+        market: Option<HumanAddr>,
+    ) -> StdResult<Decimal256> {
+        let interest_model = load_model_for(deps, market.as_ref())?;
+
+        Ok(interest_model.stable_borrow_rate(market_size, num_borrows, reserves)?)
+    }
+
     #[query]
     fn supply_rate(
         market_size: Decimal256,
         num_borrows: Decimal256,
         reserves: Decimal256,
         reserve_factor: Decimal256,
+        market: Option<HumanAddr>,
     ) -> StdResult<Decimal256> {
-        let interest_model = load_interest_model(&deps.storage)?;
+        let interest_model = load_model_for(deps, market.as_ref())?;
 
         Ok(interest_model.supply_rate(market_size, num_borrows, reserves, reserve_factor)?)
     }
 }
+
+/// Loads the interest model with the adaptive rate of the given `market`, if any.
+/// Markets that haven't notified the model yet use the initial rate.
+fn load_model_for<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    market: Option<&HumanAddr>,
+) -> StdResult<InterestRateModel> {
+    let mut interest_model = load_interest_model(&deps.storage)?;
+
+    if let (InterestRateModel::Adaptive(model), Some(market)) = (&mut interest_model, market) {
+        let market = deps.api.canonical_address(market)?;
+
+        if let Some(rate) = load_rate_at_target(&deps.storage, &market)? {
+            model.with_rate_at_target(rate);
+        }
+    }
+
+    Ok(interest_model)
+}
@@ -1,17 +1,45 @@
 use lend_shared::{
-    core::JumpRateInterest,
+    core::{AccrualMode, InterestRateModel},
     fadroma::{
-        cosmwasm_std::{StdResult, Storage},
-        storage::{load, save},
+        cosmwasm_std::{CanonicalAddr, StdResult, Storage},
+        storage::{load, save, ns_load, ns_save},
+        Decimal256,
     },
 };
 
 static KEY_INTEREST_MODEL: &[u8] = b"interest_model";
+static KEY_ACCRUAL_MODE: &[u8] = b"accrual_mode";
+static NS_RATES_AT_TARGET: &[u8] = b"rates_at_target";
 
-pub fn save_interest_model(storage: &mut impl Storage, model: &JumpRateInterest) -> StdResult<()> {
+pub fn save_interest_model(storage: &mut impl Storage, model: &InterestRateModel) -> StdResult<()> {
     save(storage, KEY_INTEREST_MODEL, model)
 }
 
-pub fn load_interest_model(storage: &impl Storage) -> StdResult<JumpRateInterest> {
+pub fn load_interest_model(storage: &impl Storage) -> StdResult<InterestRateModel> {
     Ok(load(storage, KEY_INTEREST_MODEL)?.unwrap())
 }
+
+/// Whether the rates of the model are per block or per second.
+pub fn save_accrual_mode(storage: &mut impl Storage, mode: &AccrualMode) -> StdResult<()> {
+    save(storage, KEY_ACCRUAL_MODE, mode)
+}
+
+pub fn load_accrual_mode(storage: &impl Storage) -> StdResult<AccrualMode> {
+    Ok(load(storage, KEY_ACCRUAL_MODE)?.unwrap_or_default())
+}
+
+/// The current rate at target of the adaptive model for the given market.
+pub fn save_rate_at_target(
+    storage: &mut impl Storage,
+    market: &CanonicalAddr,
+    rate: &Decimal256
+) -> StdResult<()> {
+    ns_save(storage, NS_RATES_AT_TARGET, market.as_slice(), rate)
+}
+
+pub fn load_rate_at_target(
+    storage: &impl Storage,
+    market: &CanonicalAddr
+) -> StdResult<Option<Decimal256>> {
+    ns_load(storage, NS_RATES_AT_TARGET, market.as_slice())
+}
@@ -6,23 +6,88 @@ use lend_shared::{
         },
         Uint256, ContractLink
     },
-    interfaces::overseer::{
-        query_config, query_account_liquidity
+    interfaces::{
+        overseer::{
+            query_config, query_account_liquidity, query_entered_markets,
+            query_market, MarketStatus
+        },
+        market::{query_account, Config, MarketAuth},
+        interest_model::query_accrual_mode
     },
-    core::MasterKey
+    core::{AccrualMode, MasterKey}
 };
 
-use crate::state::{Contracts, Global, TotalBorrows};
+use crate::state::{Constants, Contracts, Global, TotalBorrows};
+
+pub enum Action {
+    Deposit,
+    Redeem,
+    Borrow,
+    Repay,
+    Liquidate,
+    Transfer
+}
+
+pub fn assert_not_paused(storage: &impl Storage, action: Action) -> StdResult<()> {
+    let paused = Constants::load_paused(storage)?;
+
+    let (is_paused, name) = match action {
+        Action::Deposit => (paused.deposit, "Deposits"),
+        Action::Redeem => (paused.redeem, "Redeems"),
+        Action::Borrow => (paused.borrow, "Borrows"),
+        Action::Repay => (paused.repay, "Repays"),
+        Action::Liquidate => (paused.liquidate, "Liquidations"),
+        Action::Transfer => (paused.transfer, "Transfers")
+    };
+
+    if is_paused {
+        Err(StdError::generic_err(format!("{} are currently paused in this market.", name)))
+    } else {
+        Ok(())
+    }
+}
+
+pub fn assert_no_flash_loan(storage: &impl Storage) -> StdResult<()> {
+    if Global::load_flash_loan(storage)?.is_some() {
+        Err(StdError::generic_err("Cannot interact with the market while a flash loan is in progress."))
+    } else {
+        Ok(())
+    }
+}
+
+/// The rates of the interest model must be per block or per second
+/// depending on how the market accrues interest.
+pub fn assert_accrual_mode_matches(
+    querier: &impl Querier,
+    interest_model: ContractLink<HumanAddr>,
+    mode: AccrualMode
+) -> StdResult<()> {
+    let model_mode = query_accrual_mode(querier, interest_model)?;
+
+    if model_mode != mode {
+        Err(StdError::generic_err(format!(
+            "The interest model accrues in {:?} mode, but the market accrues in {:?} mode.",
+            model_mode,
+            mode
+        )))
+    } else {
+        Ok(())
+    }
+}
 
 pub fn assert_borrow_allowed<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S,A,Q>,
     sender: HumanAddr,
     block: u64,
+    time: Option<u64>,
     self_addr: HumanAddr,
     amount: Uint256
 ) -> StdResult<()> {
     if let Some(cap) = Global::load_borrow_cap(&deps.storage)? {
-        let total = TotalBorrows::load(&deps.storage)?;
+        let total = (
+            TotalBorrows::load(&deps.storage)? +
+            Global::load_stable_debt(&deps.storage)?.total
+        )?;
 
         let new = total.0.checked_add(amount.0).ok_or_else(||
             StdError::generic_err("Total borrows amount overflowed.")
@@ -40,6 +105,7 @@ pub fn assert_borrow_allowed<S: Storage, A: Api, Q: Querier>(
         sender,
         Some(self_addr),
         Some(block),
+        time,
         Uint256::zero(),
         amount
     )?;
@@ -51,12 +117,30 @@ pub fn assert_borrow_allowed<S: Storage, A: Api, Q: Querier>(
     }
 }
 
+pub fn assert_deposit_allowed<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S,A,Q>,
+    self_addr: HumanAddr
+) -> StdResult<()> {
+    let market = query_market(
+        &deps.querier,
+        Contracts::load_overseer(deps)?,
+        self_addr
+    )?;
+
+    if market.status != MarketStatus::Active {
+        return Err(StdError::generic_err("Market is deprecated and doesn't accept deposits."));
+    }
+
+    Ok(())
+}
+
 pub fn assert_liquidate_allowed<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S,A,Q>,
     overseer: ContractLink<HumanAddr>,
     borrower: HumanAddr,
     borrower_balance: Uint256,
     block: u64,
+    time: Option<u64>,
     amount: Uint256
 ) -> StdResult<()> {
     if amount == Uint256::zero() {
@@ -70,6 +154,7 @@ pub fn assert_liquidate_allowed<S: Storage, A: Api, Q: Querier>(
         borrower,
         None,
         Some(block),
+        time,
         Uint256::zero(),
         Uint256::zero()
     )?;
@@ -96,6 +181,62 @@ pub fn assert_liquidate_allowed<S: Storage, A: Api, Q: Querier>(
     }
 }
 
+/// Asserts that the borrower has no slTokens left in any of the markets
+/// that they have entered. `balance` is the balance in this market.
+pub fn assert_no_collateral<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S,A,Q>,
+    self_addr: &HumanAddr,
+    borrower: HumanAddr,
+    balance: Uint256,
+    block: u64,
+    time: Option<u64>
+) -> StdResult<()> {
+    const ERR: &str = "Borrower still has collateral and their debt cannot be written off.";
+
+    if !balance.is_zero() {
+        return Err(StdError::generic_err(ERR));
+    }
+
+    let key = MasterKey::load(&deps.storage)?;
+    let markets = query_entered_markets(
+        &deps.querier,
+        Contracts::load_overseer(deps)?,
+        key.clone(),
+        borrower.clone()
+    )?;
+
+    for market in markets {
+        if market.contract.address == *self_addr {
+            continue;
+        }
+
+        let account = query_account(
+            &deps.querier,
+            market.contract,
+            MarketAuth::Internal {
+                key: key.clone(),
+                address: borrower.clone()
+            },
+            Some(block),
+            time
+        )?;
+
+        if !account.sl_token_balance.is_zero() {
+            return Err(StdError::generic_err(ERR));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn assert_stable_borrow_enabled(config: &Config) -> StdResult<()> {
+    if config.stable_rate_threshold.is_none() {
+        Err(StdError::generic_err("Stable rate borrowing is not enabled in this market."))
+    } else {
+        Ok(())
+    }
+}
+
 pub fn assert_can_withdraw(
     balance: Uint256,
     amount: Uint256
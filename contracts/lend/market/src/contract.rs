@@ -4,7 +4,7 @@ mod state;
 mod token;
 
 use lend_shared::{
-    core::{AuthenticatedUser, MasterKey, Pagination},
+    core::{utilization_rate, AuthenticatedUser, MasterKey, Pagination},
     fadroma::{
         admin,
         admin::{assert_admin, Admin},
@@ -14,7 +14,7 @@ use lend_shared::{
         },
         cosmwasm_std,
         cosmwasm_std::{
-            Api, Binary, CosmosMsg, Env, Extern, HandleResponse,
+            Api, Binary, BlockInfo, CosmosMsg, Env, Extern, HandleResponse,
             HumanAddr, InitResponse, Querier, StdError, StdResult,
             Storage, WasmMsg, log
         },
@@ -26,16 +26,19 @@ use lend_shared::{
         to_binary, Callback, ContractLink, Decimal256, Uint128, Uint256, BLOCK_SIZE,
     },
     interfaces::{
-        interest_model::{query_borrow_rate, query_supply_rate},
+        interest_model::{query_borrow_rate, query_stable_borrow_rate, query_supply_rate},
         market::{
-            AccountInfo, Borrower, Config, HandleMsg, MarketAuth,
-            MarketPermissions, ReceiverCallbackMsg, State, BorrowersResponse,
+            AccountInfo, BadDebt, Borrower, Config, HandleMsg, HistoryEvent, HistoryResponse,
+            MarketAuth, MarketPermissions, PausedActions, ReceiverCallbackMsg, State, SwapOutputAction,
+            BorrowersResponse, BorrowerAddressesResponse, ReservePolicy, SweepReservesResult,
             SimulateLiquidationResult, query_simulate_seize
         },
         overseer::{
             query_account_liquidity, query_can_transfer, query_entered_markets,
-            query_market, query_seize_amount,
+            query_market, query_seize_amount, query_swap_router, update_isolated_debt_msg,
+            RewardBalances,
         },
+        swap::swap_destination,
     },
 };
 
@@ -45,12 +48,16 @@ pub const MAX_BORROW_RATE: u64 = 5000000000000;
 
 const TOKEN_PREFIX: &str = "sl-";
 
-use ops::{accrue_interest, accrued_interest_at, LatestInterest};
+use checks::Action;
+use ops::{
+    accrual_point, accrue_interest, accrued_interest_at,
+    query_accrual_point, rewards_checkpoint, rewards_update, with_notification, LatestInterest
+};
 use state::{
-    load_borrowers, Account, BorrowerId, Constants, Contracts,
+    load_borrowers, Account, BorrowerId, Constants, Contracts, FlashLoan,
     Global, TotalBorrows, TotalSupply, ReceiverRegistry
 };
-use token::calc_exchange_rate;
+use token::{calc_exchange_rate, RedeemTo};
 
 #[contract_impl(
     entry,
@@ -78,6 +85,12 @@ pub trait Market {
         };
 
         config.validate()?;
+        checks::assert_accrual_mode_matches(
+            &deps.querier,
+            interest_model_contract.clone(),
+            config.accrual_mode
+        )?;
+
         Constants::save_config(&mut deps.storage, &config)?;
         BorrowerId::set_prng_seed(&mut deps.storage, &prng_seed)?;
 
@@ -87,7 +100,10 @@ pub trait Market {
         Contracts::save_self_ref(deps, self_ref.clone())?;
 
         Global::save_borrow_index(&mut deps.storage, &Decimal256::one())?;
-        Global::save_accrual_block_number(&mut deps.storage, env.block.height)?;
+        Global::save_accrual_block_number(
+            &mut deps.storage,
+            config.accrual_mode.current(&env.block)
+        )?;
 
         let viewing_key = ViewingKey::new(&env, prng_seed.as_slice(), entropy.as_slice()).0;
         Constants::save_vk(&mut deps.storage, &viewing_key)?;
@@ -159,6 +175,8 @@ pub trait Market {
             return Err(StdError::unauthorized());
         }
 
+        checks::assert_no_flash_loan(&deps.storage)?;
+
         let balance = snip20::balance_query(
             &deps.querier,
             env.contract.address.clone(),
@@ -173,14 +191,39 @@ pub trait Market {
         // in order to get the correct interest/exchange rate up to this point.
         let balance = (balance - amount)?;
 
-        let interest = accrue_interest(deps, env.block.height, balance.into())?;
+        let mut interest = accrue_interest(deps, &env.block, balance.into())?;
+        let notification = interest.take_notification();
+
+        let msg: ReceiverCallbackMsg = from_binary(&msg.unwrap())?;
+
+        checks::assert_not_paused(&deps.storage, match msg {
+            ReceiverCallbackMsg::Deposit => Action::Deposit,
+            ReceiverCallbackMsg::Repay { .. } |
+            ReceiverCallbackMsg::RepayStable { .. } => Action::Repay,
+            ReceiverCallbackMsg::Liquidate { .. } => Action::Liquidate,
+        })?;
 
-        match from_binary(&msg.unwrap())? {
+        let response = match msg {
             ReceiverCallbackMsg::Deposit => {
+                checks::assert_deposit_allowed(deps, env.contract.address.clone())?;
+
                 token::deposit(deps, interest, balance.into(), from, amount.into())
             }
             ReceiverCallbackMsg::Repay { borrower } => repay(
                 deps,
+                &env.block,
+                interest,
+                if let Some(borrower) = borrower {
+                    Account::from_id(&deps.storage, &borrower)?
+                } else {
+                    Account::of(deps, &from)?
+                },
+                from,
+                amount.into(),
+            ),
+            ReceiverCallbackMsg::RepayStable { borrower } => repay_stable(
+                deps,
+                &env.block,
                 interest,
                 if let Some(borrower) = borrower {
                     Account::from_id(&deps.storage, &borrower)?
@@ -203,21 +246,34 @@ pub trait Market {
                 collateral,
                 amount.into(),
             ),
-        }
+        }?;
+
+        Ok(with_notification(response, notification))
     }
 
     #[handle]
     fn redeem_token(burn_amount: Uint256) -> StdResult<HandleResponse> {
-        token::redeem(deps, env, burn_amount, Uint256::zero())
+        checks::assert_not_paused(&deps.storage, Action::Redeem)?;
+
+        let owner = env.message.sender.clone();
+
+        token::redeem(deps, env, owner, burn_amount, Uint256::zero(), RedeemTo::Owner)
     }
 
     #[handle]
     fn redeem_underlying(receive_amount: Uint256) -> StdResult<HandleResponse> {
-        token::redeem(deps, env, Uint256::zero(), receive_amount)
+        checks::assert_not_paused(&deps.storage, Action::Redeem)?;
+
+        let owner = env.message.sender.clone();
+
+        token::redeem(deps, env, owner, Uint256::zero(), receive_amount, RedeemTo::Owner)
     }
 
     #[handle]
     fn borrow(amount: Uint256) -> StdResult<HandleResponse> {
+        checks::assert_not_paused(&deps.storage, Action::Borrow)?;
+        checks::assert_no_flash_loan(&deps.storage)?;
+
         let underlying_asset = Contracts::load_underlying(deps)?;
 
         let balance = snip20::balance_query(
@@ -232,45 +288,249 @@ pub trait Market {
 
         checks::assert_can_withdraw(balance.into(), amount)?;
 
-        let mut latest = accrue_interest(deps, env.block.height, balance)?;
+        let mut latest = accrue_interest(deps, &env.block, balance)?;
 
         checks::assert_borrow_allowed(
             deps,
             env.message.sender.clone(),
             env.block.height,
+            Some(env.block.time),
             env.contract.address,
             amount,
         )?;
 
         let account = Account::new(deps, &env.message.sender)?;
+        let rewards = rewards_checkpoint(deps, &account)?;
 
         let mut snapshot = account.get_borrow_snapshot(&deps.storage)?;
         snapshot.add_balance(latest.borrow_index(&deps.storage)?, amount)?;
         account.save_borrow_snapshot(&mut deps.storage, snapshot)?;
+        account.push_history(
+            &mut deps.storage,
+            &env.block,
+            HistoryEvent::Borrow { amount, stable: false },
+        )?;
 
         TotalBorrows::increase(&mut deps.storage, amount)?;
 
-        Ok(HandleResponse {
-            messages: vec![snip20::transfer_msg(
+        let mut messages = vec![
+            snip20::transfer_msg(
+                env.message.sender.clone(),
+                amount.clamp_u128()?.into(),
+                None,
+                None,
+                BLOCK_SIZE,
+                underlying_asset.code_hash,
+                underlying_asset.address,
+            )?,
+            update_isolated_debt_msg(
+                Contracts::load_overseer(deps)?,
                 env.message.sender,
+                amount,
+                false,
+            )?,
+        ];
+        messages.extend(rewards_update(deps, &account, rewards)?);
+
+        Ok(with_notification(
+            HandleResponse {
+                messages,
+                log: vec![log("action", "borrow")],
+                data: None,
+            },
+            latest.take_notification(),
+        ))
+    }
+
+    #[handle]
+    fn borrow_stable(amount: Uint256) -> StdResult<HandleResponse> {
+        checks::assert_not_paused(&deps.storage, Action::Borrow)?;
+        checks::assert_no_flash_loan(&deps.storage)?;
+
+        let config = Constants::load_config(&deps.storage)?;
+        checks::assert_stable_borrow_enabled(&config)?;
+
+        let underlying_asset = Contracts::load_underlying(deps)?;
+
+        let balance = snip20::balance_query(
+            &deps.querier,
+            env.contract.address.clone(),
+            Constants::load_vk(&deps.storage)?,
+            BLOCK_SIZE,
+            underlying_asset.code_hash.clone(),
+            underlying_asset.address.clone(),
+        )?
+        .amount;
+
+        checks::assert_can_withdraw(balance.into(), amount)?;
+
+        let mut latest = accrue_interest(deps, &env.block, balance)?;
+
+        checks::assert_borrow_allowed(
+            deps,
+            env.message.sender.clone(),
+            env.block.height,
+            Some(env.block.time),
+            env.contract.address,
+            amount,
+        )?;
+
+        // The rate is locked in as it would be after the borrow.
+        let total_borrows = (
+            latest.total_borrows(&deps.storage)? +
+            latest.total_stable_borrows(&deps.storage)?
+        )?;
+        let rate = current_stable_rate(
+            deps,
+            (Uint256::from(balance) - amount)?,
+            (total_borrows + amount)?,
+            latest.total_reserves(&deps.storage)?,
+        )?;
+
+        let account = Account::new(deps, &env.message.sender)?;
+        let rewards = rewards_checkpoint(deps, &account)?;
+
+        let mut debt = latest.stable_debt(&deps.storage)?;
+        let mut snapshot = account.get_borrow_snapshot(&deps.storage)?;
+        let now = accrual_point(&deps.storage, &env.block)?;
+        snapshot.add_stable_balance(&mut debt, now, rate, amount)?;
+        account.save_borrow_snapshot(&mut deps.storage, snapshot)?;
+        account.push_history(
+            &mut deps.storage,
+            &env.block,
+            HistoryEvent::Borrow { amount, stable: true },
+        )?;
+
+        Global::save_stable_debt(&mut deps.storage, &debt)?;
+
+        let mut messages = vec![
+            snip20::transfer_msg(
+                env.message.sender.clone(),
                 amount.clamp_u128()?.into(),
                 None,
                 None,
                 BLOCK_SIZE,
                 underlying_asset.code_hash,
                 underlying_asset.address,
-            )?],
-            log: vec![log("action", "borrow")],
-            data: None,
-        })
+            )?,
+            update_isolated_debt_msg(
+                Contracts::load_overseer(deps)?,
+                env.message.sender,
+                amount,
+                false,
+            )?,
+        ];
+        messages.extend(rewards_update(deps, &account, rewards)?);
+
+        Ok(with_notification(
+            HandleResponse {
+                messages,
+                log: vec![
+                    log("action", "borrow_stable"),
+                    log("rate", rate),
+                ],
+                data: None,
+            },
+            latest.take_notification(),
+        ))
+    }
+
+    #[handle]
+    fn rebalance_stable_rate(borrower: Binary) -> StdResult<HandleResponse> {
+        checks::assert_not_paused(&deps.storage, Action::Borrow)?;
+        checks::assert_no_flash_loan(&deps.storage)?;
+
+        let config = Constants::load_config(&deps.storage)?;
+        checks::assert_stable_borrow_enabled(&config)?;
+
+        let underlying_asset = Contracts::load_underlying(deps)?;
+
+        let balance = snip20::balance_query(
+            &deps.querier,
+            env.contract.address.clone(),
+            Constants::load_vk(&deps.storage)?,
+            BLOCK_SIZE,
+            underlying_asset.code_hash,
+            underlying_asset.address,
+        )?
+        .amount;
+
+        let mut latest = accrue_interest(deps, &env.block, balance)?;
+
+        let total_borrows = (
+            latest.total_borrows(&deps.storage)? +
+            latest.total_stable_borrows(&deps.storage)?
+        )?;
+        let total_reserves = latest.total_reserves(&deps.storage)?;
+
+        let rate = current_stable_rate(deps, balance.into(), total_borrows, total_reserves)?;
+        let utilization = utilization_rate(
+            Decimal256::from_uint256(balance)?,
+            Decimal256::from_uint256(total_borrows)?,
+            Decimal256::from_uint256(total_reserves)?,
+        )?;
+
+        let account = Account::from_id(&deps.storage, &borrower)?;
+        let mut snapshot = account.get_borrow_snapshot(&deps.storage)?;
+
+        if snapshot.stable.principal.is_zero() {
+            return Err(StdError::generic_err("Borrower has no stable rate debt."));
+        }
+
+        let can_rebalance = if snapshot.stable.rate > rate {
+            // Otherwise anyone could reset every stable borrower to the current
+            // rate as soon as it dips, making the locked rate meaningless.
+            match config.stable_rate_rebalance_delta {
+                Some(delta) => rate <= (snapshot.stable.rate * (Decimal256::one() - delta)?)?,
+                None => false
+            }
+        } else {
+            // Checked by assert_stable_borrow_enabled() above.
+            let threshold = config.stable_rate_threshold.unwrap();
+
+            utilization >= threshold && rate > snapshot.stable.rate
+        };
+
+        if !can_rebalance {
+            return Err(StdError::generic_err(format!(
+                "Stable rate cannot be rebalanced. Current rate: {}, Borrower rate: {}, Utilization: {}",
+                rate,
+                snapshot.stable.rate,
+                utilization
+            )));
+        }
+
+        let mut debt = latest.stable_debt(&deps.storage)?;
+        let now = accrual_point(&deps.storage, &env.block)?;
+        snapshot.rebalance_stable_rate(&mut debt, now, rate)?;
+        account.save_borrow_snapshot(&mut deps.storage, snapshot)?;
+        account.push_history(
+            &mut deps.storage,
+            &env.block,
+            HistoryEvent::RebalanceStableRate { rate },
+        )?;
+
+        Global::save_stable_debt(&mut deps.storage, &debt)?;
+
+        Ok(with_notification(
+            HandleResponse {
+                messages: vec![],
+                log: vec![
+                    log("action", "rebalance_stable_rate"),
+                    log("rate", rate),
+                ],
+                data: None,
+            },
+            latest.take_notification(),
+        ))
     }
 
     #[handle]
     fn transfer(recipient: HumanAddr, amount: Uint256) -> StdResult<HandleResponse> {
-        do_transfer(deps, env, &recipient, amount)?;
+        let messages = do_transfer(deps, env, &recipient, amount)?;
 
         Ok(HandleResponse {
-            messages: vec![],
+            messages,
             log: vec![],
             // SNIP-20 spec compliance.
             data: Some(to_binary(&snip20_msg::HandleAnswer::Transfer {
@@ -290,7 +550,7 @@ pub trait Market {
     ) -> StdResult<HandleResponse> {
         let sender = env.message.sender.clone();
 
-        do_transfer(deps, env, &recipient, amount)?;
+        let mut messages = do_transfer(deps, env, &recipient, amount)?;
 
         let code_hash = if recipient_code_hash.is_some() {
             recipient_code_hash
@@ -298,8 +558,8 @@ pub trait Market {
             ReceiverRegistry::get(deps, &recipient)?
         };
 
-        let messages = if let Some(code_hash) = code_hash {
-            vec![
+        if let Some(code_hash) = code_hash {
+            messages.push(
                 Snip20ReceiveMsg {
                     amount: amount.low_u128().into(),
                     from: sender.clone(),
@@ -307,10 +567,8 @@ pub trait Market {
                     msg,
                     memo
                 }.into_cosmos_msg(code_hash, recipient)?
-            ]
-        } else {
-            vec![]
-        };
+            );
+        }
 
         Ok(HandleResponse {
             messages,
@@ -341,6 +599,8 @@ pub trait Market {
 
     #[handle]
     fn accrue_interest() -> StdResult<HandleResponse> {
+        checks::assert_no_flash_loan(&deps.storage)?;
+
         let underlying_asset = Contracts::load_underlying(deps)?;
 
         let balance = snip20::balance_query(
@@ -353,9 +613,12 @@ pub trait Market {
         )?
         .amount;
 
-        accrue_interest(deps, env.block.height, balance)?;
+        let mut latest = accrue_interest(deps, &env.block, balance)?;
 
-        Ok(HandleResponse::default())
+        Ok(with_notification(
+            HandleResponse::default(),
+            latest.take_notification(),
+        ))
     }
 
     #[handle]
@@ -364,6 +627,9 @@ pub trait Market {
         borrower: HumanAddr,
         amount: Uint256,
     ) -> StdResult<HandleResponse> {
+        checks::assert_not_paused(&deps.storage, Action::Liquidate)?;
+        checks::assert_no_flash_loan(&deps.storage)?;
+
         // Assert that the caller is a market contract.
         query_market(
             &deps.querier,
@@ -383,16 +649,24 @@ pub trait Market {
         )?
         .amount;
 
-        let latest = accrue_interest(deps, env.block.height, balance)?;
+        let mut latest = accrue_interest(deps, &env.block, balance)?;
+        let notification = latest.take_notification();
 
-        seize(
+        let borrower = Account::of(deps, &borrower)?;
+        let borrower_rewards = rewards_checkpoint(deps, &borrower)?;
+
+        let response = seize(
             deps,
+            &env.block,
             latest,
             balance.into(),
             Account::of(deps, &liquidator)?,
-            Account::of(deps, &borrower)?,
+            borrower,
+            borrower_rewards,
             amount
-        )
+        )?;
+
+        Ok(with_notification(response, notification))
     }
 
     #[handle]
@@ -401,7 +675,10 @@ pub trait Market {
         interest_model: Option<ContractLink<HumanAddr>>,
         reserve_factor: Option<Decimal256>,
         borrow_cap: Option<Uint256>,
+        flash_loan_fee: Option<Decimal256>,
     ) -> StdResult<HandleResponse> {
+        checks::assert_no_flash_loan(&deps.storage)?;
+
         let underlying_asset = Contracts::load_underlying(deps)?;
         let balance = snip20::balance_query(
             &deps.querier,
@@ -412,9 +689,15 @@ pub trait Market {
             underlying_asset.address.clone(),
         )?
         .amount;
-        accrue_interest(deps, env.block.height, balance)?;
+        let mut latest = accrue_interest(deps, &env.block, balance)?;
 
         if let Some(interest_model) = interest_model {
+            checks::assert_accrual_mode_matches(
+                &deps.querier,
+                interest_model.clone(),
+                Constants::load_config(&deps.storage)?.accrual_mode
+            )?;
+
             Contracts::save_interest_model(deps, interest_model)?;
         }
 
@@ -424,16 +707,27 @@ pub trait Market {
             Constants::save_config(&mut deps.storage, &config)?;
         }
 
+        if let Some(flash_loan_fee) = flash_loan_fee {
+            let mut config = Constants::load_config(&deps.storage)?;
+            config.set_flash_loan_fee(flash_loan_fee)?;
+            Constants::save_config(&mut deps.storage, &config)?;
+        }
+
         if let Some(borrow_cap) = borrow_cap {
             Global::save_borrow_cap(&mut deps.storage, &borrow_cap)?;
         }
 
-        Ok(HandleResponse::default())
+        Ok(with_notification(
+            HandleResponse::default(),
+            latest.take_notification(),
+        ))
     }
 
     #[handle]
     #[require_admin]
     fn reduce_reserves(amount: Uint128, to: Option<HumanAddr>) -> StdResult<HandleResponse> {
+        checks::assert_no_flash_loan(&deps.storage)?;
+
         let underlying_asset = Contracts::load_underlying(deps)?;
 
         let balance = snip20::balance_query(
@@ -453,7 +747,7 @@ pub trait Market {
             )));
         }
 
-        let mut latest = accrue_interest(deps, env.block.height, balance)?;
+        let mut latest = accrue_interest(deps, &env.block, balance)?;
 
         // Load after accrue_interest(), because it's updated inside.
         let reserve = latest.total_reserves(&deps.storage)?;
@@ -469,24 +763,519 @@ pub trait Market {
         let reserve = (reserve - amount_256)?;
         Global::save_interest_reserve(&mut deps.storage, &reserve)?;
 
+        Ok(with_notification(
+            HandleResponse {
+                messages: vec![snip20::transfer_msg(
+                    to.unwrap_or(env.message.sender),
+                    amount,
+                    None,
+                    None,
+                    BLOCK_SIZE,
+                    underlying_asset.code_hash,
+                    underlying_asset.address,
+                )?],
+                log: vec![
+                    log("action", "reduce_reserves"),
+                    log("new_reserve", reserve),
+                ],
+                data: None,
+            },
+            latest.take_notification(),
+        ))
+    }
+
+    #[handle]
+    #[require_admin]
+    fn set_reserve_policy(policy: Option<ReservePolicy<HumanAddr>>) -> StdResult<HandleResponse> {
+        if let Some(policy) = &policy {
+            policy.validate()?;
+        }
+
+        Constants::save_reserve_policy(deps, policy)?;
+
         Ok(HandleResponse {
-            messages: vec![snip20::transfer_msg(
-                to.unwrap_or(env.message.sender),
-                amount,
+            messages: vec![],
+            log: vec![log("action", "set_reserve_policy")],
+            data: None,
+        })
+    }
+
+    #[handle]
+    fn sweep_reserves(min_buyback_output: Option<Uint256>) -> StdResult<HandleResponse> {
+        checks::assert_no_flash_loan(&deps.storage)?;
+
+        let policy = Constants::load_reserve_policy(deps)?.ok_or_else(||
+            StdError::generic_err("Reserve sweeping is not enabled in this market.")
+        )?;
+
+        // Whoever sets the minimum output of the buyback must be trusted.
+        if min_buyback_output.is_some() && policy.keeper.as_ref() != Some(&env.message.sender) {
+            assert_admin(deps, &env)?;
+        }
+
+        let underlying_asset = Contracts::load_underlying(deps)?;
+
+        let balance = snip20::balance_query(
+            &deps.querier,
+            env.contract.address.clone(),
+            Constants::load_vk(&deps.storage)?,
+            BLOCK_SIZE,
+            underlying_asset.code_hash.clone(),
+            underlying_asset.address.clone(),
+        )?
+        .amount;
+
+        let mut latest = accrue_interest(deps, &env.block, balance)?;
+        let reserve = latest.total_reserves(&deps.storage)?;
+
+        // Reserves that are lent out can't be swept until they are repaid.
+        let sweepable = (reserve - policy.min_reserves).unwrap_or_default();
+        let sweepable = if sweepable > Uint256::from(balance) {
+            Uint256::from(balance)
+        } else {
+            sweepable
+        };
+
+        let to_treasury = sweepable.decimal_mul(policy.treasury_share)?;
+        let bought_back = if min_buyback_output.is_some() {
+            sweepable.decimal_mul(policy.buyback_share)?
+        } else {
+            Uint256::zero()
+        };
+        let swept = (to_treasury + bought_back)?;
+
+        if swept.is_zero() {
+            return Err(StdError::generic_err(format!(
+                "Nothing to sweep. Reserves: {}, Minimum: {}",
+                reserve,
+                policy.min_reserves
+            )));
+        }
+
+        let remaining_reserves = (reserve - swept)?;
+        Global::save_interest_reserve(&mut deps.storage, &remaining_reserves)?;
+
+        let mut messages = vec![];
+
+        if !to_treasury.is_zero() {
+            messages.push(snip20::transfer_msg(
+                policy.treasury,
+                to_treasury.clamp_u128()?.into(),
                 None,
                 None,
                 BLOCK_SIZE,
-                underlying_asset.code_hash,
-                underlying_asset.address,
-            )?],
+                underlying_asset.code_hash.clone(),
+                underlying_asset.address.clone(),
+            )?);
+        }
+
+        if !bought_back.is_zero() {
+            // Only non-zero if a minimum output was given.
+            let min_buyback_output = min_buyback_output.unwrap_or_default();
+
+            let (recipient, msg) = swap_destination(
+                query_swap_router(&deps.querier, Contracts::load_overseer(deps)?)?,
+                policy.buyback_route,
+                policy.rewards_pool,
+                Some(min_buyback_output.clamp_u128()?.into()),
+            )?;
+
+            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: underlying_asset.address,
+                callback_code_hash: underlying_asset.code_hash,
+                msg: to_binary(&snip20_msg::HandleMsg::Send {
+                    recipient: recipient.address,
+                    recipient_code_hash: Some(recipient.code_hash),
+                    amount: bought_back.clamp_u128()?.into(),
+                    msg: Some(msg),
+                    memo: None,
+                    padding: None,
+                })?,
+                send: vec![],
+            }));
+        }
+
+        Ok(with_notification(
+            HandleResponse {
+                messages,
+                log: vec![
+                    log("action", "sweep_reserves"),
+                    log("to_treasury", to_treasury),
+                    log("bought_back", bought_back),
+                    log("new_reserve", remaining_reserves),
+                ],
+                data: Some(to_binary(&SweepReservesResult {
+                    to_treasury,
+                    bought_back,
+                    remaining_reserves,
+                })?),
+            },
+            latest.take_notification(),
+        ))
+    }
+
+    #[handle]
+    fn realize_bad_debt(borrower: Binary) -> StdResult<HandleResponse> {
+        checks::assert_no_flash_loan(&deps.storage)?;
+
+        let underlying_asset = Contracts::load_underlying(deps)?;
+
+        let balance = snip20::balance_query(
+            &deps.querier,
+            env.contract.address.clone(),
+            Constants::load_vk(&deps.storage)?,
+            BLOCK_SIZE,
+            underlying_asset.code_hash,
+            underlying_asset.address,
+        )?
+        .amount;
+
+        let mut latest = accrue_interest(deps, &env.block, balance)?;
+
+        let borrower = Account::from_id(&deps.storage, &borrower)?;
+        let borrower_address = borrower.address(&deps.api)?;
+
+        checks::assert_no_collateral(
+            deps,
+            &env.contract.address,
+            borrower_address.clone(),
+            borrower.get_balance(&deps.storage)?,
+            env.block.height,
+            Some(env.block.time),
+        )?;
+
+        let rewards = rewards_checkpoint(deps, &borrower)?;
+
+        let now = accrual_point(&deps.storage, &env.block)?;
+        let borrow_index = latest.borrow_index(&deps.storage)?;
+        let mut snapshot = borrower.get_borrow_snapshot(&deps.storage)?;
+
+        let variable = snapshot.current_balance(borrow_index)?;
+        let stable = snapshot.current_stable_balance(now)?;
+        let amount = (variable + stable)?;
+
+        if amount.is_zero() {
+            return Err(StdError::generic_err("Borrower has no outstanding debt."));
+        }
+
+        snapshot.subtract_balance(borrow_index, variable)?;
+        TotalBorrows::decrease(&mut deps.storage, variable)?;
+
+        if !stable.is_zero() {
+            let mut debt = latest.stable_debt(&deps.storage)?;
+            snapshot.subtract_stable_balance(&mut debt, now, stable)?;
+
+            Global::save_stable_debt(&mut deps.storage, &debt)?;
+        }
+
+        borrower.save_borrow_snapshot(&mut deps.storage, snapshot)?;
+
+        // Cover as much as possible from the reserves. Whatever is left is
+        // socialized since removing it from the borrows lowers the exchange rate.
+        let reserve = latest.total_reserves(&deps.storage)?;
+        let covered_by_reserves = if amount > reserve { reserve } else { amount };
+        let socialized = (amount - covered_by_reserves)?;
+
+        Global::save_interest_reserve(&mut deps.storage, &(reserve - covered_by_reserves)?)?;
+
+        let mut bad_debt = Global::load_bad_debt(&deps.storage)?;
+        bad_debt.total = (bad_debt.total + amount)?;
+        bad_debt.covered_by_reserves = (bad_debt.covered_by_reserves + covered_by_reserves)?;
+        bad_debt.socialized = (bad_debt.socialized + socialized)?;
+
+        Global::save_bad_debt(&mut deps.storage, &bad_debt)?;
+
+        borrower.push_history(
+            &mut deps.storage,
+            &env.block,
+            HistoryEvent::BadDebt {
+                amount,
+                covered_by_reserves,
+            },
+        )?;
+
+        let mut messages = vec![update_isolated_debt_msg(
+            Contracts::load_overseer(deps)?,
+            borrower_address,
+            amount,
+            true,
+        )?];
+        messages.extend(rewards_update(deps, &borrower, rewards)?);
+
+        Ok(with_notification(
+            HandleResponse {
+                messages,
+                log: vec![
+                    log("action", "realize_bad_debt"),
+                    log("amount", amount),
+                    log("covered_by_reserves", covered_by_reserves),
+                    log("socialized", socialized),
+                ],
+                data: None,
+            },
+            latest.take_notification(),
+        ))
+    }
+
+    #[handle]
+    fn flash_loan(amount: Uint256, callback: Callback<HumanAddr>) -> StdResult<HandleResponse> {
+        checks::assert_not_paused(&deps.storage, Action::Borrow)?;
+        checks::assert_no_flash_loan(&deps.storage)?;
+
+        let config = Constants::load_config(&deps.storage)?;
+        let fee_rate = config.flash_loan_fee.ok_or_else(||
+            StdError::generic_err("Flash loans are not enabled in this market.")
+        )?;
+
+        if amount.is_zero() {
+            return Err(StdError::generic_err("Flash loan amount cannot be zero."));
+        }
+
+        let underlying_asset = Contracts::load_underlying(deps)?;
+
+        let balance = snip20::balance_query(
+            &deps.querier,
+            env.contract.address.clone(),
+            Constants::load_vk(&deps.storage)?,
+            BLOCK_SIZE,
+            underlying_asset.code_hash.clone(),
+            underlying_asset.address.clone(),
+        )?
+        .amount;
+
+        let mut latest = accrue_interest(deps, &env.block, balance)?;
+
+        checks::assert_can_withdraw(balance.into(), amount)?;
+
+        let fee = amount.decimal_mul(fee_rate)?;
+
+        Global::save_flash_loan(&mut deps.storage, &FlashLoan {
+            expected_balance: (Uint256::from(balance) + fee)?,
+            fee
+        })?;
+
+        Ok(with_notification(
+            HandleResponse {
+                messages: vec![
+                    snip20::transfer_msg(
+                        callback.contract.address.clone(),
+                        amount.low_u128().into(),
+                        None,
+                        None,
+                        BLOCK_SIZE,
+                        underlying_asset.code_hash,
+                        underlying_asset.address,
+                    )?,
+                    CosmosMsg::Wasm(WasmMsg::Execute {
+                        send: vec![],
+                        contract_addr: callback.contract.address,
+                        callback_code_hash: callback.contract.code_hash,
+                        msg: callback.msg,
+                    }),
+                    CosmosMsg::Wasm(WasmMsg::Execute {
+                        send: vec![],
+                        contract_addr: env.contract.address,
+                        callback_code_hash: env.contract_code_hash,
+                        msg: to_binary(&HandleMsg::FinishFlashLoan {})?,
+                    }),
+                ],
+                log: vec![
+                    log("action", "flash_loan"),
+                    log("amount", amount),
+                    log("fee", fee),
+                ],
+                data: None,
+            },
+            latest.take_notification(),
+        ))
+    }
+
+    #[handle]
+    fn finish_flash_loan() -> StdResult<HandleResponse> {
+        if env.message.sender != env.contract.address {
+            return Err(StdError::unauthorized());
+        }
+
+        let loan = Global::load_flash_loan(&deps.storage)?.ok_or_else(||
+            StdError::generic_err("No flash loan is in progress.")
+        )?;
+        Global::remove_flash_loan(&mut deps.storage);
+
+        let underlying_asset = Contracts::load_underlying(deps)?;
+
+        let balance = snip20::balance_query(
+            &deps.querier,
+            env.contract.address,
+            Constants::load_vk(&deps.storage)?,
+            BLOCK_SIZE,
+            underlying_asset.code_hash,
+            underlying_asset.address,
+        )?
+        .amount;
+
+        let balance = Uint256::from(balance);
+
+        if balance < loan.expected_balance {
+            return Err(StdError::generic_err(format!(
+                "Flash loan was not paid back in full. Expected balance: {}, Actual: {}",
+                loan.expected_balance, balance
+            )));
+        }
+
+        // The rest of the fee is left to the suppliers.
+        let config = Constants::load_config(&deps.storage)?;
+        let reserve_share = loan.fee.decimal_mul(config.reserve_factor)?;
+
+        let reserve = (Global::load_interest_reserve(&deps.storage)? + reserve_share)?;
+        Global::save_interest_reserve(&mut deps.storage, &reserve)?;
+
+        Ok(HandleResponse {
+            messages: vec![],
             log: vec![
-                log("action", "reduce_reserves"),
+                log("action", "finish_flash_loan"),
+                log("fee", loan.fee),
                 log("new_reserve", reserve),
             ],
             data: None,
         })
     }
 
+    #[handle]
+    fn redeem_to(
+        owner: HumanAddr,
+        burn_amount: Uint256,
+        recipient: ContractLink<HumanAddr>,
+        msg: Binary
+    ) -> StdResult<HandleResponse> {
+        if Contracts::load_overseer(deps)?.address != env.message.sender {
+            return Err(StdError::unauthorized());
+        }
+
+        checks::assert_not_paused(&deps.storage, Action::Redeem)?;
+
+        token::redeem(
+            deps,
+            env,
+            owner,
+            burn_amount,
+            Uint256::zero(),
+            RedeemTo::Contract {
+                contract: recipient,
+                msg
+            }
+        )
+    }
+
+    #[handle]
+    fn apply_swap_output(
+        balance_before: Uint256,
+        min_amount: Uint256,
+        action: SwapOutputAction
+    ) -> StdResult<HandleResponse> {
+        if Contracts::load_overseer(deps)?.address != env.message.sender {
+            return Err(StdError::unauthorized());
+        }
+
+        checks::assert_no_flash_loan(&deps.storage)?;
+
+        let underlying_asset = Contracts::load_underlying(deps)?;
+
+        let balance = snip20::balance_query(
+            &deps.querier,
+            env.contract.address.clone(),
+            Constants::load_vk(&deps.storage)?,
+            BLOCK_SIZE,
+            underlying_asset.code_hash,
+            underlying_asset.address,
+        )?
+        .amount;
+
+        let amount = (Uint256::from(balance) - balance_before).unwrap_or_default();
+
+        if amount.is_zero() || amount < min_amount {
+            return Err(StdError::generic_err(format!(
+                "Swap output is less than the minimum. Output: {}, Minimum: {}",
+                amount, min_amount
+            )));
+        }
+
+        // Same as in receive(), the interest must be accrued with the balance prior to the swap.
+        let mut interest = accrue_interest(
+            deps,
+            &env.block,
+            balance_before.clamp_u128()?.into(),
+        )?;
+        let notification = interest.take_notification();
+
+        let response = match action {
+            SwapOutputAction::Repay { borrower } => {
+                checks::assert_not_paused(&deps.storage, Action::Repay)?;
+
+                repay(
+                    deps,
+                    &env.block,
+                    interest,
+                    Account::of(deps, &borrower)?,
+                    borrower,
+                    amount,
+                )
+            }
+            SwapOutputAction::Deposit { owner } => {
+                checks::assert_not_paused(&deps.storage, Action::Deposit)?;
+                checks::assert_deposit_allowed(deps, env.contract.address)?;
+
+                token::deposit(deps, interest, balance_before, owner, amount)
+            }
+        }?;
+
+        Ok(with_notification(response, notification))
+    }
+
+    #[handle]
+    fn pause(actions: PausedActions) -> StdResult<HandleResponse> {
+        if Contracts::load_overseer(deps)?.address != env.message.sender {
+            assert_admin(deps, &env)?;
+        }
+
+        let mut paused = Constants::load_paused(&deps.storage)?;
+        paused.merge(&actions);
+
+        Constants::save_paused(&mut deps.storage, &paused)?;
+
+        Ok(HandleResponse {
+            messages: vec![],
+            log: vec![log("action", "pause")],
+            data: None,
+        })
+    }
+
+    #[handle]
+    #[require_admin]
+    fn set_paused(actions: PausedActions) -> StdResult<HandleResponse> {
+        Constants::save_paused(&mut deps.storage, &actions)?;
+
+        Ok(HandleResponse {
+            messages: vec![],
+            log: vec![log("action", "set_paused")],
+            data: None,
+        })
+    }
+
+    #[handle]
+    fn enable_rewards() -> StdResult<HandleResponse> {
+        if Contracts::load_overseer(deps)?.address != env.message.sender {
+            return Err(StdError::unauthorized());
+        }
+
+        Constants::enable_rewards(&mut deps.storage)?;
+
+        Ok(HandleResponse {
+            messages: vec![],
+            log: vec![log("action", "enable_rewards")],
+            data: None,
+        })
+    }
+
     #[handle]
     fn create_viewing_key(entropy: String, padding: Option<String>) -> StdResult<HandleResponse> {
         AuthImpl.create_viewing_key(entropy, padding, deps, env)
@@ -500,10 +1289,13 @@ pub trait Market {
     #[query]
     fn simulate_liquidation(
         block: u64,
+        time: Option<u64>,
         borrower: Binary,
         collateral: HumanAddr,
         amount: Uint256
     ) -> StdResult<SimulateLiquidationResult> {
+        checks::assert_no_flash_loan(&deps.storage)?;
+
         let underlying_asset = Contracts::load_underlying(deps)?;
 
         let balance = snip20::balance_query(
@@ -516,7 +1308,9 @@ pub trait Market {
         )?
         .amount;
 
-        let interest = accrued_interest_at(deps, Some(block), balance)?;
+        let point = query_accrual_point(&deps.storage, Some(block), time)?;
+        let interest = accrued_interest_at(deps, point, balance)?;
+        let point = point.unwrap_or(Global::load_accrual_block_number(&deps.storage)?);
 
         let borrower = Account::from_id(&deps.storage, &borrower)?;
         let snapshot = borrower.get_borrow_snapshot(&deps.storage)?;
@@ -528,8 +1322,12 @@ pub trait Market {
             deps,
             overseer.clone(),
             borrower_address.clone(),
-            snapshot.current_balance(interest.borrow_index)?,
+            (
+                snapshot.current_balance(interest.borrow_index)? +
+                snapshot.current_stable_balance(point)?
+            )?,
             block,
+            time,
             amount,
         )?;
 
@@ -577,6 +1375,21 @@ pub trait Market {
         borrower.can_subtract(&deps.storage, amount)
     }
 
+    #[query]
+    fn paused() -> StdResult<PausedActions> {
+        Constants::load_paused(&deps.storage)
+    }
+
+    #[query]
+    fn bad_debt() -> StdResult<BadDebt> {
+        Global::load_bad_debt(&deps.storage)
+    }
+
+    #[query]
+    fn reserve_policy() -> StdResult<Option<ReservePolicy<HumanAddr>>> {
+        Constants::load_reserve_policy(deps)
+    }
+
     #[query]
     fn token_info() -> StdResult<snip20_msg::QueryAnswer> {
         let underlying = Contracts::load_underlying(deps)?;
@@ -604,7 +1417,11 @@ pub trait Market {
     }
 
     #[query]
-    fn balance_underlying(method: MarketAuth, block: Option<u64>) -> StdResult<Uint128> {
+    fn balance_underlying(
+        method: MarketAuth,
+        block: Option<u64>,
+        time: Option<u64>
+    ) -> StdResult<Uint128> {
         let account = Account::authenticate(
             deps,
             method,
@@ -612,14 +1429,16 @@ pub trait Market {
             Contracts::load_self_ref,
         )?;
 
-        let exchange_rate = self.exchange_rate(block, deps)?;
+        let exchange_rate = self.exchange_rate(block, time, deps)?;
         let balance = account.get_balance(&deps.storage)?;
 
         Ok(balance.decimal_mul(exchange_rate)?.low_u128().into())
     }
 
     #[query]
-    fn state(block: Option<u64>) -> StdResult<State> {
+    fn state(block: Option<u64>, time: Option<u64>) -> StdResult<State> {
+        checks::assert_no_flash_loan(&deps.storage)?;
+
         let underlying_asset = Contracts::load_underlying(deps)?;
 
         let balance = snip20::balance_query(
@@ -632,11 +1451,14 @@ pub trait Market {
         )?
         .amount;
 
-        let interest = accrued_interest_at(deps, block, balance)?;
+        let point = query_accrual_point(&deps.storage, block, time)?;
+        let interest = accrued_interest_at(deps, point, balance)?;
 
         Ok(State {
             underlying_balance: balance,
             total_borrows: interest.total_borrows,
+            total_stable_borrows: interest.stable_debt.total,
+            average_stable_rate: interest.stable_debt.average_rate()?,
             total_reserves: interest.total_reserves,
             borrow_index: interest.borrow_index,
             total_supply: TotalSupply::load(&deps.storage)?,
@@ -656,7 +1478,9 @@ pub trait Market {
     }
 
     #[query]
-    fn borrow_rate(block: Option<u64>) -> StdResult<Decimal256> {
+    fn borrow_rate(block: Option<u64>, time: Option<u64>) -> StdResult<Decimal256> {
+        checks::assert_no_flash_loan(&deps.storage)?;
+
         let underlying_asset = Contracts::load_underlying(deps)?;
 
         let balance = snip20::balance_query(
@@ -669,19 +1493,50 @@ pub trait Market {
         )?
         .amount;
 
-        let interest = accrued_interest_at(deps, block, balance)?;
+        let point = query_accrual_point(&deps.storage, block, time)?;
+        let interest = accrued_interest_at(deps, point, balance)?;
 
         query_borrow_rate(
             &deps.querier,
             Contracts::load_interest_model(deps)?,
+            Contracts::load_self_ref(deps)?.address,
             Decimal256::from_uint256(balance)?,
-            Decimal256::from_uint256(interest.total_borrows)?,
+            Decimal256::from_uint256((interest.total_borrows + interest.stable_debt.total)?)?,
             Decimal256::from_uint256(interest.total_reserves)?,
         )
     }
 
     #[query]
-    fn supply_rate(block: Option<u64>) -> StdResult<Decimal256> {
+    fn stable_borrow_rate(block: Option<u64>, time: Option<u64>) -> StdResult<Decimal256> {
+        checks::assert_no_flash_loan(&deps.storage)?;
+
+        let underlying_asset = Contracts::load_underlying(deps)?;
+
+        let balance = snip20::balance_query(
+            &deps.querier,
+            Contracts::load_self_ref(deps)?.address,
+            Constants::load_vk(&deps.storage)?,
+            BLOCK_SIZE,
+            underlying_asset.code_hash.clone(),
+            underlying_asset.address.clone(),
+        )?
+        .amount;
+
+        let point = query_accrual_point(&deps.storage, block, time)?;
+        let interest = accrued_interest_at(deps, point, balance)?;
+
+        current_stable_rate(
+            deps,
+            balance.into(),
+            (interest.total_borrows + interest.stable_debt.total)?,
+            interest.total_reserves,
+        )
+    }
+
+    #[query]
+    fn supply_rate(block: Option<u64>, time: Option<u64>) -> StdResult<Decimal256> {
+        checks::assert_no_flash_loan(&deps.storage)?;
+
         let underlying_asset = Contracts::load_underlying(deps)?;
 
         let balance = snip20::balance_query(
@@ -694,20 +1549,24 @@ pub trait Market {
         )?
         .amount;
 
-        let interest = accrued_interest_at(deps, block, balance)?;
+        let point = query_accrual_point(&deps.storage, block, time)?;
+        let interest = accrued_interest_at(deps, point, balance)?;
 
         query_supply_rate(
             &deps.querier,
             Contracts::load_interest_model(deps)?,
+            Contracts::load_self_ref(deps)?.address,
             Decimal256::from_uint256(balance)?,
-            Decimal256::from_uint256(interest.total_borrows)?,
+            Decimal256::from_uint256((interest.total_borrows + interest.stable_debt.total)?)?,
             Decimal256::from_uint256(interest.total_reserves)?,
             Constants::load_config(&deps.storage)?.reserve_factor,
         )
     }
 
     #[query]
-    fn exchange_rate(block: Option<u64>) -> StdResult<Decimal256> {
+    fn exchange_rate(block: Option<u64>, time: Option<u64>) -> StdResult<Decimal256> {
+        checks::assert_no_flash_loan(&deps.storage)?;
+
         let underlying_asset = Contracts::load_underlying(deps)?;
 
         let balance = snip20::balance_query(
@@ -720,18 +1579,26 @@ pub trait Market {
         )?
         .amount;
 
-        let interest = accrued_interest_at(deps, block, balance)?;
+        let point = query_accrual_point(&deps.storage, block, time)?;
+        let interest = accrued_interest_at(deps, point, balance)?;
 
         calc_exchange_rate(
             deps,
             balance.into(),
             interest.total_borrows,
+            interest.stable_debt.total,
             interest.total_reserves,
         )
     }
 
     #[query]
-    fn account(method: MarketAuth, block: Option<u64>) -> StdResult<AccountInfo> {
+    fn account(
+        method: MarketAuth,
+        block: Option<u64>,
+        time: Option<u64>
+    ) -> StdResult<AccountInfo> {
+        checks::assert_no_flash_loan(&deps.storage)?;
+
         let account = Account::authenticate(
             deps,
             method,
@@ -750,17 +1617,21 @@ pub trait Market {
         )?
         .amount;
 
-        let interest = accrued_interest_at(deps, block, balance)?;
+        let point = query_accrual_point(&deps.storage, block, time)?;
+        let interest = accrued_interest_at(deps, point, balance)?;
+        let point = point.unwrap_or(Global::load_accrual_block_number(&deps.storage)?);
 
         let snapshot = account.get_borrow_snapshot(&deps.storage)?;
 
         Ok(AccountInfo {
             sl_token_balance: account.get_balance(&deps.storage)?,
             borrow_balance: snapshot.current_balance(interest.borrow_index)?,
+            stable_borrow_balance: snapshot.current_stable_balance(point)?,
             exchange_rate: calc_exchange_rate(
                 deps,
                 balance.into(),
                 interest.total_borrows,
+                interest.stable_debt.total,
                 interest.total_reserves,
             )?,
         })
@@ -779,7 +1650,40 @@ pub trait Market {
     }
 
     #[query]
-    fn borrowers(block: u64, pagination: Pagination) -> StdResult<BorrowersResponse> {
+    fn borrower_addresses(
+        key: MasterKey,
+        pagination: Pagination,
+    ) -> StdResult<BorrowerAddressesResponse> {
+        MasterKey::check(&deps.storage, &key)?;
+
+        let (total, borrowers) = load_borrowers(deps, pagination)?;
+
+        Ok(BorrowerAddressesResponse {
+            entries: borrowers.into_iter().map(|x| x.address).collect(),
+            total,
+        })
+    }
+
+    #[query]
+    fn history(method: MarketAuth, pagination: Pagination) -> StdResult<HistoryResponse> {
+        let account = Account::authenticate(
+            deps,
+            method,
+            MarketPermissions::AccountInfo,
+            Contracts::load_self_ref,
+        )?;
+
+        account.list_history(&deps.storage, pagination)
+    }
+
+    #[query]
+    fn borrowers(
+        block: u64,
+        time: Option<u64>,
+        pagination: Pagination
+    ) -> StdResult<BorrowersResponse> {
+        checks::assert_no_flash_loan(&deps.storage)?;
+
         let (total, borrowers) = load_borrowers(deps, pagination)?;
         let mut result = Vec::with_capacity(borrowers.len());
 
@@ -797,13 +1701,16 @@ pub trait Market {
         )?
         .amount;
 
-        let interest = accrued_interest_at(deps, Some(block), balance)?;
+        let point = query_accrual_point(&deps.storage, Some(block), time)?;
+        let interest = accrued_interest_at(deps, point, balance)?;
+        let point = point.unwrap_or(Global::load_accrual_block_number(&deps.storage)?);
 
         for record in borrowers {
             result.push(Borrower {
                 id: record.id,
                 principal_balance: record.snapshot.info.principal,
                 actual_balance: record.snapshot.current_balance(interest.borrow_index)?,
+                stable_balance: record.snapshot.current_stable_balance(point)?,
                 liquidity: query_account_liquidity(
                     &deps.querier,
                     overseer.clone(),
@@ -811,6 +1718,7 @@ pub trait Market {
                     record.address.clone(),
                     None,
                     Some(block),
+                    time,
                     Uint256::zero(),
                     Uint256::zero(),
                 )?,
@@ -830,12 +1738,15 @@ pub trait Market {
     }
 }
 
+/// Returns the rewards updates for both accounts.
 fn do_transfer<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
     recipient: &HumanAddr,
     amount: Uint256
-) -> StdResult<()> {
+) -> StdResult<Vec<CosmosMsg>> {
+    checks::assert_not_paused(&deps.storage, Action::Transfer)?;
+
     let sender = Account::of(deps, &env.message.sender)?;
     let recipient = Account::of(deps, &recipient)?;
 
@@ -846,6 +1757,7 @@ fn do_transfer<S: Storage, A: Api, Q: Querier>(
         env.message.sender,
         env.contract.address,
         env.block.height,
+        Some(env.block.time),
         amount,
     )?;
 
@@ -855,18 +1767,28 @@ fn do_transfer<S: Storage, A: Api, Q: Querier>(
         ));
     }
 
+    let sender_rewards = rewards_checkpoint(deps, &sender)?;
+    let recipient_rewards = rewards_checkpoint(deps, &recipient)?;
+
     sender.subtract_balance(&mut deps.storage, amount)?;
-    
-    recipient.add_balance(&mut deps.storage, amount)
+    recipient.add_balance(&mut deps.storage, amount)?;
+
+    Ok(rewards_update(deps, &sender, sender_rewards)?
+        .into_iter()
+        .chain(rewards_update(deps, &recipient, recipient_rewards)?)
+        .collect())
 }
 
 fn repay<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
+    block: &BlockInfo,
     mut interest: LatestInterest,
     borrower: Account,
     sender: HumanAddr,
     amount: Uint256,
 ) -> StdResult<HandleResponse> {
+    let rewards = rewards_checkpoint(deps, &borrower)?;
+
     let mut snapshot = borrower.get_borrow_snapshot(&deps.storage)?;
     let remainder = snapshot.subtract_balance(interest.borrow_index(&deps.storage)?, amount)?;
     borrower.save_borrow_snapshot(&mut deps.storage, snapshot)?;
@@ -874,6 +1796,66 @@ fn repay<S: Storage, A: Api, Q: Querier>(
     let amount = (amount.0 - remainder.0).into();
     TotalBorrows::decrease(&mut deps.storage, amount)?;
 
+    borrower.push_history(
+        &mut deps.storage,
+        block,
+        HistoryEvent::Repay { amount, stable: false },
+    )?;
+
+    let mut response = refund_remainder(deps, sender, remainder)?;
+    response.messages.push(update_isolated_debt_msg(
+        Contracts::load_overseer(deps)?,
+        borrower.address(&deps.api)?,
+        amount,
+        true,
+    )?);
+    response.messages.extend(rewards_update(deps, &borrower, rewards)?);
+
+    Ok(response)
+}
+
+fn repay_stable<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    block: &BlockInfo,
+    mut interest: LatestInterest,
+    borrower: Account,
+    sender: HumanAddr,
+    amount: Uint256,
+) -> StdResult<HandleResponse> {
+    let rewards = rewards_checkpoint(deps, &borrower)?;
+    let mut debt = interest.stable_debt(&deps.storage)?;
+
+    let mut snapshot = borrower.get_borrow_snapshot(&deps.storage)?;
+    let now = accrual_point(&deps.storage, block)?;
+    let remainder = snapshot.subtract_stable_balance(&mut debt, now, amount)?;
+    borrower.save_borrow_snapshot(&mut deps.storage, snapshot)?;
+
+    Global::save_stable_debt(&mut deps.storage, &debt)?;
+
+    let repaid = (amount - remainder)?;
+    borrower.push_history(
+        &mut deps.storage,
+        block,
+        HistoryEvent::Repay { amount: repaid, stable: true },
+    )?;
+
+    let mut response = refund_remainder(deps, sender, remainder)?;
+    response.messages.push(update_isolated_debt_msg(
+        Contracts::load_overseer(deps)?,
+        borrower.address(&deps.api)?,
+        repaid,
+        true,
+    )?);
+    response.messages.extend(rewards_update(deps, &borrower, rewards)?);
+
+    Ok(response)
+}
+
+fn refund_remainder<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    sender: HumanAddr,
+    remainder: Uint256,
+) -> StdResult<HandleResponse> {
     if remainder > Uint256::zero() {
         let underlying = Contracts::load_underlying(deps)?;
 
@@ -913,29 +1895,65 @@ fn liquidate<S: Storage, A: Api, Q: Querier>(
         ));
     }
 
+    let borrower_rewards = rewards_checkpoint(deps, &borrower)?;
+
     let borrow_index = interest.borrow_index(&deps.storage)?;
     let mut snapshot = borrower.get_borrow_snapshot(&deps.storage)?;
 
     let borrower_address = borrower.address(&deps.api)?;
+    let now = accrual_point(&deps.storage, &env.block)?;
 
     let overseer = Contracts::load_overseer(deps)?;
     checks::assert_liquidate_allowed(
         deps,
         overseer.clone(),
         borrower_address.clone(),
-        snapshot.current_balance(borrow_index)?,
+        (
+            snapshot.current_balance(borrow_index)? +
+            snapshot.current_stable_balance(now)?
+        )?,
         env.block.height,
+        Some(env.block.time),
         amount,
     )?;
 
-    // Do repay
-    snapshot.subtract_balance(borrow_index, amount)?;
+    // Do repay. Variable rate debt is repaid first.
+    let remainder = snapshot.subtract_balance(borrow_index, amount)?;
+    TotalBorrows::decrease(&mut deps.storage, (amount.0 - remainder.0).into())?;
+
+    if !remainder.is_zero() {
+        let mut debt = interest.stable_debt(&deps.storage)?;
+        snapshot.subtract_stable_balance(&mut debt, now, remainder)?;
+
+        Global::save_stable_debt(&mut deps.storage, &debt)?;
+    }
+
     borrower.save_borrow_snapshot(&mut deps.storage, snapshot)?;
+    borrower.push_history(
+        &mut deps.storage,
+        &env.block,
+        HistoryEvent::Liquidated {
+            amount,
+            collateral: collateral.clone(),
+        },
+    )?;
 
-    TotalBorrows::decrease(&mut deps.storage, amount)?;
+    let isolated_debt_msg = update_isolated_debt_msg(
+        overseer.clone(),
+        borrower_address.clone(),
+        amount,
+        true,
+    )?;
 
     let this_is_collateral = env.contract.address == collateral;
 
+    // Seizing reports the borrower as well when it happens in this market.
+    let borrower_update = if this_is_collateral {
+        None
+    } else {
+        rewards_update(deps, &borrower, borrower_rewards)?
+    };
+
     let seize_amount = query_seize_amount(
         &deps.querier,
         overseer.clone(),
@@ -944,19 +1962,21 @@ fn liquidate<S: Storage, A: Api, Q: Querier>(
         amount,
     )?;
 
-    if this_is_collateral {
+    let mut response = if this_is_collateral {
         seize(
             deps,
+            &env.block,
             interest,
             underlying_balance,
             liquidator,
             borrower,
+            borrower_rewards,
             seize_amount,
-        )
+        )?
     } else {
         let market = query_market(&deps.querier, overseer, collateral)?;
 
-        Ok(HandleResponse {
+        HandleResponse {
             messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
                 send: vec![],
                 contract_addr: market.contract.address,
@@ -969,18 +1989,27 @@ fn liquidate<S: Storage, A: Api, Q: Querier>(
             })],
             log: vec![],
             data: None,
-        })
-    }
+        }
+    };
+
+    response.messages.push(isolated_debt_msg);
+    response.messages.extend(borrower_update);
+
+    Ok(response)
 }
 
 fn seize<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
+    block: &BlockInfo,
     mut latest: LatestInterest,
     underlying_balance: Uint256,
     liquidator: Account,
     borrower: Account,
+    borrower_rewards: Option<RewardBalances>,
     amount: Uint256,
 ) -> StdResult<HandleResponse> {
+    let liquidator_rewards = rewards_checkpoint(deps, &liquidator)?;
+
     if borrower
         .subtract_balance(&mut deps.storage, amount)
         .is_err()
@@ -1001,6 +2030,7 @@ fn seize<S: Storage, A: Api, Q: Querier>(
         deps,
         underlying_balance,
         latest.total_borrows(&deps.storage)?,
+        latest.total_stable_borrows(&deps.storage)?,
         interest_reserve,
     )?;
 
@@ -1012,5 +2042,35 @@ fn seize<S: Storage, A: Api, Q: Querier>(
 
     liquidator.add_balance(&mut deps.storage, liquidator_share)?;
 
-    Ok(HandleResponse::default())
+    borrower.push_history(&mut deps.storage, block, HistoryEvent::Seized { amount })?;
+
+    Ok(HandleResponse {
+        // The borrower goes first since its checkpoint may have been taken before
+        // the repayment, so the totals it reports cover the whole elapsed period.
+        messages: rewards_update(deps, &borrower, borrower_rewards)?
+            .into_iter()
+            .chain(rewards_update(deps, &liquidator, liquidator_rewards)?)
+            .collect(),
+        log: vec![],
+        data: None
+    })
+}
+
+fn current_stable_rate<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    underlying_balance: Uint256,
+    total_borrows: Uint256,
+    total_reserves: Uint256,
+) -> StdResult<Decimal256> {
+    let rate = query_stable_borrow_rate(
+        &deps.querier,
+        Contracts::load_interest_model(deps)?,
+        Contracts::load_self_ref(deps)?.address,
+        Decimal256::from_uint256(underlying_balance)?,
+        Decimal256::from_uint256(total_borrows)?,
+        Decimal256::from_uint256(total_reserves)?,
+    )?;
+
+    // Same as with the variable rate, cap it instead of failing.
+    Ok(rate.min(Decimal256(MAX_BORROW_RATE.into())))
 }
@@ -2,28 +2,37 @@ use lend_shared::{
     fadroma::{
         cosmwasm_std::{
             StdResult, Storage, Api, Querier,
-            Extern, Uint128, StdError
+            Extern, Uint128, StdError, CosmosMsg,
+            HandleResponse, BlockInfo
         },
         Uint256, Decimal256
     },
-    interfaces::interest_model::query_borrow_rate
+    interfaces::{
+        interest_model::{on_accrue_msg, query_accrual_rate},
+        overseer::{update_rewards_msg, RewardBalances}
+    }
 };
 
 use crate::MAX_BORROW_RATE;
 use crate::state::{
-    BorrowSnapshot, Global, TotalBorrows, Contracts, Constants};
+    Account, BorrowSnapshot, Global, TotalBorrows, TotalSupply, Contracts, Constants, StableDebt};
 
 pub struct AccruedInterest {
     pub total_borrows: Uint256,
     pub total_reserves: Uint256,
-    pub borrow_index: Decimal256
+    pub borrow_index: Decimal256,
+    pub stable_debt: StableDebt,
+    /// Message that notifies the interest model about the elapsed period.
+    pub notification: Option<CosmosMsg>
 }
 
 #[derive(Default)]
 pub struct LatestInterest {
     total_borrows: Option<Uint256>,
     total_reserves: Option<Uint256>,
-    borrow_index: Option<Decimal256>
+    borrow_index: Option<Decimal256>,
+    stable_debt: Option<StableDebt>,
+    notification: Option<CosmosMsg>
 }
 
 impl BorrowSnapshot {
@@ -72,13 +81,149 @@ impl BorrowSnapshot {
 
         Ok(remainder)
     }
+
+    pub fn current_stable_balance(&self, block: u64) -> StdResult<Uint256> {
+        if self.stable.principal.is_zero() {
+            return Ok(Uint256::zero());
+        }
+
+        let blocks = block.saturating_sub(self.stable.accrual_block);
+        let interest = self.stable_interest_per_block()?
+            .decimal_mul(Decimal256::from_uint256(blocks)?)?;
+
+        self.stable.principal + interest
+    }
+
+    #[inline]
+    pub fn stable_interest_per_block(&self) -> StdResult<Uint256> {
+        self.stable.principal.decimal_mul(self.stable.rate)
+    }
+
+    /// Adds the `amount` to the stable balance. The resulting rate is the average
+    /// of the current stable rate and the `rate` of the new borrow, weighted by amount.
+    pub fn add_stable_balance(
+        &mut self,
+        debt: &mut StableDebt,
+        block: u64,
+        rate: Decimal256,
+        amount: Uint256
+    ) -> StdResult<()> {
+        let balance = self.settle_stable_balance(debt, block)?;
+        let new_balance = (balance + amount)?;
+
+        let interest_per_block = (
+            balance.decimal_mul(self.stable.rate)? +
+            amount.decimal_mul(rate)?
+        )?;
+
+        self.stable.principal = new_balance;
+        self.stable.rate = Decimal256::from_ratio(interest_per_block.0, new_balance.0)?;
+
+        debt.add(self.stable.principal, self.stable_interest_per_block()?)
+    }
+
+    /// Returns the remainder after subtracting the `amount` from the current stable balance.
+    pub fn subtract_stable_balance(
+        &mut self,
+        debt: &mut StableDebt,
+        block: u64,
+        amount: Uint256
+    ) -> StdResult<Uint256> {
+        let balance = self.settle_stable_balance(debt, block)?;
+
+        let remainder = if amount > balance {
+            self.stable.principal = Uint256::zero();
+            self.stable.rate = Decimal256::zero();
+
+            (amount.0 - balance.0).into()
+        } else {
+            self.stable.principal = (balance.0 - amount.0).into();
+
+            Uint256::zero()
+        };
+
+        debt.add(self.stable.principal, self.stable_interest_per_block()?)?;
+
+        Ok(remainder)
+    }
+
+    /// Locks the current stable balance at the new `rate`.
+    pub fn rebalance_stable_rate(
+        &mut self,
+        debt: &mut StableDebt,
+        block: u64,
+        rate: Decimal256
+    ) -> StdResult<()> {
+        self.settle_stable_balance(debt, block)?;
+        self.stable.rate = rate;
+
+        debt.add(self.stable.principal, self.stable_interest_per_block()?)
+    }
+
+    /// Adds the interest accrued so far to the stable principal and removes
+    /// this borrow's contribution from the market totals. Returns the current balance.
+    fn settle_stable_balance(
+        &mut self,
+        debt: &mut StableDebt,
+        block: u64
+    ) -> StdResult<Uint256> {
+        let balance = self.current_stable_balance(block)?;
+        debt.remove(balance, self.stable_interest_per_block()?);
+
+        self.stable.principal = balance;
+        self.stable.accrual_block = block;
+
+        Ok(balance)
+    }
+}
+
+impl StableDebt {
+    pub fn average_rate(&self) -> StdResult<Decimal256> {
+        if self.total.is_zero() {
+            return Ok(Decimal256::zero());
+        }
+
+        Decimal256::from_ratio(self.interest_per_block.0, self.total.0)
+    }
+
+    fn add(&mut self, balance: Uint256, interest_per_block: Uint256) -> StdResult<()> {
+        self.total = (self.total + balance)?;
+        self.interest_per_block = (self.interest_per_block + interest_per_block)?;
+
+        Ok(())
+    }
+
+    fn remove(&mut self, balance: Uint256, interest_per_block: Uint256) {
+        // Rounding can cause the totals to be off by a tiny amount
+        // so we saturate instead of failing.
+        self.total = (self.total - balance).unwrap_or_default();
+        self.interest_per_block = (self.interest_per_block - interest_per_block)
+            .unwrap_or_default();
+    }
+}
+
+/// The block height or time, depending on the accrual mode of the market,
+/// that interest is accrued up to at the given `block`.
+pub fn accrual_point(storage: &impl Storage, block: &BlockInfo) -> StdResult<u64> {
+    Ok(Constants::load_config(storage)?.accrual_mode.current(block))
+}
+
+/// Picks either the `block` or the `time` that queries should accrue interest
+/// up to, depending on the accrual mode of the market.
+pub fn query_accrual_point(
+    storage: &impl Storage,
+    block: Option<u64>,
+    time: Option<u64>
+) -> StdResult<Option<u64>> {
+    Ok(Constants::load_config(storage)?.accrual_mode.select(block, time))
 }
 
 pub fn accrue_interest<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
-    current_block: u64,
+    block: &BlockInfo,
     balance_prior: Uint128
 ) -> StdResult<LatestInterest> {
+    let current_block = accrual_point(&deps.storage, block)?;
     let result = calc_accrued_interest(deps, current_block, balance_prior)?;
 
     if let Some(interest) = result {
@@ -87,6 +232,7 @@ pub fn accrue_interest<S: Storage, A: Api, Q: Querier>(
         Global::save_interest_reserve(&mut deps.storage, &interest.total_reserves)?;
         Global::save_borrow_index(&mut deps.storage, &interest.borrow_index)?;
         Global::save_accrual_block_number(&mut deps.storage, current_block)?;
+        Global::save_stable_debt(&mut deps.storage, &interest.stable_debt)?;
 
         Ok(LatestInterest::from(interest))
     } else {
@@ -94,8 +240,81 @@ pub fn accrue_interest<S: Storage, A: Api, Q: Querier>(
     }
 }
 
-/// Calculate accrued interest for the given block. If no block is supplied,
-/// loads the last cached values from storage.
+/// Appends the interest model notification, if any, to the `response`.
+pub fn with_notification(
+    mut response: HandleResponse,
+    notification: Option<CosmosMsg>
+) -> HandleResponse {
+    if let Some(msg) = notification {
+        response.messages.push(msg);
+    }
+
+    response
+}
+
+/// The balances of the account that the market rewards are currently distributed on.
+/// Must be called after interest has been accrued and before the balances of the account
+/// change so that they can be passed to `rewards_update` afterwards.
+/// Returns `None` if rewards haven't been enabled for this market.
+pub fn rewards_checkpoint<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    account: &Account
+) -> StdResult<Option<RewardBalances>> {
+    if !Constants::rewards_enabled(&deps.storage)? {
+        return Ok(None);
+    }
+
+    reward_balances(deps, account).map(Some)
+}
+
+/// Reports the `previous` balances of the account, as returned by `rewards_checkpoint`,
+/// and the current ones to the overseer which distributes the market rewards based on them.
+/// Returns `None` if rewards haven't been enabled for this market.
+pub fn rewards_update<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    account: &Account,
+    previous: Option<RewardBalances>
+) -> StdResult<Option<CosmosMsg>> {
+    let previous = match previous {
+        Some(previous) => previous,
+        None => return Ok(None)
+    };
+
+    let msg = update_rewards_msg(
+        Contracts::load_overseer(deps)?,
+        account.address(&deps.api)?,
+        previous,
+        reward_balances(deps, account)?
+    )?;
+
+    Ok(Some(msg))
+}
+
+fn reward_balances<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    account: &Account
+) -> StdResult<RewardBalances> {
+    let snapshot = account.get_borrow_snapshot(&deps.storage)?;
+    let borrow_balance = (
+        snapshot.current_balance(Global::load_borrow_index(&deps.storage)?)? +
+        snapshot.current_stable_balance(Global::load_accrual_block_number(&deps.storage)?)?
+    )?;
+
+    let total_borrows = (
+        TotalBorrows::load(&deps.storage)? +
+        Global::load_stable_debt(&deps.storage)?.total
+    )?;
+
+    Ok(RewardBalances {
+        sl_token_balance: account.get_balance(&deps.storage)?,
+        borrow_balance,
+        total_supply: TotalSupply::load(&deps.storage)?,
+        total_borrows
+    })
+}
+
+/// Calculate accrued interest for the given block (or time, when accruing by time).
+/// If no block is supplied, loads the last cached values from storage.
 pub fn accrued_interest_at<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     block: Option<u64>,
@@ -113,7 +332,9 @@ pub fn accrued_interest_at<S: Storage, A: Api, Q: Querier>(
         Ok(AccruedInterest {
             total_borrows: TotalBorrows::load(&deps.storage)?,
             total_reserves: Global::load_interest_reserve(&deps.storage)?,
-            borrow_index: Global::load_borrow_index(&deps.storage)?
+            borrow_index: Global::load_borrow_index(&deps.storage)?,
+            stable_debt: Global::load_stable_debt(&deps.storage)?,
+            notification: None
         })
     }
 }
@@ -135,23 +356,29 @@ fn calc_accrued_interest<S: Storage, A: Api, Q: Querier>(
     let borrows_prior = TotalBorrows::load(&deps.storage)?;
     let reserves_prior = Global::load_interest_reserve(&deps.storage)?;
     let borrow_index_prior = Global::load_borrow_index(&deps.storage)?;
+    let stable_debt_prior = Global::load_stable_debt(&deps.storage)?;
 
-    // Current borrow interest rate
+    // Current borrow interest rate. Utilization includes stable rate borrows.
     let interest_model = Contracts::load_interest_model(deps)?;
-    let borrow_rate = query_borrow_rate(
+    let market_size = Decimal256::from_uint256(balance_prior)?;
+    let num_borrows = Decimal256::from_uint256((borrows_prior + stable_debt_prior.total)?)?;
+    let reserves = Decimal256::from_uint256(reserves_prior)?;
+
+    let accrual_rate = query_accrual_rate(
         &deps.querier,
-        interest_model,
-        Decimal256::from_uint256(balance_prior)?,
-        Decimal256::from_uint256(borrows_prior)?,
-        Decimal256::from_uint256(reserves_prior)?,
+        interest_model.clone(),
+        Contracts::load_self_ref(deps)?.address,
+        market_size,
+        num_borrows,
+        reserves,
     )?;
 
     // MAX_BORROW_RATE should never be reached but if it is, we want to cap it
     // instead of throwing and error because that would freeze all operations
     // on the contract, locking any funds inside it forever.
-    let borrow_rate = borrow_rate.min(Decimal256(MAX_BORROW_RATE.into()));
+    let borrow_rate = accrual_rate.borrow_rate.min(Decimal256(MAX_BORROW_RATE.into()));
 
-    // Calculate the number of blocks elapsed since last accrual
+    // Calculate the number of blocks (or seconds) elapsed since last accrual
     let block_delta = current_block
         .checked_sub(last_accrual_block)
         .ok_or_else(|| StdError::generic_err(format!(
@@ -159,13 +386,36 @@ fn calc_accrued_interest<S: Storage, A: Api, Q: Querier>(
             last_accrual_block
         )))?;
 
+    // Models that adapt over time need to know what the utilization was during this period.
+    let notification = if accrual_rate.notify {
+        Some(on_accrue_msg(
+            interest_model,
+            market_size,
+            num_borrows,
+            reserves,
+            block_delta
+        )?)
+    } else {
+        None
+    };
+
     let simple_interest_factor = (borrow_rate * Decimal256::from_uint256(block_delta)?)?;
     let interest_accumulated = borrows_prior.decimal_mul(simple_interest_factor)?;
 
+    // Stable rate borrows accrue simple interest at their own locked rates.
+    let stable_interest = stable_debt_prior.interest_per_block
+        .decimal_mul(Decimal256::from_uint256(block_delta)?)?;
+    let total_interest = (interest_accumulated + stable_interest)?;
+
     Ok(Some(AccruedInterest {
         total_borrows: (interest_accumulated + borrows_prior)?,
-        total_reserves: (interest_accumulated.decimal_mul(config.reserve_factor)? + reserves_prior)?,
-        borrow_index: ((borrow_index_prior * simple_interest_factor)? + borrow_index_prior)?
+        total_reserves: (total_interest.decimal_mul(config.reserve_factor)? + reserves_prior)?,
+        borrow_index: ((borrow_index_prior * simple_interest_factor)? + borrow_index_prior)?,
+        stable_debt: StableDebt {
+            total: (stable_debt_prior.total + stable_interest)?,
+            interest_per_block: stable_debt_prior.interest_per_block
+        },
+        notification
     }))
 }
 
@@ -205,6 +455,30 @@ impl LatestInterest {
             Ok(result)
         }
     }
+
+    #[inline]
+    pub fn stable_debt(&mut self, storage: &impl Storage) -> StdResult<StableDebt> {
+        if let Some(debt) = &self.stable_debt {
+            Ok(debt.clone())
+        } else {
+            let result = Global::load_stable_debt(storage)?;
+            self.stable_debt = Some(result.clone());
+
+            Ok(result)
+        }
+    }
+
+    /// Takes the interest model notification that needs to be
+    /// included in the response if interest was accrued.
+    #[inline]
+    pub fn take_notification(&mut self) -> Option<CosmosMsg> {
+        self.notification.take()
+    }
+
+    #[inline]
+    pub fn total_stable_borrows(&mut self, storage: &impl Storage) -> StdResult<Uint256> {
+        Ok(self.stable_debt(storage)?.total)
+    }
 }
 
 impl From<AccruedInterest> for LatestInterest {
@@ -212,7 +486,9 @@ impl From<AccruedInterest> for LatestInterest {
         LatestInterest {
             total_borrows: Some(value.total_borrows),
             total_reserves: Some(value.total_reserves),
-            borrow_index: Some(value.borrow_index)
+            borrow_index: Some(value.borrow_index),
+            stable_debt: Some(value.stable_debt),
+            notification: value.notification
         }
     }
 }
@@ -4,7 +4,7 @@ use std::borrow::Borrow;
 use lend_shared::{
     fadroma::{
         cosmwasm_std::{
-            Api, Binary, CanonicalAddr, Extern,
+            Api, Binary, BlockInfo, CanonicalAddr, Extern,
             HumanAddr, Querier, StdResult, Storage
         },
         schemars,
@@ -13,7 +13,10 @@ use lend_shared::{
         crypto::sha_256,
         Canonize, ContractLink, Decimal256, Humanize, StdError, Uint256,
     },
-    interfaces::market::{BorrowerInfo, Config},
+    interfaces::market::{
+        BadDebt, BorrowerInfo, Config, HistoryEntry, HistoryEvent,
+        HistoryResponse, PausedActions, ReservePolicy, StableBorrowInfo
+    },
     core::{AuthenticatedUser, Pagination},
     impl_contract_storage
 };
@@ -33,9 +36,28 @@ pub struct Account(CanonicalAddr);
 #[derive(Serialize, Deserialize, JsonSchema, Debug)]
 pub struct BorrowSnapshot {
     pub info: BorrowerInfo,
+    #[serde(default)]
+    pub stable: StableBorrowInfo,
     address: CanonicalAddr,
 }
 
+/// Aggregate of all stable rate borrows in the market.
+#[derive(Serialize, Deserialize, JsonSchema, Default, Clone, Debug)]
+pub struct StableDebt {
+    /// Sum of all stable rate balances as of the last accrual.
+    pub total: Uint256,
+    /// Sum of the interest accrued by each stable rate borrow per block.
+    pub interest_per_block: Uint256
+}
+
+/// A flash loan that is currently in progress.
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+pub struct FlashLoan {
+    /// The underlying balance that the market must have once the loan is finished.
+    pub expected_balance: Uint256,
+    pub fee: Uint256
+}
+
 #[derive(Serialize, Deserialize, JsonSchema, PartialEq, Clone, Debug)]
 pub struct BorrowerId([u8; 32]);
 
@@ -57,6 +79,9 @@ impl Contracts {
 impl Constants {
     const KEY_CONFIG: &'static [u8] = b"config";
     const KEY_VK: &'static [u8] = b"underlying_vk";
+    const KEY_PAUSED: &'static [u8] = b"paused_actions";
+    const KEY_RESERVE_POLICY: &'static [u8] = b"reserve_policy";
+    const KEY_REWARDS_ENABLED: &'static [u8] = b"rewards_enabled";
 
     pub fn save_config(
         storage: &mut impl Storage,
@@ -83,6 +108,52 @@ impl Constants {
 
         Ok(result)
     }
+
+    pub fn save_paused(
+        storage: &mut impl Storage,
+        paused: &PausedActions
+    ) -> StdResult<()> {
+        save(storage, Self::KEY_PAUSED, paused)
+    }
+
+    pub fn load_paused(storage: &impl Storage) -> StdResult<PausedActions> {
+        Ok(load(storage, Self::KEY_PAUSED)?.unwrap_or_default())
+    }
+
+    pub fn enable_rewards(storage: &mut impl Storage) -> StdResult<()> {
+        save(storage, Self::KEY_REWARDS_ENABLED, &true)
+    }
+
+    pub fn rewards_enabled(storage: &impl Storage) -> StdResult<bool> {
+        Ok(load(storage, Self::KEY_REWARDS_ENABLED)?.unwrap_or_default())
+    }
+
+    pub fn save_reserve_policy<S: Storage, A: Api, Q: Querier>(
+        deps: &mut Extern<S, A, Q>,
+        policy: Option<ReservePolicy<HumanAddr>>
+    ) -> StdResult<()> {
+        match policy {
+            Some(policy) => {
+                let policy = policy.canonize(&deps.api)?;
+
+                save(&mut deps.storage, Self::KEY_RESERVE_POLICY, &policy)
+            },
+            None => {
+                deps.storage.remove(Self::KEY_RESERVE_POLICY);
+
+                Ok(())
+            }
+        }
+    }
+
+    pub fn load_reserve_policy<S: Storage, A: Api, Q: Querier>(
+        deps: &Extern<S, A, Q>
+    ) -> StdResult<Option<ReservePolicy<HumanAddr>>> {
+        let result: Option<ReservePolicy<CanonicalAddr>> =
+            load(&deps.storage, Self::KEY_RESERVE_POLICY)?;
+
+        result.humanize(&deps.api)
+    }
 }
 
 macro_rules! impl_uint_storage {
@@ -135,6 +206,9 @@ impl Global {
     const KEY_BORROW_INDEX: &'static [u8] = b"borrow_index";
     const KEY_INTEREST_RESERVE: &'static [u8] = b"interest_reserve";
     const KEY_ACCRUAL_BLOCK_NUMBER: &'static [u8] = b"accrual_block_number";
+    const KEY_STABLE_DEBT: &'static [u8] = b"stable_debt";
+    const KEY_BAD_DEBT: &'static [u8] = b"bad_debt";
+    const KEY_FLASH_LOAN: &'static [u8] = b"flash_loan";
 
     #[inline]
     pub fn save_borrow_cap(storage: &mut impl Storage, borrow_cap: &Uint256) -> StdResult<()> {
@@ -175,6 +249,41 @@ impl Global {
     pub fn save_accrual_block_number(storage: &mut impl Storage, block: u64) -> StdResult<()> {
         save(storage, Self::KEY_ACCRUAL_BLOCK_NUMBER, &block)
     }
+
+    #[inline]
+    pub fn load_stable_debt(storage: &impl Storage) -> StdResult<StableDebt> {
+        Ok(load(storage, Self::KEY_STABLE_DEBT)?.unwrap_or_default())
+    }
+
+    #[inline]
+    pub fn save_stable_debt(storage: &mut impl Storage, debt: &StableDebt) -> StdResult<()> {
+        save(storage, Self::KEY_STABLE_DEBT, debt)
+    }
+
+    #[inline]
+    pub fn load_bad_debt(storage: &impl Storage) -> StdResult<BadDebt> {
+        Ok(load(storage, Self::KEY_BAD_DEBT)?.unwrap_or_default())
+    }
+
+    #[inline]
+    pub fn save_bad_debt(storage: &mut impl Storage, bad_debt: &BadDebt) -> StdResult<()> {
+        save(storage, Self::KEY_BAD_DEBT, bad_debt)
+    }
+
+    #[inline]
+    pub fn load_flash_loan(storage: &impl Storage) -> StdResult<Option<FlashLoan>> {
+        load(storage, Self::KEY_FLASH_LOAN)
+    }
+
+    #[inline]
+    pub fn save_flash_loan(storage: &mut impl Storage, loan: &FlashLoan) -> StdResult<()> {
+        save(storage, Self::KEY_FLASH_LOAN, loan)
+    }
+
+    #[inline]
+    pub fn remove_flash_loan(storage: &mut impl Storage) {
+        storage.remove(Self::KEY_FLASH_LOAN)
+    }
 }
 
 impl Account {
@@ -183,6 +292,7 @@ impl Account {
     const NS_BORROW_INFO: &'static [u8] = b"borrow_info";
     const NS_ID_TO_ADDR: &'static [u8] = b"ids";
     const NS_ADDR_TO_ID: &'static [u8] = b"addr";
+    const NS_HISTORY: &'static [u8] = b"history";
 
     pub fn new<S: Storage, A: Api, Q: Querier>(
         deps: &mut Extern<S, A, Q>,
@@ -283,8 +393,11 @@ impl Account {
 
         let index = ns_load(storage, Self::NS_BORROW_INFO, self.0.as_slice())?;
 
+        let is_empty = borrow_info.info.principal.is_zero() &&
+            borrow_info.stable.principal.is_zero();
+
         if let Some(index) = index {
-            if borrow_info.info.principal.is_zero() {
+            if is_empty {
                 // If a swap occurred, update the stored borrower index to the new one. 
                 if let Some(swapped) = borrowers.swap_remove(storage, index)? {
                     ns_save(storage, Self::NS_BORROW_INFO, swapped.address.as_slice(), &index)?;
@@ -296,7 +409,7 @@ impl Account {
                     Ok(borrow_info)
                 })?;
             }
-        } else if !borrow_info.info.principal.is_zero() {
+        } else if !is_empty {
             let index = borrowers.push(storage, &borrow_info)?;
             ns_save(storage, Self::NS_BORROW_INFO, self.0.as_slice(), &index)?;
         }
@@ -304,6 +417,50 @@ impl Account {
         Ok(())
     }
 
+    pub fn push_history(
+        &self,
+        storage: &mut impl Storage,
+        block: &BlockInfo,
+        event: HistoryEvent
+    ) -> StdResult<()> {
+        let ns = self.history_ns();
+
+        IterableStorage::new(&ns).push(storage, &HistoryEntry {
+            block: block.height,
+            time: block.time,
+            event
+        })?;
+
+        Ok(())
+    }
+
+    pub fn list_history(
+        &self,
+        storage: &impl Storage,
+        pagination: Pagination
+    ) -> StdResult<HistoryResponse> {
+        let ns = self.history_ns();
+        let history = IterableStorage::<HistoryEntry>::new(&ns);
+
+        let limit = pagination.limit.min(PAGINATION_LIMIT) as usize;
+
+        let entries = history
+            .iter(storage)?
+            .skip(pagination.start as usize)
+            .take(limit)
+            .collect::<StdResult<Vec<HistoryEntry>>>()?;
+
+        Ok(HistoryResponse {
+            entries,
+            total: history.len(storage)?
+        })
+    }
+
+    #[inline]
+    fn history_ns(&self) -> Vec<u8> {
+        [Self::NS_HISTORY, self.0.as_slice()].concat()
+    }
+
     pub fn get_borrow_snapshot(&self, storage: &impl Storage) -> StdResult<BorrowSnapshot> {
         let index = ns_load(storage, Self::NS_BORROW_INFO, self.0.as_slice())?;
 
@@ -315,7 +472,8 @@ impl Account {
             }
             None => Ok(BorrowSnapshot {
                 address: self.0.clone(),
-                info: BorrowerInfo::default()
+                info: BorrowerInfo::default(),
+                stable: StableBorrowInfo::default()
             })
         }
     }
@@ -513,7 +671,8 @@ mod tests {
 
         ryan.save_borrow_snapshot(&mut deps.storage, BorrowSnapshot {
             address: ryan.0.clone(),
-            info: BorrowerInfo::default()
+            info: BorrowerInfo::default(),
+            stable: StableBorrowInfo::default()
         }).unwrap();
 
         assert_eq!(load_index(&deps.storage, &dave).unwrap(), 0);
@@ -529,5 +688,21 @@ mod tests {
         assert_eq!(snapshot.info.principal, Uint256::from(2));
         assert_eq!(snapshot.info.interest_index, Decimal256::from_uint256(Uint256::from(2)).unwrap());
         assert_eq!(snapshot.address, nancy.0);
+
+        // Stable rate debt alone should keep the borrower in the list.
+        let mut snapshot = nancy.get_borrow_snapshot(&deps.storage).unwrap();
+        snapshot.info.principal = Uint256::zero();
+        snapshot.stable.principal = Uint256::from(5);
+
+        nancy.save_borrow_snapshot(&mut deps.storage, snapshot).unwrap();
+        assert_eq!(load_index(&deps.storage, &nancy).unwrap(), 0);
+
+        let mut snapshot = nancy.get_borrow_snapshot(&deps.storage).unwrap();
+        assert_eq!(snapshot.stable.principal, Uint256::from(5));
+
+        snapshot.stable.principal = Uint256::zero();
+        nancy.save_borrow_snapshot(&mut deps.storage, snapshot).unwrap();
+
+        assert!(load_index(&deps.storage, &nancy).is_none());
     }
 }
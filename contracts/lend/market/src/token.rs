@@ -3,10 +3,12 @@ use lend_shared::{
         cosmwasm_std::{
             Storage, Api, Querier, Extern,
             StdResult, StdError, HumanAddr,
-            Env, HandleResponse, log
+            Env, HandleResponse, CosmosMsg,
+            WasmMsg, Binary, log
         },
         secret_toolkit::snip20,
-        Decimal256, Uint256, BLOCK_SIZE
+        snip20_impl::msg::HandleMsg as Snip20HandleMsg,
+        to_binary, ContractLink, Decimal256, Uint256, BLOCK_SIZE
     },
     interfaces::overseer::query_can_transfer,
     core::MasterKey
@@ -16,7 +18,7 @@ use crate::accrue_interest;
 use crate::state::{
     Constants, Contracts, Account, TotalSupply
 };
-use crate::ops::LatestInterest;
+use crate::ops::{rewards_checkpoint, rewards_update, with_notification, LatestInterest};
 use crate::checks;
 
 pub fn deposit<S: Storage, A: Api, Q: Querier>(
@@ -30,18 +32,20 @@ pub fn deposit<S: Storage, A: Api, Q: Querier>(
         deps,
         underlying_balance,
         interest.total_borrows(&deps.storage)?,
+        interest.total_stable_borrows(&deps.storage)?,
         interest.total_reserves(&deps.storage)?
     )?;
     let mint_amount = Uint256::from(amount)
         .decimal_div(exchange_rate)?;
 
-    TotalSupply::increase(&mut deps.storage, mint_amount)?;
-
     let account = Account::of(deps, &from)?;
+    let rewards = rewards_checkpoint(deps, &account)?;
+
+    TotalSupply::increase(&mut deps.storage, mint_amount)?;
     account.add_balance(&mut deps.storage, mint_amount)?;
 
     Ok(HandleResponse {
-        messages: vec![],
+        messages: rewards_update(deps, &account, rewards)?.into_iter().collect(),
         log: vec![
             log("action", "deposit"),
             log("mint_amount", mint_amount)
@@ -50,12 +54,28 @@ pub fn deposit<S: Storage, A: Api, Q: Querier>(
     })
 }
 
+/// Where the redeemed underlying goes to.
+pub enum RedeemTo {
+    /// Transfer it to the owner of the slTokens.
+    Owner,
+    /// Send it to the contract along with the message. The liquidity
+    /// check is skipped since it is done by the overseer instead.
+    Contract {
+        contract: ContractLink<HumanAddr>,
+        msg: Binary
+    }
+}
+
 pub fn redeem<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S,A,Q>,
     env: Env,
+    owner: HumanAddr,
     from_sl_token: Uint256,
-    from_underlying: Uint256
+    from_underlying: Uint256,
+    to: RedeemTo
 ) -> StdResult<HandleResponse> {
+    checks::assert_no_flash_loan(&deps.storage)?;
+
     let underlying_asset = Contracts::load_underlying(deps)?;
 
     let balance = snip20::balance_query(
@@ -67,12 +87,13 @@ pub fn redeem<S: Storage, A: Api, Q: Querier>(
         underlying_asset.address.clone(),
     )?.amount;
 
-    let mut latest = accrue_interest(deps, env.block.height, balance)?;
+    let mut latest = accrue_interest(deps, &env.block, balance)?;
 
     let exchange_rate = calc_exchange_rate(
         deps,
         balance.into(),
         latest.total_borrows(&deps.storage)?,
+        latest.total_stable_borrows(&deps.storage)?,
         latest.total_reserves(&deps.storage)?
     )?;
 
@@ -88,48 +109,76 @@ pub fn redeem<S: Storage, A: Api, Q: Querier>(
 
     checks::assert_can_withdraw(balance.into(), redeem_amount)?;
 
-    let can_transfer = query_can_transfer(
-        &deps.querier,
-        Contracts::load_overseer(deps)?,
-        MasterKey::load(&deps.storage)?,
-        env.message.sender.clone(),
-        env.contract.address,
-        env.block.height,
-        burn_amount.clamp_u128()?.into()
-    )?;
-
-    if !can_transfer {
-        return Err(StdError::generic_err("Account has negative liquidity and cannot redeem."));
+    if let RedeemTo::Owner = to {
+        let can_transfer = query_can_transfer(
+            &deps.querier,
+            Contracts::load_overseer(deps)?,
+            MasterKey::load(&deps.storage)?,
+            owner.clone(),
+            env.contract.address,
+            env.block.height,
+            Some(env.block.time),
+            burn_amount.clamp_u128()?.into()
+        )?;
+
+        if !can_transfer {
+            return Err(StdError::generic_err("Account has negative liquidity and cannot redeem."));
+        }
     }
 
-    TotalSupply::decrease(&mut deps.storage, burn_amount)?;
+    let account = Account::of(deps, &owner)?;
+    let rewards = rewards_checkpoint(deps, &account)?;
 
-    let account = Account::of(deps, &env.message.sender)?;
+    TotalSupply::decrease(&mut deps.storage, burn_amount)?;
     account.subtract_balance(&mut deps.storage, burn_amount)?;
 
-    Ok(HandleResponse {
-        messages: vec![snip20::transfer_msg(
-            env.message.sender,
+    let message = match to {
+        RedeemTo::Owner => snip20::transfer_msg(
+            owner,
             redeem_amount.clamp_u128()?.into(),
             None,
             None,
             BLOCK_SIZE,
             underlying_asset.code_hash,
             underlying_asset.address
-        )?],
-        log: vec![
-            log("action", "redeem"),
-            log("redeem_amount", redeem_amount),
-            log("burn_amount", burn_amount)
-        ],
-        data: None
-    })
+        )?,
+        RedeemTo::Contract { contract, msg } => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: underlying_asset.address,
+            callback_code_hash: underlying_asset.code_hash,
+            msg: to_binary(&Snip20HandleMsg::Send {
+                recipient: contract.address,
+                recipient_code_hash: Some(contract.code_hash),
+                amount: redeem_amount.clamp_u128()?.into(),
+                msg: Some(msg),
+                memo: None,
+                padding: None
+            })?,
+            send: vec![]
+        })
+    };
+
+    let mut messages = vec![message];
+    messages.extend(rewards_update(deps, &account, rewards)?);
+
+    Ok(with_notification(
+        HandleResponse {
+            messages,
+            log: vec![
+                log("action", "redeem"),
+                log("redeem_amount", redeem_amount),
+                log("burn_amount", burn_amount)
+            ],
+            data: None
+        },
+        latest.take_notification(),
+    ))
 }
 
 pub fn calc_exchange_rate<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S,A,Q>,
     balance: Uint256,
     total_borrows: Uint256,
+    total_stable_borrows: Uint256,
     total_reserves: Uint256
 ) -> StdResult<Decimal256> {
     let total_supply = TotalSupply::load(&deps.storage)?;
@@ -140,6 +189,7 @@ pub fn calc_exchange_rate<S: Storage, A: Api, Q: Querier>(
         return Ok(config.initial_exchange_rate);
     }
 
+    let total_borrows = (total_borrows + total_stable_borrows)?;
     let total_minus_reserves = ((balance + total_borrows)? - total_reserves)?;
 
     Decimal256::from_ratio(total_minus_reserves.0, Uint256::from(total_supply).0)
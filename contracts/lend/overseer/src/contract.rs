@@ -9,8 +9,8 @@ use lend_shared::{
         admin::{assert_admin, Admin},
         auth, cosmwasm_std,
         cosmwasm_std::{
-            log, to_binary, Api, Binary, CosmosMsg, Extern, HandleResponse, HumanAddr,
-            InitResponse, Querier, StdError, StdResult, Storage, WasmMsg,
+            log, to_binary, Api, Binary, BlockInfo, CosmosMsg, Env, Extern, HandleResponse,
+            HumanAddr, InitResponse, Querier, StdError, StdResult, Storage, WasmMsg,
         },
         derive_contract::*,
         require_admin,
@@ -19,21 +19,31 @@ use lend_shared::{
         BLOCK_SIZE,
     },
     interfaces::{
-        market::{query_account, query_exchange_rate, InitMsg as MarketInitMsg, MarketAuth},
+        market::{
+            query_account, query_borrower_addresses, query_exchange_rate, query_id,
+            query_simulate_liquidation, query_state, HandleMsg as MarketHandleMsg,
+            InitMsg as MarketInitMsg, MarketAuth, PausedActions, SwapOutputAction,
+        },
         oracle::{
             query_price, Asset, AssetType, HandleMsg as OracleHandleMsg, InitMsg as OracleInitMsg,
             OverseerRef,
         },
         overseer::{
-            AccountLiquidity, Config, HandleMsg, Market, MarketInitConfig, MarketsResponse,
-            OverseerAuth, OverseerPermissions,
+            AccountLiquidity, Config, EModeCategoriesResponse, HandleMsg, LiquidatableAccount,
+            LiquidatableAccountsResponse, Market, MarketInitConfig, MarketStatus, MarketsResponse,
+            OverseerAuth, OverseerPermissions, Position, RewardBalances, RewardSpeeds,
         },
+        swap::{swap_destination, Hop},
     },
 };
 
-use state::{Account, Constants, Contracts, Markets, Whitelisting};
+use state::{
+    Account, AccountRewards, Constants, Contracts, EModeCategories, Guardian, IsolatedDebt,
+    MarketRewards, Markets, PendingOperation, Rewards, SwapPairs, Whitelisting,
+};
 
 const QUOTE_SYMBOL: &str = "USD";
+const LIQUIDATABLE_ACCOUNTS_LIMIT: u8 = 10;
 
 #[contract_impl(
     entry,
@@ -157,6 +167,10 @@ pub trait Overseer {
             symbol: config.token_symbol,
             ltv_ratio: config.ltv_ratio,
             decimals: underlying_info.decimals,
+            isolated: config.isolated,
+            borrowable_in_isolation: config.borrowable_in_isolation,
+            e_mode_category: None,
+            status: MarketStatus::Active,
         };
         market.validate()?;
 
@@ -229,8 +243,50 @@ pub trait Overseer {
             .map(|x| Markets::get_id(deps, x))
             .collect::<StdResult<Vec<u64>>>()?;
 
+        let mut isolated = account.isolated_market(deps)?.map(|(id, _)| id);
+
+        for id in ids.iter() {
+            let market = Markets::get_by_id(deps, *id)?.unwrap();
+
+            if market.status != MarketStatus::Active {
+                return Err(StdError::generic_err(format!(
+                    "Cannot enter deprecated market: {}",
+                    market.contract.address
+                )));
+            }
+
+            if market.isolated.is_none() {
+                continue;
+            }
+
+            match isolated {
+                Some(current) if current != *id => {
+                    return Err(StdError::generic_err(
+                        "Cannot enter more than one isolated market.",
+                    ));
+                }
+                _ => isolated = Some(*id),
+            }
+        }
+
+        if let Some(id) = isolated {
+            if let Some((debt_market, _)) = account.load_isolated_debt(&deps.storage)? {
+                if debt_market != id {
+                    return Err(StdError::generic_err(
+                        "Cannot enter an isolated market while having debt against another one.",
+                    ));
+                }
+            }
+        }
+
         account.add_markets(&mut deps.storage, ids)?;
 
+        // Entering a market outside of the e-mode category means
+        // that the category parameters no longer apply.
+        if account.load_e_mode(&deps.storage)?.is_some() {
+            assert_no_shortfall(deps, &account)?;
+        }
+
         Ok(HandleResponse {
             messages: vec![],
             log: vec![log("action", "enter")],
@@ -254,9 +310,10 @@ pub trait Overseer {
             market.contract,
             method.clone(),
             None, // None because we only check if borrows balance is zero here.
+            None,
         )?;
 
-        if snapshot.borrow_balance != Uint256::zero() {
+        if !snapshot.borrow_balance.is_zero() || !snapshot.stable_borrow_balance.is_zero() {
             return Err(StdError::generic_err("Cannot exit market while borrowing."));
         }
 
@@ -266,8 +323,10 @@ pub trait Overseer {
             method,
             Some(market_address),
             Some(env.block.height),
+            Some(env.block.time),
             snapshot.sl_token_balance,
             Uint256::zero(),
+            CollateralRequirement::Ltv,
         )?;
 
         if liquidity.shortfall > Uint256::zero() {
@@ -292,6 +351,8 @@ pub trait Overseer {
         market: HumanAddr,
         ltv_ratio: Option<Decimal256>,
         symbol: Option<String>,
+        debt_ceiling: Option<Uint256>,
+        borrowable_in_isolation: Option<bool>,
     ) -> StdResult<HandleResponse> {
         let (_, stored_market) = Markets::get_by_addr(deps, &market)?;
 
@@ -326,6 +387,21 @@ pub trait Overseer {
 
             m.symbol = symbol.clone();
 
+            if let Some(debt_ceiling) = debt_ceiling {
+                match m.isolated.as_mut() {
+                    Some(isolation) => isolation.debt_ceiling = debt_ceiling,
+                    None => {
+                        return Err(StdError::generic_err(
+                            "Cannot set a debt ceiling on a market that is not isolated.",
+                        ))
+                    }
+                }
+            }
+
+            if let Some(borrowable) = borrowable_in_isolation {
+                m.borrowable_in_isolation = borrowable;
+            }
+
             Ok(m)
         })?;
 
@@ -354,6 +430,498 @@ pub trait Overseer {
         })
     }
 
+    #[handle]
+    #[require_admin]
+    fn deprecate_market(market: HumanAddr) -> StdResult<HandleResponse> {
+        Markets::update(deps, &market, |mut m| {
+            if m.status != MarketStatus::Active {
+                return Err(StdError::generic_err("Market is already deprecated."));
+            }
+
+            m.status = MarketStatus::Deprecated;
+
+            Ok(m)
+        })?;
+
+        Ok(HandleResponse {
+            messages: vec![],
+            log: vec![
+                log("action", "deprecate_market"),
+                log("market", market),
+            ],
+            data: None,
+        })
+    }
+
+    #[handle]
+    #[require_admin]
+    fn delist_market(market: HumanAddr) -> StdResult<HandleResponse> {
+        let (_, stored_market) = Markets::get_by_addr(deps, &market)?;
+
+        if stored_market.status != MarketStatus::Deprecated {
+            return Err(StdError::generic_err(
+                "Only deprecated markets can be delisted.",
+            ));
+        }
+
+        let state = query_state(&deps.querier, stored_market.contract, None, None)?;
+
+        if !state.total_supply.is_zero()
+            || !state.total_borrows.is_zero()
+            || !state.total_stable_borrows.is_zero()
+        {
+            return Err(StdError::generic_err(
+                "Cannot delist a market that has supply or outstanding borrows.",
+            ));
+        }
+
+        Markets::delist(deps, &market)?;
+
+        Ok(HandleResponse {
+            messages: vec![],
+            log: vec![
+                log("action", "delist_market"),
+                log("market", market),
+            ],
+            data: None,
+        })
+    }
+
+    #[handle]
+    #[require_admin]
+    fn set_guardian(guardian: Option<HumanAddr>) -> StdResult<HandleResponse> {
+        Guardian::save(deps, guardian)?;
+
+        Ok(HandleResponse {
+            messages: vec![],
+            log: vec![log("action", "set_guardian")],
+            data: None,
+        })
+    }
+
+    #[handle]
+    fn pause_market(market: HumanAddr, actions: PausedActions) -> StdResult<HandleResponse> {
+        if Guardian::load(deps)? != Some(env.message.sender.clone()) {
+            assert_admin(deps, &env)?;
+        }
+
+        let (_, market) = Markets::get_by_addr(deps, &market)?;
+
+        Ok(HandleResponse {
+            messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: market.contract.address.clone(),
+                callback_code_hash: market.contract.code_hash,
+                send: vec![],
+                msg: to_binary(&MarketHandleMsg::Pause { actions })?,
+            })],
+            log: vec![
+                log("action", "pause_market"),
+                log("market", market.contract.address),
+            ],
+            data: None,
+        })
+    }
+
+    #[handle]
+    #[require_admin]
+    fn set_swap_router(router: ContractLink<HumanAddr>) -> StdResult<HandleResponse> {
+        Contracts::save_swap_router(deps, router)?;
+
+        Ok(HandleResponse {
+            messages: vec![],
+            log: vec![log("action", "set_swap_router")],
+            data: None,
+        })
+    }
+
+    #[handle]
+    #[require_admin]
+    fn set_swap_pairs(pairs: Vec<HumanAddr>, allowed: bool) -> StdResult<HandleResponse> {
+        for pair in pairs.iter() {
+            SwapPairs::set(deps, pair, allowed)?;
+        }
+
+        Ok(HandleResponse {
+            messages: vec![],
+            log: vec![
+                log("action", "set_swap_pairs"),
+                log("allowed", allowed),
+            ],
+            data: None,
+        })
+    }
+
+    #[handle]
+    fn repay_with_collateral(
+        collateral: HumanAddr,
+        amount: Uint256,
+        borrowed: HumanAddr,
+        hops: Vec<Hop>,
+        min_output: Uint256,
+    ) -> StdResult<HandleResponse> {
+        let action = SwapOutputAction::Repay {
+            borrower: env.message.sender.clone(),
+        };
+
+        Ok(HandleResponse {
+            messages: collateral_operation(
+                deps, &env, collateral, amount, borrowed, hops, min_output, action,
+            )?,
+            log: vec![log("action", "repay_with_collateral")],
+            data: None,
+        })
+    }
+
+    #[handle]
+    fn swap_collateral(
+        from: HumanAddr,
+        amount: Uint256,
+        to: HumanAddr,
+        hops: Vec<Hop>,
+        min_output: Uint256,
+    ) -> StdResult<HandleResponse> {
+        let action = SwapOutputAction::Deposit {
+            owner: env.message.sender.clone(),
+        };
+
+        Ok(HandleResponse {
+            messages: collateral_operation(
+                deps, &env, from, amount, to, hops, min_output, action,
+            )?,
+            log: vec![log("action", "swap_collateral")],
+            data: None,
+        })
+    }
+
+    #[handle]
+    fn finish_collateral_operation() -> StdResult<HandleResponse> {
+        if env.message.sender != env.contract.address {
+            return Err(StdError::unauthorized());
+        }
+
+        let pending = PendingOperation::load(&deps.storage)?
+            .ok_or_else(|| StdError::generic_err("No collateral operation is in progress."))?;
+        PendingOperation::remove(&mut deps.storage);
+
+        let shortfall = current_shortfall(deps, &Account(pending.account), &env.block)?;
+
+        // Accounts that were already below the requirement can still improve their position.
+        if shortfall > pending.shortfall {
+            return Err(StdError::generic_err(format!(
+                "Operation would leave the account below its target collateral requirement by {}",
+                shortfall
+            )));
+        }
+
+        Ok(HandleResponse {
+            messages: vec![],
+            log: vec![log("action", "finish_collateral_operation")],
+            data: None,
+        })
+    }
+
+    #[handle]
+    fn update_isolated_debt(
+        borrower: HumanAddr,
+        amount: Uint256,
+        repay: bool,
+    ) -> StdResult<HandleResponse> {
+        // Only listed markets can report debt.
+        let (_, market) = Markets::get_by_addr(deps, &env.message.sender)
+            .map_err(|_| StdError::unauthorized())?;
+
+        let account = Account::new(&deps.api, &borrower)?;
+
+        let debt_market = if repay {
+            account.load_isolated_debt(&deps.storage)?
+        } else {
+            match account.isolated_market(deps)? {
+                Some((id, _)) => Some((
+                    id,
+                    account
+                        .load_isolated_debt(&deps.storage)?
+                        .map(|(_, debt)| debt)
+                        .unwrap_or_default(),
+                )),
+                None => None,
+            }
+        };
+
+        let (id, debt) = match debt_market {
+            Some(debt_market) => debt_market,
+            None => return Ok(HandleResponse::default()),
+        };
+
+        let price = query_price(
+            &deps.querier,
+            Contracts::load_oracle(deps)?,
+            market.symbol.into(),
+            QUOTE_SYMBOL.into(),
+            market.decimals,
+            None,
+        )?;
+        let value = amount.decimal_mul(price.rate)?;
+
+        if repay {
+            // The price may have moved since the debt was taken.
+            let value = if value > debt { debt } else { value };
+
+            account.save_isolated_debt(&mut deps.storage, id, (debt - value)?)?;
+            IsolatedDebt::decrease(&mut deps.storage, id, value)?;
+        } else {
+            account.save_isolated_debt(&mut deps.storage, id, (debt + value)?)?;
+            IsolatedDebt::increase(&mut deps.storage, id, value)?;
+        }
+
+        Ok(HandleResponse {
+            messages: vec![],
+            log: vec![
+                log("action", "update_isolated_debt"),
+                log("isolated_market", id),
+                log("amount", value),
+            ],
+            data: None,
+        })
+    }
+
+    #[handle]
+    #[require_admin]
+    fn add_e_mode_category(
+        label: String,
+        ltv_ratio: Decimal256,
+        liquidation_threshold: Decimal256,
+    ) -> StdResult<HandleResponse> {
+        let id = EModeCategories::push(
+            &mut deps.storage,
+            label,
+            ltv_ratio,
+            liquidation_threshold,
+        )?;
+
+        Ok(HandleResponse {
+            messages: vec![],
+            log: vec![
+                log("action", "add_e_mode_category"),
+                log("id", id),
+            ],
+            data: Some(to_binary(&id)?),
+        })
+    }
+
+    #[handle]
+    #[require_admin]
+    fn update_e_mode_category(
+        id: u64,
+        ltv_ratio: Option<Decimal256>,
+        liquidation_threshold: Option<Decimal256>,
+    ) -> StdResult<HandleResponse> {
+        EModeCategories::update(&mut deps.storage, id, |mut category| {
+            if let Some(ltv_ratio) = ltv_ratio {
+                category.ltv_ratio = ltv_ratio;
+            }
+
+            if let Some(liquidation_threshold) = liquidation_threshold {
+                category.liquidation_threshold = liquidation_threshold;
+            }
+
+            category.validate()?;
+
+            Ok(category)
+        })?;
+
+        Ok(HandleResponse {
+            messages: vec![],
+            log: vec![log("action", "update_e_mode_category")],
+            data: None,
+        })
+    }
+
+    #[handle]
+    #[require_admin]
+    fn set_market_e_mode_category(
+        market: HumanAddr,
+        category: Option<u64>,
+    ) -> StdResult<HandleResponse> {
+        if let Some(id) = category {
+            EModeCategories::get(&deps.storage, id)?;
+        }
+
+        Markets::update(deps, &market, |mut m| {
+            m.e_mode_category = category;
+
+            Ok(m)
+        })?;
+
+        Ok(HandleResponse {
+            messages: vec![],
+            log: vec![log("action", "set_market_e_mode_category")],
+            data: None,
+        })
+    }
+
+    #[handle]
+    fn set_e_mode(category: Option<u64>) -> StdResult<HandleResponse> {
+        if let Some(id) = category {
+            EModeCategories::get(&deps.storage, id)?;
+        }
+
+        let account = Account::new(&deps.api, &env.message.sender)?;
+        account.save_e_mode(&mut deps.storage, category)?;
+
+        if !account.list_markets(deps)?.is_empty() {
+            assert_no_shortfall(deps, &account)?;
+        }
+
+        Ok(HandleResponse {
+            messages: vec![],
+            log: vec![log("action", "set_e_mode")],
+            data: None,
+        })
+    }
+
+    #[handle]
+    #[require_admin]
+    fn set_rewards_token(token: ContractLink<HumanAddr>) -> StdResult<HandleResponse> {
+        Contracts::save_rewards_token(deps, token)?;
+
+        Ok(HandleResponse {
+            messages: vec![],
+            log: vec![log("action", "set_rewards_token")],
+            data: None,
+        })
+    }
+
+    #[handle]
+    #[require_admin]
+    fn set_reward_speeds(market: HumanAddr, speeds: RewardSpeeds) -> StdResult<HandleResponse> {
+        let (id, market) = Markets::get_by_addr(deps, &market)?;
+
+        // Distribute at the previous speeds up to this block. Reading the totals from the market
+        // means that the positions which exist when rewards are first enabled earn right away.
+        let mut rewards = Rewards::load_market(&deps.storage, id)?;
+        rewards.set_totals(&query_reward_balances(
+            &deps.querier,
+            market.contract.clone(),
+            None,
+        )?);
+        rewards.accrue(env.block.height)?;
+
+        rewards.supply.speed = speeds.supply;
+        rewards.borrow.speed = speeds.borrow;
+
+        Rewards::save_market(&mut deps.storage, id, &rewards)?;
+
+        // Markets don't report balances until there is something to distribute.
+        let mut messages = vec![];
+
+        if !speeds.supply.is_zero() || !speeds.borrow.is_zero() {
+            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: market.contract.address.clone(),
+                callback_code_hash: market.contract.code_hash,
+                send: vec![],
+                msg: to_binary(&MarketHandleMsg::EnableRewards {})?,
+            }));
+        }
+
+        Ok(HandleResponse {
+            messages,
+            log: vec![
+                log("action", "set_reward_speeds"),
+                log("market", market.contract.address),
+                log("supply_speed", speeds.supply),
+                log("borrow_speed", speeds.borrow),
+            ],
+            data: None,
+        })
+    }
+
+    #[handle]
+    fn update_rewards(
+        account: HumanAddr,
+        previous: RewardBalances,
+        current: RewardBalances,
+    ) -> StdResult<HandleResponse> {
+        // Only listed markets can report balances.
+        let id = Markets::get_id(deps, &env.message.sender)
+            .map_err(|_| StdError::unauthorized())?;
+
+        let account = Account::new(&deps.api, &account)?;
+
+        let (mut rewards, position, earned) =
+            accrue_rewards(&deps.storage, id, &account, &previous, env.block.height)?;
+
+        // The previous balances were in effect up to this block, the new ones apply from now on.
+        rewards.set_totals(&current);
+
+        Rewards::save_market(&mut deps.storage, id, &rewards)?;
+        Rewards::save_account(&mut deps.storage, id, &account, &position)?;
+
+        if !earned.is_zero() {
+            let accrued = Rewards::load_accrued(&deps.storage, &account)?;
+            Rewards::save_accrued(&mut deps.storage, &account, &(accrued + earned)?)?;
+        }
+
+        Ok(HandleResponse {
+            messages: vec![],
+            log: vec![
+                log("action", "update_rewards"),
+                log("earned", earned),
+            ],
+            data: None,
+        })
+    }
+
+    #[handle]
+    fn claim_rewards(markets: Vec<HumanAddr>) -> StdResult<HandleResponse> {
+        let token = Contracts::load_rewards_token(deps)?
+            .ok_or_else(|| StdError::generic_err("Rewards token is not configured."))?;
+
+        let account = Account::new(&deps.api, &env.message.sender)?;
+        let mut amount = Rewards::load_accrued(&deps.storage, &account)?;
+
+        let key = MasterKey::load(&deps.storage)?;
+
+        for market in markets {
+            let (id, market) = Markets::get_by_addr(deps, &market)?;
+
+            let balances = query_reward_balances(
+                &deps.querier,
+                market.contract,
+                Some((key.clone(), env.message.sender.clone())),
+            )?;
+            let (rewards, position, earned) =
+                accrue_rewards(&deps.storage, id, &account, &balances, env.block.height)?;
+
+            Rewards::save_market(&mut deps.storage, id, &rewards)?;
+            Rewards::save_account(&mut deps.storage, id, &account, &position)?;
+
+            amount = (amount + earned)?;
+        }
+
+        if amount.is_zero() {
+            return Err(StdError::generic_err("No rewards to claim."));
+        }
+
+        Rewards::save_accrued(&mut deps.storage, &account, &Uint256::zero())?;
+
+        Ok(HandleResponse {
+            messages: vec![snip20::transfer_msg(
+                env.message.sender,
+                amount.clamp_u128()?.into(),
+                None,
+                None,
+                BLOCK_SIZE,
+                token.code_hash,
+                token.address,
+            )?],
+            log: vec![
+                log("action", "claim_rewards"),
+                log("amount", amount),
+            ],
+            data: None,
+        })
+    }
+
     #[handle]
     #[require_admin]
     fn change_config(
@@ -388,6 +956,32 @@ pub trait Overseer {
         })
     }
 
+    #[query]
+    fn isolated_debt(market: HumanAddr) -> StdResult<Uint256> {
+        let id = Markets::get_id(deps, &market)?;
+
+        IsolatedDebt::load(&deps.storage, id)
+    }
+
+    #[query]
+    fn e_mode_categories(pagination: Pagination) -> StdResult<EModeCategoriesResponse> {
+        let (total, entries) = EModeCategories::list(&deps.storage, pagination)?;
+
+        Ok(EModeCategoriesResponse { entries, total })
+    }
+
+    #[query]
+    fn e_mode(method: OverseerAuth) -> StdResult<Option<u64>> {
+        let account = Account::authenticate(
+            deps,
+            method,
+            OverseerPermissions::AccountInfo,
+            Contracts::load_self_ref,
+        )?;
+
+        account.load_e_mode(&deps.storage)
+    }
+
     #[query]
     fn entered_markets(method: OverseerAuth) -> StdResult<Vec<Market<HumanAddr>>> {
         let account = Account::authenticate(
@@ -400,16 +994,82 @@ pub trait Overseer {
         account.list_markets(deps)
     }
 
+    #[query]
+    fn guardian() -> StdResult<Option<HumanAddr>> {
+        Guardian::load(deps)
+    }
+
+    #[query]
+    fn swap_router() -> StdResult<Option<ContractLink<HumanAddr>>> {
+        Contracts::load_swap_router(deps)
+    }
+
+    #[query]
+    fn swap_pair_allowed(pair: HumanAddr) -> StdResult<bool> {
+        SwapPairs::is_allowed(deps, &pair)
+    }
+
     #[query]
     fn oracle_contract() -> StdResult<ContractLink<HumanAddr>> {
         Contracts::load_oracle(deps)
     }
 
+    #[query]
+    fn rewards_token() -> StdResult<Option<ContractLink<HumanAddr>>> {
+        Contracts::load_rewards_token(deps)
+    }
+
+    #[query]
+    fn reward_speeds(market: HumanAddr) -> StdResult<RewardSpeeds> {
+        let id = Markets::get_id(deps, &market)?;
+        let rewards = Rewards::load_market(&deps.storage, id)?;
+
+        Ok(RewardSpeeds {
+            supply: rewards.supply.speed,
+            borrow: rewards.borrow.speed,
+        })
+    }
+
+    #[query]
+    fn unclaimed_rewards(
+        method: OverseerAuth,
+        markets: Vec<HumanAddr>,
+        block: u64,
+    ) -> StdResult<Uint256> {
+        let account = Account::authenticate(
+            deps,
+            method,
+            OverseerPermissions::AccountInfo,
+            Contracts::load_self_ref,
+        )?;
+
+        let mut amount = Rewards::load_accrued(&deps.storage, &account)?;
+
+        let key = MasterKey::load(&deps.storage)?;
+        let address = account.0.borrow().humanize(&deps.api)?;
+
+        for market in markets {
+            let (id, market) = Markets::get_by_addr(deps, &market)?;
+
+            let balances = query_reward_balances(
+                &deps.querier,
+                market.contract,
+                Some((key.clone(), address.clone())),
+            )?;
+            let (_, _, earned) = accrue_rewards(&deps.storage, id, &account, &balances, block)?;
+
+            amount = (amount + earned)?;
+        }
+
+        Ok(amount)
+    }
+
     #[query]
     fn account_liquidity(
         method: OverseerAuth,
         market: Option<HumanAddr>,
         block: Option<u64>,
+        time: Option<u64>,
         redeem_amount: Uint256,
         borrow_amount: Uint256,
     ) -> StdResult<AccountLiquidity> {
@@ -420,6 +1080,13 @@ pub trait Overseer {
             Contracts::load_self_ref,
         )?;
 
+        // Without any amounts, this is whether the account can be liquidated.
+        let requirement = if redeem_amount.is_zero() && borrow_amount.is_zero() {
+            CollateralRequirement::LiquidationThreshold
+        } else {
+            CollateralRequirement::Ltv
+        };
+
         calc_liquidity(
             deps,
             &account,
@@ -430,8 +1097,10 @@ pub trait Overseer {
             },
             market,
             block,
+            time,
             redeem_amount,
             borrow_amount,
+            requirement,
         )
     }
 
@@ -441,6 +1110,7 @@ pub trait Overseer {
         address: HumanAddr,
         market: HumanAddr,
         block: u64,
+        time: Option<u64>,
         amount: Uint256,
     ) -> StdResult<bool> {
         MasterKey::check(&deps.storage, &key)?;
@@ -458,8 +1128,10 @@ pub trait Overseer {
             MarketAuth::Internal { key, address },
             Some(market),
             Some(block),
+            time,
             amount,
             Uint256::zero(),
+            CollateralRequirement::Ltv,
         )?;
 
         if result.shortfall > Uint256::zero() {
@@ -469,6 +1141,76 @@ pub trait Overseer {
         }
     }
 
+    #[query]
+    fn liquidatable_accounts(
+        block: u64,
+        time: Option<u64>,
+        pagination: Pagination,
+    ) -> StdResult<LiquidatableAccountsResponse> {
+        if pagination.limit == 0 {
+            return Err(StdError::generic_err("Pagination limit must be greater than 0."));
+        }
+
+        let key = MasterKey::load(&deps.storage)?;
+        let close_factor = Constants::load(&deps.storage)?.close_factor();
+        let limit = pagination.limit.min(LIQUIDATABLE_ACCOUNTS_LIMIT) as u64;
+
+        let mut entries = vec![];
+        let mut skip = pagination.start;
+        let mut scanned = 0;
+
+        for market in Markets::list_all(deps)? {
+            let mut start = skip;
+
+            while scanned < limit {
+                let page = query_borrower_addresses(
+                    &deps.querier,
+                    market.contract.clone(),
+                    key.clone(),
+                    Pagination {
+                        start,
+                        limit: (limit - scanned) as u8,
+                    },
+                )?;
+
+                if start >= page.total || page.entries.is_empty() {
+                    skip = start.saturating_sub(page.total);
+
+                    break;
+                }
+
+                skip = 0;
+                start += page.entries.len() as u64;
+                scanned += page.entries.len() as u64;
+
+                for address in page.entries {
+                    let entry = liquidatable_account(
+                        deps,
+                        &key,
+                        address,
+                        &market.contract.address,
+                        block,
+                        time,
+                        close_factor,
+                    )?;
+
+                    if let Some(entry) = entry {
+                        entries.push(entry);
+                    }
+                }
+            }
+        }
+
+        Ok(LiquidatableAccountsResponse {
+            entries,
+            next: if scanned == limit {
+                Some(pagination.start + scanned)
+            } else {
+                None
+            },
+        })
+    }
+
     #[query]
     fn seize_amount(
         borrowed: HumanAddr,
@@ -500,7 +1242,7 @@ pub trait Overseer {
         )?;
 
         // Get the exchange rate and calculate the number of collateral tokens to seize
-        let exchange_rate = query_exchange_rate(&deps.querier, collateral_market.contract, None)?;
+        let exchange_rate = query_exchange_rate(&deps.querier, collateral_market.contract, None, None)?;
         let ratio = ((premium * price_borrowed.rate)? / (price_collateral.rate * exchange_rate)?)?;
 
         repay_amount.decimal_mul(ratio)
@@ -529,15 +1271,344 @@ pub trait Overseer {
     }
 }
 
+/// Returns the liquidation info of the account if it has a shortfall.
+/// Accounts are only reported when scanning the market in which
+/// they have their largest debt so that they appear only once.
+fn liquidatable_account<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    key: &MasterKey,
+    address: HumanAddr,
+    scanned_market: &HumanAddr,
+    block: u64,
+    time: Option<u64>,
+    close_factor: Decimal256,
+) -> StdResult<Option<LiquidatableAccount>> {
+    let account = Account::new(&deps.api, &address)?;
+    let method = MarketAuth::Internal {
+        key: key.clone(),
+        address,
+    };
+
+    let liquidity = calc_liquidity(
+        deps,
+        &account,
+        method.clone(),
+        None,
+        Some(block),
+        time,
+        Uint256::zero(),
+        Uint256::zero(),
+        CollateralRequirement::LiquidationThreshold,
+    )?;
+
+    if liquidity.shortfall.is_zero() {
+        return Ok(None);
+    }
+
+    let oracle = Contracts::load_oracle(deps)?;
+
+    let mut largest_debt: Option<(ContractLink<HumanAddr>, Position)> = None;
+    let mut largest_collateral: Option<Position> = None;
+
+    for market in account.list_markets(deps)? {
+        let snapshot = query_account(
+            &deps.querier,
+            market.contract.clone(),
+            method.clone(),
+            Some(block),
+            time,
+        )?;
+        let price = query_price(
+            &deps.querier,
+            oracle.clone(),
+            market.symbol.clone().into(),
+            QUOTE_SYMBOL.into(),
+            market.decimals,
+            None,
+        )?;
+
+        let borrow_balance = (snapshot.borrow_balance + snapshot.stable_borrow_balance)?;
+        let debt_value = borrow_balance.decimal_mul(price.rate)?;
+
+        if !debt_value.is_zero()
+            && largest_debt.as_ref().map_or(true, |(_, x)| debt_value > x.value)
+        {
+            let position = Position {
+                market: market.contract.address.clone(),
+                balance: borrow_balance,
+                value: debt_value,
+            };
+
+            largest_debt = Some((market.contract.clone(), position));
+        }
+
+        let collateral_value = snapshot
+            .sl_token_balance
+            .decimal_mul((snapshot.exchange_rate * price.rate)?)?;
+
+        if !collateral_value.is_zero()
+            && largest_collateral.as_ref().map_or(true, |x| collateral_value > x.value)
+        {
+            largest_collateral = Some(Position {
+                market: market.contract.address,
+                balance: snapshot.sl_token_balance,
+                value: collateral_value,
+            });
+        }
+    }
+
+    let (borrow_market, largest_debt) = match largest_debt {
+        Some(debt) => debt,
+        None => return Ok(None),
+    };
+
+    if borrow_market.address != *scanned_market {
+        return Ok(None);
+    }
+
+    let id = query_id(&deps.querier, borrow_market.clone(), method)?;
+    let max_repay = largest_debt.balance.decimal_mul(close_factor)?;
+
+    let simulation = match &largest_collateral {
+        Some(collateral) => query_simulate_liquidation(
+            &deps.querier,
+            borrow_market,
+            block,
+            time,
+            id.clone(),
+            collateral.market.clone(),
+            max_repay,
+        )
+        .ok(),
+        None => None,
+    };
+
+    Ok(Some(LiquidatableAccount {
+        id,
+        shortfall: liquidity.shortfall,
+        largest_debt,
+        largest_collateral,
+        max_repay,
+        simulation,
+    }))
+}
+
+/// Brings the rewards of the market up to the given block and returns them
+/// along with the position of the account and the rewards that it earned since.
+/// The `balances` are those that were in effect since the last update.
+fn accrue_rewards(
+    storage: &impl Storage,
+    market: u64,
+    account: &Account,
+    balances: &RewardBalances,
+    block: u64,
+) -> StdResult<(MarketRewards, AccountRewards, Uint256)> {
+    let mut rewards = Rewards::load_market(storage, market)?;
+    rewards.set_totals(balances);
+    rewards.accrue(block)?;
+
+    let mut position = Rewards::load_account(storage, market, account)?;
+    let earned = position.accrue(&rewards, balances)?;
+
+    Ok((rewards, position, earned))
+}
+
+/// Reads the totals of the market and, if an `account` is given,
+/// the balances that it currently holds there.
+fn query_reward_balances(
+    querier: &impl Querier,
+    market: ContractLink<HumanAddr>,
+    account: Option<(MasterKey, HumanAddr)>,
+) -> StdResult<RewardBalances> {
+    let state = query_state(querier, market.clone(), None, None)?;
+
+    let mut balances = RewardBalances {
+        total_supply: state.total_supply,
+        total_borrows: (state.total_borrows + state.total_stable_borrows)?,
+        ..RewardBalances::default()
+    };
+
+    if let Some((key, address)) = account {
+        let info = query_account(
+            querier,
+            market,
+            MarketAuth::Internal { address, key },
+            None,
+            None,
+        )?;
+
+        balances.sl_token_balance = info.sl_token_balance;
+        balances.borrow_balance = (info.borrow_balance + info.stable_borrow_balance)?;
+    }
+
+    Ok(balances)
+}
+
+/// Redeems `amount` of slTokens of the sender in the `from` market, swaps the underlying
+/// along `hops` into the underlying of the `to` market and applies the `action` there.
+/// Returns the messages that carry out the operation, the last of which checks
+/// the liquidity of the account once everything else has been executed.
+fn collateral_operation<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    from: HumanAddr,
+    amount: Uint256,
+    to: HumanAddr,
+    hops: Vec<Hop>,
+    min_output: Uint256,
+    action: SwapOutputAction,
+) -> StdResult<Vec<CosmosMsg>> {
+    if PendingOperation::load(&deps.storage)?.is_some() {
+        return Err(StdError::generic_err("Another collateral operation is in progress."));
+    }
+
+    if from == to {
+        return Err(StdError::generic_err("Cannot swap collateral into the same market."));
+    }
+
+    let (_, from) = Markets::get_by_addr(deps, &from)?;
+    let (_, to) = Markets::get_by_addr(deps, &to)?;
+
+    // The redeemed collateral is sent to the first pair (or the router, which
+    // sends it through every pair), so only trusted ones can be used.
+    for hop in hops.iter() {
+        if !SwapPairs::is_allowed(deps, &hop.pair_address)? {
+            return Err(StdError::generic_err(format!(
+                "Swapping through pair {} is not allowed.",
+                hop.pair_address
+            )));
+        }
+    }
+
+    let account = Account::new(&deps.api, &env.message.sender)?;
+    let shortfall = current_shortfall(deps, &account, &env.block)?;
+
+    PendingOperation {
+        account: account.0,
+        shortfall,
+    }
+    .save(&mut deps.storage)?;
+
+    // The minimum output is checked by the receiving market instead.
+    let (recipient, msg) = swap_destination(
+        Contracts::load_swap_router(deps)?,
+        hops,
+        to.contract.address.clone(),
+        None,
+    )?;
+    let balance_before = query_state(&deps.querier, to.contract.clone(), None, None)?
+        .underlying_balance;
+
+    Ok(vec![
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: from.contract.address,
+            callback_code_hash: from.contract.code_hash,
+            send: vec![],
+            msg: to_binary(&MarketHandleMsg::RedeemTo {
+                owner: env.message.sender.clone(),
+                burn_amount: amount,
+                recipient,
+                msg,
+            })?,
+        }),
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: to.contract.address,
+            callback_code_hash: to.contract.code_hash,
+            send: vec![],
+            msg: to_binary(&MarketHandleMsg::ApplySwapOutput {
+                balance_before: balance_before.into(),
+                min_amount: min_output,
+                action,
+            })?,
+        }),
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address.clone(),
+            callback_code_hash: env.contract_code_hash.clone(),
+            send: vec![],
+            msg: to_binary(&HandleMsg::FinishCollateralOperation {})?,
+        }),
+    ])
+}
+
+/// The shortfall of the account against its LTV or zero if it hasn't entered any markets.
+fn current_shortfall<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    account: &Account,
+    block: &BlockInfo,
+) -> StdResult<Uint256> {
+    if account.list_markets(deps)?.is_empty() {
+        return Ok(Uint256::zero());
+    }
+
+    let liquidity = calc_liquidity(
+        deps,
+        account,
+        MarketAuth::Internal {
+            key: MasterKey::load(&deps.storage)?,
+            address: account.0.borrow().humanize(&deps.api)?,
+        },
+        None,
+        Some(block.height),
+        Some(block.time),
+        Uint256::zero(),
+        Uint256::zero(),
+        CollateralRequirement::Ltv,
+    )?;
+
+    Ok(liquidity.shortfall)
+}
+
+fn assert_no_shortfall<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    account: &Account,
+) -> StdResult<()> {
+    let liquidity = calc_liquidity(
+        deps,
+        account,
+        MarketAuth::Internal {
+            key: MasterKey::load(&deps.storage)?,
+            address: account.0.borrow().humanize(&deps.api)?,
+        },
+        None,
+        None,
+        None,
+        Uint256::zero(),
+        Uint256::zero(),
+        CollateralRequirement::Ltv,
+    )?;
+
+    if liquidity.shortfall > Uint256::zero() {
+        return Err(StdError::generic_err(format!(
+            "This account is currently below its target collateral requirement by {}",
+            liquidity.shortfall
+        )));
+    }
+
+    Ok(())
+}
+
+/// Which ratio of an e-mode category the collateral is valued at.
+#[derive(Clone, Copy, PartialEq)]
+enum CollateralRequirement {
+    /// For actions that take on more risk, like borrowing, redeeming or opting into e-mode.
+    Ltv,
+    /// For determining whether the account can be liquidated.
+    LiquidationThreshold,
+}
+
 /// Determine what the account liquidity would be if the given amounts were redeemed/borrowed.
+/// If the account has opted into an e-mode category and all of its markets are in it, the
+/// category ratio that corresponds to the `requirement` is used instead of the market LTV.
 fn calc_liquidity<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     account: &Account,
     method: MarketAuth,
     target_asset: Option<HumanAddr>,
     block: Option<u64>,
+    time: Option<u64>,
     redeem_amount: Uint256,
     borrow_amount: Uint256,
+    requirement: CollateralRequirement,
 ) -> StdResult<AccountLiquidity> {
     const INVALID_PRICE_ERR: &str = "Invalid price reported by the oracle.";
 
@@ -547,6 +1618,7 @@ fn calc_liquidity<S: Storage, A: Api, Q: Querier>(
     let mut total_borrowed = Uint256::zero();
 
     let markets = account.list_markets(deps)?;
+    let isolated = account.isolated_market(deps)?;
 
     if markets.len() == 0 {
         return Err(StdError::generic_err("Not entered in any markets."));
@@ -565,11 +1637,41 @@ fn calc_liquidity<S: Storage, A: Api, Q: Querier>(
         HumanAddr::default()
     };
 
+    if !borrow_amount.is_zero()
+        && markets
+            .iter()
+            .any(|x| x.contract.address == target_asset && x.status != MarketStatus::Active)
+    {
+        return Err(StdError::generic_err("Cannot borrow from a deprecated market."));
+    }
+
+    let e_mode = match account.load_e_mode(&deps.storage)? {
+        Some(id) if markets.iter().all(|x| x.e_mode_category == Some(id)) => {
+            Some(EModeCategories::get(&deps.storage, id)?)
+        }
+        _ => None,
+    };
+
+    let mut borrow_value = Uint256::zero();
+
     for market in markets {
         let is_target_asset = target_asset == market.contract.address;
-        let is_zero_ltv = market.ltv_ratio.is_zero();
+        let ltv_ratio = match &e_mode {
+            Some(category) => match requirement {
+                CollateralRequirement::Ltv => category.ltv_ratio,
+                CollateralRequirement::LiquidationThreshold => category.liquidation_threshold,
+            },
+            None => market.ltv_ratio,
+        };
+        // Deprecated markets don't count as collateral for new borrows.
+        let ltv_ratio = if market.status != MarketStatus::Active && !borrow_amount.is_zero() {
+            Decimal256::zero()
+        } else {
+            ltv_ratio
+        };
+        let is_zero_ltv = ltv_ratio.is_zero();
 
-        let snapshot = query_account(&deps.querier, market.contract, method.clone(), block)?;
+        let snapshot = query_account(&deps.querier, market.contract, method.clone(), block, time)?;
         let price = query_price(
             &deps.querier,
             oracle.clone(),
@@ -579,20 +1681,21 @@ fn calc_liquidity<S: Storage, A: Api, Q: Querier>(
             None,
         )?;
 
-        let conversion_factor = ((market.ltv_ratio * snapshot.exchange_rate)? * price.rate)?;
+        let conversion_factor = ((ltv_ratio * snapshot.exchange_rate)? * price.rate)?;
 
         // Precalculate, so we can check if the price is valid.
         let redeem_amount_validated = redeem_amount.decimal_mul(conversion_factor)?;
         let borrow_amount_validated = borrow_amount.decimal_mul(price.rate)?;
         let sl_token_conversion = snapshot.sl_token_balance.decimal_mul(conversion_factor)?;
-        let borrow_conversion = snapshot.borrow_balance.decimal_mul(price.rate)?;
+        let borrow_balance = (snapshot.borrow_balance + snapshot.stable_borrow_balance)?;
+        let borrow_conversion = borrow_balance.decimal_mul(price.rate)?;
 
         // This check is needed to check validity of price
         // in case `redeem_amount` and `borrow_amount` are both 0.
         if !is_zero_ltv && !snapshot.sl_token_balance.is_zero() && sl_token_conversion.is_zero() {
             return Err(StdError::generic_err(INVALID_PRICE_ERR));
         }
-        if !is_zero_ltv && !snapshot.borrow_balance.is_zero() && borrow_conversion.is_zero() {
+        if !is_zero_ltv && !borrow_balance.is_zero() && borrow_conversion.is_zero() {
             return Err(StdError::generic_err(INVALID_PRICE_ERR));
         }
 
@@ -611,6 +1714,28 @@ fn calc_liquidity<S: Storage, A: Api, Q: Querier>(
             }
             total_borrowed = (redeem_amount_validated + total_borrowed)?;
             total_borrowed = (borrow_amount_validated + total_borrowed)?;
+
+            if market.borrowable_in_isolation {
+                borrow_value = borrow_amount_validated;
+            } else if !borrow_amount.is_zero() && isolated.is_some() {
+                return Err(StdError::generic_err(
+                    "Cannot borrow from this market while using isolated collateral.",
+                ));
+            }
+        }
+    }
+
+    if let Some((id, market)) = isolated {
+        if !borrow_value.is_zero() {
+            let debt_ceiling = market.isolated.unwrap().debt_ceiling;
+            let debt = (IsolatedDebt::load(&deps.storage, id)? + borrow_value)?;
+
+            if debt > debt_ceiling {
+                return Err(StdError::generic_err(format!(
+                    "Debt ceiling of isolated collateral {} reached.",
+                    market.symbol
+                )));
+            }
         }
     }
 
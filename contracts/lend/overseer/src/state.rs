@@ -1,16 +1,16 @@
 use lend_shared::{
-    impl_contract_storage,
+    impl_contract_storage, impl_contract_storage_option,
     fadroma::{
         cosmwasm_std::{
             HumanAddr, CanonicalAddr, Extern,
             StdResult, Api, Storage, Querier,
             StdError, ReadonlyStorage
         },
-        storage::{load, save, ns_load, ns_save, IterableStorage},
+        storage::{load, save, ns_load, ns_save, ns_remove, IterableStorage},
         Canonize, Humanize, ContractLink,
-        ContractInstantiationInfo, Decimal256
+        ContractInstantiationInfo, Decimal256, Uint256
     },
-    interfaces::overseer::{Market, Config},
+    interfaces::overseer::{Market, MarketStatus, Config, EModeCategory, RewardBalances},
     core::{AuthenticatedUser, Pagination}
 };
 use serde::{Deserialize, Serialize};
@@ -28,8 +28,54 @@ pub struct Constants {
 pub struct Contracts;
 pub struct Markets;
 
+pub struct Guardian;
+
+/// The pairs that collateral operations are allowed to swap through.
+pub struct SwapPairs;
+
 pub struct Whitelisting;
 
+pub struct IsolatedDebt;
+
+pub struct EModeCategories;
+
+pub struct Rewards;
+
+/// The distribution of rewards to one side (supply or borrow) of a market.
+#[derive(Serialize, Deserialize, Default)]
+pub struct RewardIndex {
+    /// Rewards distributed per block.
+    pub speed: Uint256,
+    /// Rewards distributed per unit of balance since the start.
+    pub index: Decimal256,
+    /// The total balance as last read from or reported by the market.
+    pub total: Uint256,
+    /// The block that the index was last updated at.
+    pub block: u64
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct MarketRewards {
+    pub supply: RewardIndex,
+    pub borrow: RewardIndex
+}
+
+/// The indices of a market that an account was last credited up to.
+/// The balances themselves are always taken from the market.
+#[derive(Serialize, Deserialize, Default)]
+pub struct AccountRewards {
+    pub supply_index: Decimal256,
+    pub borrow_index: Decimal256
+}
+
+/// A collateral operation that is currently in progress.
+#[derive(Serialize, Deserialize)]
+pub struct PendingOperation {
+    pub account: CanonicalAddr,
+    /// The shortfall of the account before the operation started.
+    pub shortfall: Uint256
+}
+
 #[derive(Clone)]
 pub struct Account(pub CanonicalAddr);
 
@@ -55,6 +101,90 @@ impl Constants {
 impl Contracts {
     impl_contract_storage!(save_oracle, load_oracle, b"oracle");
     impl_contract_storage!(save_self_ref, load_self_ref, b"self");
+    impl_contract_storage_option!(save_swap_router, load_swap_router, b"swap_router");
+    impl_contract_storage_option!(save_rewards_token, load_rewards_token, b"rewards_token");
+}
+
+impl PendingOperation {
+    const KEY: &'static [u8] = b"pending_operation";
+
+    #[inline]
+    pub fn save(&self, storage: &mut impl Storage) -> StdResult<()> {
+        save(storage, Self::KEY, self)
+    }
+
+    #[inline]
+    pub fn load(storage: &impl Storage) -> StdResult<Option<Self>> {
+        load(storage, Self::KEY)
+    }
+
+    #[inline]
+    pub fn remove(storage: &mut impl Storage) {
+        storage.remove(Self::KEY)
+    }
+}
+
+impl Guardian {
+    const KEY: &'static [u8] = b"guardian";
+
+    pub fn save<S: Storage, A: Api, Q: Querier>(
+        deps: &mut Extern<S, A, Q>,
+        guardian: Option<HumanAddr>
+    ) -> StdResult<()> {
+        match guardian {
+            Some(address) => {
+                let address = address.canonize(&deps.api)?;
+
+                save(&mut deps.storage, Self::KEY, &address)
+            },
+            None => {
+                deps.storage.remove(Self::KEY);
+
+                Ok(())
+            }
+        }
+    }
+
+    pub fn load<S: Storage, A: Api, Q: Querier>(
+        deps: &Extern<S, A, Q>
+    ) -> StdResult<Option<HumanAddr>> {
+        let result: Option<CanonicalAddr> = load(&deps.storage, Self::KEY)?;
+
+        match result {
+            Some(address) => Ok(Some(address.humanize(&deps.api)?)),
+            None => Ok(None)
+        }
+    }
+}
+
+impl SwapPairs {
+    const NS: &'static [u8] = b"swap_pairs";
+
+    pub fn set<S: Storage, A: Api, Q: Querier>(
+        deps: &mut Extern<S, A, Q>,
+        pair: &HumanAddr,
+        allowed: bool
+    ) -> StdResult<()> {
+        let pair = pair.canonize(&deps.api)?;
+
+        if allowed {
+            ns_save(&mut deps.storage, Self::NS, pair.as_slice(), &true)
+        } else {
+            ns_remove(&mut deps.storage, Self::NS, pair.as_slice());
+
+            Ok(())
+        }
+    }
+
+    pub fn is_allowed<S: Storage, A: Api, Q: Querier>(
+        deps: &Extern<S, A, Q>,
+        pair: &HumanAddr
+    ) -> StdResult<bool> {
+        let pair = pair.canonize(&deps.api)?;
+        let result: Option<bool> = ns_load(&deps.storage, Self::NS, pair.as_slice())?;
+
+        Ok(result.unwrap_or_default())
+    }
 }
 
 impl Whitelisting {
@@ -102,6 +232,7 @@ impl Whitelisting {
 
 impl Markets {
     const NS: &'static [u8] = b"markets";
+    const KEY_DELISTED: &'static [u8] = b"delisted_markets";
 
     pub fn push<S: Storage, A: Api, Q: Querier>(
         deps: &mut Extern<S, A, Q>,
@@ -186,6 +317,25 @@ impl Markets {
         Ok(())
     }
 
+    /// Removes the market from the address lookup and the list of markets.
+    /// Its entry is kept so that the ids of the rest of the markets don't change.
+    pub fn delist<S: Storage, A: Api, Q: Querier>(
+        deps: &mut Extern<S, A, Q>,
+        market: &HumanAddr
+    ) -> StdResult<()> {
+        Self::update(deps, market, |mut m| {
+            m.status = MarketStatus::Delisted;
+
+            Ok(m)
+        })?;
+
+        let market = market.canonize(&deps.api)?;
+        ns_remove(&mut deps.storage, Self::NS, market.as_slice());
+
+        let delisted = Self::load_delisted(&deps.storage)?;
+        save(&mut deps.storage, Self::KEY_DELISTED, &(delisted + 1))
+    }
+
     pub fn list<S: Storage, A: Api, Q: Querier>(
         deps: &Extern<S, A, Q>,
         pagination: Pagination
@@ -193,19 +343,57 @@ impl Markets {
         let limit = pagination.limit.min(PAGINATION_LIMIT);
 
         let storage = IterableStorage::new(Self::NS);
-        let iterator = storage
-            .iter(&deps.storage)?
-            .skip(pagination.start as usize)
-            .take(limit as usize);
+        let iterator = storage.iter(&deps.storage)?;
 
-        let mut result = Vec::with_capacity(iterator.len());
+        let mut result = Vec::with_capacity(limit as usize);
+        let mut skipped = 0;
 
         for elem in iterator {
+            if result.len() == limit as usize {
+                break;
+            }
+
             let elem: Market<CanonicalAddr> = elem?;
+
+            if elem.status == MarketStatus::Delisted {
+                continue;
+            }
+
+            if skipped < pagination.start {
+                skipped += 1;
+
+                continue;
+            }
+
             result.push(elem.humanize(&deps.api)?);
         }
 
-        Ok((storage.len(&deps.storage)? ,result))
+        let total = storage.len(&deps.storage)? - Self::load_delisted(&deps.storage)?;
+
+        Ok((total, result))
+    }
+
+    /// All markets that haven't been delisted.
+    pub fn list_all<S: Storage, A: Api, Q: Querier>(
+        deps: &Extern<S, A, Q>
+    ) -> StdResult<Vec<Market<HumanAddr>>> {
+        let storage = IterableStorage::new(Self::NS);
+        let mut result = vec![];
+
+        for elem in storage.iter(&deps.storage)? {
+            let elem: Market<CanonicalAddr> = elem?;
+
+            if elem.status != MarketStatus::Delisted {
+                result.push(elem.humanize(&deps.api)?);
+            }
+        }
+
+        Ok(result)
+    }
+
+    #[inline]
+    fn load_delisted(storage: &impl Storage) -> StdResult<u64> {
+        Ok(load(storage, Self::KEY_DELISTED)?.unwrap_or_default())
     }
 
     #[inline]
@@ -218,8 +406,217 @@ impl Markets {
     }
 }
 
+impl IsolatedDebt {
+    const NS: &'static [u8] = b"isolated_debt";
+
+    pub fn load(storage: &impl Storage, market: u64) -> StdResult<Uint256> {
+        Ok(ns_load(storage, Self::NS, &market.to_be_bytes())?.unwrap_or_default())
+    }
+
+    pub fn increase(
+        storage: &mut impl Storage,
+        market: u64,
+        amount: Uint256
+    ) -> StdResult<()> {
+        let debt = (Self::load(storage, market)? + amount)?;
+
+        ns_save(storage, Self::NS, &market.to_be_bytes(), &debt)
+    }
+
+    pub fn decrease(
+        storage: &mut impl Storage,
+        market: u64,
+        amount: Uint256
+    ) -> StdResult<()> {
+        let debt = (Self::load(storage, market)? - amount).unwrap_or_default();
+
+        ns_save(storage, Self::NS, &market.to_be_bytes(), &debt)
+    }
+}
+
+impl EModeCategories {
+    const NS: &'static [u8] = b"e_mode_categories";
+
+    pub fn push(
+        storage: &mut impl Storage,
+        label: String,
+        ltv_ratio: Decimal256,
+        liquidation_threshold: Decimal256
+    ) -> StdResult<u64> {
+        let mut categories = IterableStorage::new(Self::NS);
+
+        let category = EModeCategory {
+            id: categories.len(storage)?,
+            label,
+            ltv_ratio,
+            liquidation_threshold
+        };
+        category.validate()?;
+
+        categories.push(storage, &category)
+    }
+
+    pub fn get(storage: &impl Storage, id: u64) -> StdResult<EModeCategory> {
+        let result = IterableStorage::new(Self::NS).get_at(storage, id)?;
+
+        match result {
+            Some(category) => Ok(category),
+            None => Err(StdError::generic_err("E-mode category does not exist."))
+        }
+    }
+
+    pub fn update<F>(
+        storage: &mut impl Storage,
+        id: u64,
+        update: F
+    ) -> StdResult<()>
+        where F: FnOnce(EModeCategory) -> StdResult<EModeCategory>
+    {
+        // Check that it exists first.
+        Self::get(storage, id)?;
+
+        IterableStorage::new(Self::NS)
+            .update_at(storage, id, update)?;
+
+        Ok(())
+    }
+
+    pub fn list(
+        storage: &impl Storage,
+        pagination: Pagination
+    ) -> StdResult<(u64, Vec<EModeCategory>)> {
+        let limit = pagination.limit.min(PAGINATION_LIMIT);
+
+        let categories = IterableStorage::new(Self::NS);
+        let result = categories
+            .iter(storage)?
+            .skip(pagination.start as usize)
+            .take(limit as usize)
+            .collect::<StdResult<Vec<EModeCategory>>>()?;
+
+        Ok((categories.len(storage)?, result))
+    }
+}
+
+impl RewardIndex {
+    /// Distributes the rewards for the blocks elapsed since the last update.
+    pub fn accrue(&mut self, block: u64) -> StdResult<()> {
+        if block <= self.block {
+            return Ok(());
+        }
+
+        if !self.speed.is_zero() && !self.total.is_zero() {
+            let distributed = (self.speed * Uint256::from((block - self.block) as u128))?;
+
+            self.index = (self.index + Decimal256::from_ratio(distributed.0, self.total.0)?)?;
+        }
+
+        self.block = block;
+
+        Ok(())
+    }
+}
+
+impl MarketRewards {
+    #[inline]
+    pub fn accrue(&mut self, block: u64) -> StdResult<()> {
+        self.supply.accrue(block)?;
+        self.borrow.accrue(block)
+    }
+
+    #[inline]
+    pub fn set_totals(&mut self, balances: &RewardBalances) {
+        self.supply.total = balances.total_supply;
+        self.borrow.total = balances.total_borrows;
+    }
+}
+
+impl AccountRewards {
+    /// Returns the rewards earned on the given balances since the last update
+    /// and moves the indices of the account to those of the market. Accounts
+    /// that haven't been seen before earn since the speeds were first set.
+    pub fn accrue(
+        &mut self,
+        market: &MarketRewards,
+        balances: &RewardBalances
+    ) -> StdResult<Uint256> {
+        let supply = balances.sl_token_balance.decimal_mul(
+            (market.supply.index - self.supply_index)?
+        )?;
+        let borrow = balances.borrow_balance.decimal_mul(
+            (market.borrow.index - self.borrow_index)?
+        )?;
+
+        self.supply_index = market.supply.index;
+        self.borrow_index = market.borrow.index;
+
+        supply + borrow
+    }
+}
+
+impl Rewards {
+    const NS_MARKETS: &'static [u8] = b"market_rewards";
+    const NS_ACCOUNTS: &'static [u8] = b"account_rewards";
+    const NS_ACCRUED: &'static [u8] = b"accrued_rewards";
+
+    #[inline]
+    pub fn load_market(storage: &impl Storage, market: u64) -> StdResult<MarketRewards> {
+        Ok(ns_load(storage, Self::NS_MARKETS, &market.to_be_bytes())?.unwrap_or_default())
+    }
+
+    #[inline]
+    pub fn save_market(
+        storage: &mut impl Storage,
+        market: u64,
+        rewards: &MarketRewards
+    ) -> StdResult<()> {
+        ns_save(storage, Self::NS_MARKETS, &market.to_be_bytes(), rewards)
+    }
+
+    #[inline]
+    pub fn load_account(
+        storage: &impl Storage,
+        market: u64,
+        account: &Account
+    ) -> StdResult<AccountRewards> {
+        Ok(ns_load(storage, Self::NS_ACCOUNTS, &Self::account_key(market, account))?
+            .unwrap_or_default())
+    }
+
+    #[inline]
+    pub fn save_account(
+        storage: &mut impl Storage,
+        market: u64,
+        account: &Account,
+        rewards: &AccountRewards
+    ) -> StdResult<()> {
+        ns_save(storage, Self::NS_ACCOUNTS, &Self::account_key(market, account), rewards)
+    }
+
+    /// Rewards earned by the account that haven't been claimed yet.
+    #[inline]
+    pub fn load_accrued(storage: &impl Storage, account: &Account) -> StdResult<Uint256> {
+        Ok(ns_load(storage, Self::NS_ACCRUED, account.0.as_slice())?.unwrap_or_default())
+    }
+
+    #[inline]
+    pub fn save_accrued(
+        storage: &mut impl Storage,
+        account: &Account,
+        amount: &Uint256
+    ) -> StdResult<()> {
+        ns_save(storage, Self::NS_ACCRUED, account.0.as_slice(), amount)
+    }
+
+    fn account_key(market: u64, account: &Account) -> Vec<u8> {
+        [&market.to_be_bytes(), account.0.as_slice()].concat()
+    }
+}
+
 impl Account {
     const NS: &'static [u8] = b"accounts";
+    const NS_ISOLATED_DEBT: &'static [u8] = b"account_isolated_debt";
+    const NS_E_MODE: &'static [u8] = b"account_e_mode";
 
     pub fn new(
         api: &impl Api,
@@ -233,7 +630,16 @@ impl Account {
         storage: &mut S,
         ids: Vec<u64>
     ) -> StdResult<()> {
-        let mut markets = self.load_markets(storage)?;
+        let mut markets = Vec::with_capacity(MAX_MARKETS_ENTERED);
+
+        // Delisted markets don't take up a slot.
+        for id in self.load_markets(storage)? {
+            let market = Markets::load(&*storage, id)?.unwrap();
+
+            if market.status != MarketStatus::Delisted {
+                markets.push(id);
+            }
+        }
 
         if markets.len() + ids.len() > MAX_MARKETS_ENTERED {
             return Err(StdError::generic_err(format!(
@@ -292,12 +698,74 @@ impl Account {
         for id in markets {
             let market = Markets::get_by_id(deps, id)?.unwrap();
 
-            result.push(market);
+            if market.status != MarketStatus::Delisted {
+                result.push(market);
+            }
         }
 
         Ok(result)
     }
 
+    /// Returns the id and the market of the isolated collateral
+    /// that this account has entered, if any.
+    pub fn isolated_market<S: Storage, A: Api, Q: Querier>(
+        &self,
+        deps: &Extern<S, A, Q>
+    ) -> StdResult<Option<(u64, Market<HumanAddr>)>> {
+        for id in self.load_markets(&deps.storage)? {
+            let market = Markets::get_by_id(deps, id)?.unwrap();
+
+            if market.isolated.is_some() && market.status != MarketStatus::Delisted {
+                return Ok(Some((id, market)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// The debt taken by this account against isolated collateral
+    /// and the id of the isolated market that it was taken against.
+    pub fn load_isolated_debt(
+        &self,
+        storage: &impl ReadonlyStorage
+    ) -> StdResult<Option<(u64, Uint256)>> {
+        ns_load(storage, Self::NS_ISOLATED_DEBT, self.0.as_slice())
+    }
+
+    pub fn save_isolated_debt(
+        &self,
+        storage: &mut impl Storage,
+        market: u64,
+        amount: Uint256
+    ) -> StdResult<()> {
+        if amount.is_zero() {
+            ns_remove(storage, Self::NS_ISOLATED_DEBT, self.0.as_slice());
+
+            Ok(())
+        } else {
+            ns_save(storage, Self::NS_ISOLATED_DEBT, self.0.as_slice(), &(market, amount))
+        }
+    }
+
+    pub fn load_e_mode(&self, storage: &impl ReadonlyStorage) -> StdResult<Option<u64>> {
+        ns_load(storage, Self::NS_E_MODE, self.0.as_slice())
+    }
+
+    pub fn save_e_mode(
+        &self,
+        storage: &mut impl Storage,
+        category: Option<u64>
+    ) -> StdResult<()> {
+        match category {
+            Some(id) => ns_save(storage, Self::NS_E_MODE, self.0.as_slice(), &id),
+            None => {
+                ns_remove(storage, Self::NS_E_MODE, self.0.as_slice());
+
+                Ok(())
+            }
+        }
+    }
+
     #[inline]
     fn save_markets(
         &self,
@@ -0,0 +1,131 @@
+use std::str::FromStr;
+
+use lend_shared::{
+    fadroma::{
+        cosmwasm_std::{HumanAddr, Uint128},
+        ensemble::MockEnv,
+        ContractLink, Decimal256, Uint256, one_token
+    },
+    interfaces::{interest_model, market, overseer},
+};
+
+use crate::setup::{Lend, ADMIN};
+
+const BOB: &str = "Bob";
+const CHESTER: &str = "Chester";
+
+fn rate_at_target(lend: &Lend, market: &ContractLink<HumanAddr>) -> Decimal256 {
+    lend.ensemble
+        .query(
+            lend.interest_model.address.clone(),
+            interest_model::QueryMsg::RateAtTarget { market: market.address.clone() }
+        )
+        .unwrap()
+}
+
+#[test]
+fn rate_adapts_to_sustained_utilization() {
+    let mut lend = Lend::default();
+    lend.ensemble.block().freeze();
+
+    let underlying_1 = lend.new_underlying_token("SSCRT", 6).unwrap();
+    let underlying_2 = lend.new_underlying_token("SIENNA", 18).unwrap();
+
+    let market_one = lend
+        .whitelist_market(underlying_1, Decimal256::percent(90), None, None)
+        .unwrap()
+        .contract;
+
+    let market_two = lend
+        .whitelist_market(underlying_2, Decimal256::percent(90), None, None)
+        .unwrap()
+        .contract;
+
+    let update = interest_model::HandleMsg::UpdateAdaptiveConfig {
+        target_utilization: Decimal256::percent(50),
+        initial_rate_year: Decimal256::from_str("0.04").unwrap(),
+        min_rate_year: Decimal256::from_str("0.001").unwrap(),
+        max_rate_year: Decimal256::from_str("2").unwrap(),
+        adjustment_speed_year: Decimal256::from_str("50").unwrap(),
+        curve_steepness: Decimal256::from_str("4").unwrap(),
+        blocks_year: None,
+        stable_premium_year: None,
+        accrual_mode: None,
+    };
+
+    lend.ensemble
+        .execute(&update, MockEnv::new(BOB, lend.interest_model.clone()))
+        .unwrap_err();
+
+    lend.ensemble
+        .execute(&update, MockEnv::new(ADMIN, lend.interest_model.clone()))
+        .unwrap();
+
+    lend.prefund_and_deposit(BOB, Uint128(100 * one_token(6)), market_one.address.clone());
+    lend.prefund_and_deposit(CHESTER, Uint128(200 * one_token(18)), market_two.address.clone());
+
+    lend.ensemble
+        .execute(
+            &overseer::HandleMsg::Enter {
+                markets: vec![market_one.address.clone(), market_two.address.clone()],
+            },
+            MockEnv::new(CHESTER, lend.overseer.clone()),
+        )
+        .unwrap();
+
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::Borrow {
+                amount: Uint256::from(90 * one_token(6)),
+            },
+            MockEnv::new(CHESTER, market_one.clone()),
+        )
+        .unwrap();
+
+    let initial = rate_at_target(&lend, &market_one);
+    let rate_before: Decimal256 = lend.ensemble
+        .query(
+            market_one.address.clone(),
+            market::QueryMsg::BorrowRate { block: None, time: None },
+        )
+        .unwrap();
+
+    lend.ensemble.block().height += 10000;
+
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::AccrueInterest {},
+            MockEnv::new(BOB, market_one.clone()),
+        )
+        .unwrap();
+
+    // Utilization stayed at 90% which is above the 50% target.
+    assert!(rate_at_target(&lend, &market_one) > initial);
+
+    // Every market drifts separately.
+    assert_eq!(rate_at_target(&lend, &market_two), initial);
+
+    let rate_after: Decimal256 = lend.ensemble
+        .query(
+            market_one.address.clone(),
+            market::QueryMsg::BorrowRate { block: None, time: None },
+        )
+        .unwrap();
+
+    assert!(rate_after > rate_before);
+
+    // Nothing is borrowed from the second market, so its rate goes down
+    // while the rate of the first one isn't affected.
+    let current = rate_at_target(&lend, &market_one);
+    lend.ensemble.block().height += 10000;
+
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::AccrueInterest {},
+            MockEnv::new(BOB, market_two.clone()),
+        )
+        .unwrap();
+
+    assert!(rate_at_target(&lend, &market_two) < initial);
+    assert_eq!(rate_at_target(&lend, &market_one), current);
+}
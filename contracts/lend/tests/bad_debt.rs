@@ -0,0 +1,165 @@
+use lend_shared::{
+    fadroma::{
+        cosmwasm_std::{to_binary, StdError, Uint128},
+        ensemble::MockEnv,
+        snip20_impl::msg::HandleMsg as Snip20HandleMsg,
+        Decimal256, Uint256, one_token
+    },
+    interfaces::{market, overseer},
+};
+
+use crate::setup::Lend;
+
+const BOB: &str = "Bob";
+const ALICE: &str = "Alice";
+
+#[test]
+fn write_off_debt_without_collateral() {
+    let mut lend = Lend::default();
+    lend.ensemble.block().freeze();
+
+    let underlying_1 = lend.new_underlying_token("ONE", 18).unwrap();
+    let underlying_2 = lend.new_underlying_token("TWO", 18).unwrap();
+
+    let market_1 = lend
+        .whitelist_market(underlying_1, Decimal256::percent(75), None, None)
+        .unwrap();
+
+    let market_2 = lend
+        .whitelist_market(underlying_2.clone(), Decimal256::percent(75), None, None)
+        .unwrap();
+
+    lend.prefund_and_deposit(
+        BOB,
+        Uint128(2 * one_token(18)),
+        market_1.contract.address.clone(),
+    );
+    lend.prefund_and_deposit(
+        ALICE,
+        Uint128(2 * one_token(18)),
+        market_2.contract.address.clone(),
+    );
+    lend.prefund_user(ALICE, Uint128(1 * one_token(18)), underlying_2.clone());
+
+    for user in &[BOB, ALICE] {
+        lend.ensemble
+            .execute(
+                &overseer::HandleMsg::Enter {
+                    markets: vec![
+                        market_1.contract.address.clone(),
+                        market_2.contract.address.clone(),
+                    ],
+                },
+                MockEnv::new(*user, lend.overseer.clone()),
+            )
+            .unwrap();
+    }
+
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::Borrow {
+                amount: Uint256::from(1 * one_token(18)),
+            },
+            MockEnv::new(BOB, market_2.contract.clone()),
+        )
+        .unwrap();
+
+    let id = lend.id(BOB, market_2.contract.address.clone());
+
+    // crash the price of the collateral so that it's worth less than the debt
+    lend.set_oracle_price(market_1.symbol.as_bytes(), Uint128(25 * one_token(16)))
+        .unwrap();
+
+    let err = lend
+        .ensemble
+        .execute(
+            &market::HandleMsg::RealizeBadDebt { borrower: id.clone() },
+            MockEnv::new(ALICE, market_2.contract.clone()),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err(
+            "Borrower still has collateral and their debt cannot be written off."
+        )
+    );
+
+    // seizes all of the collateral
+    lend.ensemble
+        .execute(
+            &Snip20HandleMsg::Send {
+                recipient: market_2.contract.address.clone(),
+                recipient_code_hash: None,
+                amount: Uint128(5 * one_token(17)),
+                msg: Some(
+                    to_binary(&market::ReceiverCallbackMsg::Liquidate {
+                        borrower: id.clone(),
+                        collateral: market_1.contract.address.clone(),
+                    })
+                    .unwrap(),
+                ),
+                memo: None,
+                padding: None,
+            },
+            MockEnv::new(ALICE, underlying_2),
+        )
+        .unwrap();
+
+    let info = lend.account_info(BOB, market_1.contract.address.clone());
+    assert_eq!(info.sl_token_balance, Uint256::zero());
+
+    let exchange_rate: Decimal256 = lend
+        .ensemble
+        .query(
+            market_2.contract.address.clone(),
+            market::QueryMsg::ExchangeRate { block: None, time: None },
+        )
+        .unwrap();
+    assert_eq!(exchange_rate, Decimal256::one());
+
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::RealizeBadDebt { borrower: id.clone() },
+            MockEnv::new(ALICE, market_2.contract.clone()),
+        )
+        .unwrap();
+
+    let info = lend.account_info(BOB, market_2.contract.address.clone());
+    assert_eq!(info.borrow_balance, Uint256::zero());
+
+    let bad_debt: market::BadDebt = lend
+        .ensemble
+        .query(market_2.contract.address.clone(), market::QueryMsg::BadDebt {})
+        .unwrap();
+    assert_eq!(bad_debt.total, Uint256::from(5 * one_token(17)));
+    assert_eq!(bad_debt.covered_by_reserves, Uint256::zero());
+    assert_eq!(bad_debt.socialized, Uint256::from(5 * one_token(17)));
+
+    // no reserves, so suppliers absorb the loss
+    let exchange_rate: Decimal256 = lend
+        .ensemble
+        .query(
+            market_2.contract.address.clone(),
+            market::QueryMsg::ExchangeRate { block: None, time: None },
+        )
+        .unwrap();
+    assert_eq!(exchange_rate, Decimal256::percent(75));
+
+    let history = lend.history(BOB, market_2.contract.address.clone());
+    assert_eq!(
+        history.entries.last().unwrap().event,
+        market::HistoryEvent::BadDebt {
+            amount: Uint256::from(5 * one_token(17)),
+            covered_by_reserves: Uint256::zero(),
+        }
+    );
+
+    let err = lend
+        .ensemble
+        .execute(
+            &market::HandleMsg::RealizeBadDebt { borrower: id },
+            MockEnv::new(ALICE, market_2.contract.clone()),
+        )
+        .unwrap_err();
+    assert_eq!(err, StdError::generic_err("Borrower has no outstanding debt."));
+}
@@ -107,7 +107,7 @@ fn borrow() {
         .ensemble
         .query(
             market_one.address.clone(),
-            market::QueryMsg::State { block: None },
+            market::QueryMsg::State { block: None, time: None },
         )
         .unwrap();
 
@@ -133,6 +133,7 @@ fn borrow() {
                     limit: 10
                 },
                 block: state.accrual_block,
+                time: None,
             },
         )
         .unwrap();
@@ -341,6 +342,7 @@ fn partial_repay() {
                     limit: 10
                 },
                 block: market_one_state.accrual_block,
+                time: None,
             },
         )
         .unwrap();
@@ -496,6 +498,7 @@ fn do_repay(self_repay: bool, overpay: Uint128) {
                     limit: 10
                 },
                 block: market_one_state.accrual_block,
+                time: None,
             },
         )
         .unwrap();
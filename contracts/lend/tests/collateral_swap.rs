@@ -0,0 +1,303 @@
+use lend_shared::{
+    fadroma::{
+        cosmwasm_std::{to_binary, CosmosMsg, StdError, Uint128, WasmMsg},
+        ensemble::{ContractHarness, MockDeps, MockEnv},
+        snip20_impl::msg::HandleMsg as Snip20HandleMsg,
+        from_binary, Binary, ContractLink, Decimal256, Env, HandleResponse,
+        HumanAddr, InitResponse, StdResult, Uint256, one_token
+    },
+    interfaces::{
+        market, overseer,
+        swap::{Hop, Snip20Swap, TokenType},
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::setup::{Lend, ADMIN};
+
+const BOB: &str = "Bob";
+const ALICE: &str = "Alice";
+
+#[derive(Serialize, Deserialize)]
+struct PairInit {
+    output: ContractLink<HumanAddr>,
+    rate: Decimal256,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum PairHandle {
+    Receive {
+        amount: Uint128,
+        msg: Option<Binary>,
+    },
+}
+
+/// Swaps anything it receives into the `output` token at a fixed `rate`.
+struct MockPair;
+
+impl ContractHarness for MockPair {
+    fn init(&self, deps: &mut MockDeps, _env: Env, msg: Binary) -> StdResult<InitResponse> {
+        let msg: PairInit = from_binary(&msg)?;
+        deps.set(b"config", msg)?;
+
+        Ok(InitResponse::default())
+    }
+
+    fn handle(&self, deps: &mut MockDeps, _env: Env, msg: Binary) -> StdResult<HandleResponse> {
+        let PairHandle::Receive { amount, msg } = from_binary(&msg)?;
+        let Snip20Swap::Swap { to, expected_return } = from_binary(&msg.unwrap())?;
+
+        let config: PairInit = deps.get(b"config")?.unwrap();
+        let output = Uint256::from(amount).decimal_mul(config.rate)?;
+
+        if let Some(expected_return) = expected_return {
+            if output < Uint256::from(expected_return) {
+                return Err(StdError::generic_err("Operation fell short of expected_return"));
+            }
+        }
+
+        Ok(HandleResponse {
+            messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: config.output.address,
+                callback_code_hash: config.output.code_hash,
+                msg: to_binary(&Snip20HandleMsg::Transfer {
+                    recipient: to.unwrap(),
+                    amount: output.low_u128().into(),
+                    memo: None,
+                    padding: None,
+                })?,
+                send: vec![],
+            })],
+            log: vec![],
+            data: None,
+        })
+    }
+
+    fn query(&self, _deps: &MockDeps, _msg: Binary) -> StdResult<Binary> {
+        Err(StdError::generic_err("Not Implemented"))
+    }
+}
+
+pub(crate) fn new_pair(
+    lend: &mut Lend,
+    name: &str,
+    input: &ContractLink<HumanAddr>,
+    output: &ContractLink<HumanAddr>,
+    rate: Decimal256,
+) -> Hop {
+    let pair = lend.ensemble.register(Box::new(MockPair));
+    let pair = lend
+        .ensemble
+        .instantiate(
+            pair.id,
+            &PairInit {
+                output: output.clone(),
+                rate,
+            },
+            MockEnv::new(
+                ADMIN,
+                ContractLink {
+                    address: name.into(),
+                    code_hash: pair.code_hash,
+                },
+            ),
+        )
+        .unwrap();
+
+    lend.prefund_user(pair.address.clone(), Uint128(100 * one_token(18)), output.clone());
+
+    Hop {
+        from_token: TokenType::CustomToken {
+            contract_addr: input.address.clone(),
+            token_code_hash: input.code_hash.clone(),
+        },
+        pair_address: pair.address,
+        pair_code_hash: pair.code_hash,
+    }
+}
+
+#[test]
+fn repay_with_collateral_and_swap_collateral() {
+    let mut lend = Lend::default();
+    lend.ensemble.block().freeze();
+
+    let underlying_1 = lend.new_underlying_token("ONE", 18).unwrap();
+    let underlying_2 = lend.new_underlying_token("TWO", 18).unwrap();
+
+    let market_1 = lend
+        .whitelist_market(underlying_1.clone(), Decimal256::percent(75), None, None)
+        .unwrap()
+        .contract;
+
+    let market_2 = lend
+        .whitelist_market(underlying_2.clone(), Decimal256::percent(75), None, None)
+        .unwrap()
+        .contract;
+
+    let hop = new_pair(&mut lend, "pair", &underlying_1, &underlying_2, Decimal256::one());
+    let bad_hop = new_pair(
+        &mut lend,
+        "bad_pair",
+        &underlying_1,
+        &underlying_2,
+        Decimal256::percent(10),
+    );
+
+    lend.prefund_and_deposit(BOB, Uint128(10 * one_token(18)), market_1.address.clone());
+    lend.prefund_and_deposit(ALICE, Uint128(10 * one_token(18)), market_2.address.clone());
+
+    lend.ensemble
+        .execute(
+            &overseer::HandleMsg::Enter {
+                markets: vec![market_1.address.clone(), market_2.address.clone()],
+            },
+            MockEnv::new(BOB, lend.overseer.clone()),
+        )
+        .unwrap();
+
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::Borrow {
+                amount: Uint256::from(7 * one_token(18)),
+            },
+            MockEnv::new(BOB, market_2.clone()),
+        )
+        .unwrap();
+
+    let repay = |min_output: u128| overseer::HandleMsg::RepayWithCollateral {
+        collateral: market_1.address.clone(),
+        amount: Uint256::from(2 * one_token(18)),
+        borrowed: market_2.address.clone(),
+        hops: vec![hop.clone()],
+        min_output: Uint256::from(min_output),
+    };
+
+    // pairs have to be allowed by the admin first
+    let err = lend
+        .ensemble
+        .execute(&repay(2 * one_token(18)), MockEnv::new(BOB, lend.overseer.clone()))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("Swapping through pair pair is not allowed.")
+    );
+
+    let err = lend
+        .ensemble
+        .execute(
+            &overseer::HandleMsg::SetSwapPairs {
+                pairs: vec![hop.pair_address.clone()],
+                allowed: true,
+            },
+            MockEnv::new(BOB, lend.overseer.clone()),
+        )
+        .unwrap_err();
+    assert_eq!(err, StdError::unauthorized());
+
+    lend.ensemble
+        .execute(
+            &overseer::HandleMsg::SetSwapPairs {
+                pairs: vec![hop.pair_address.clone(), bad_hop.pair_address.clone()],
+                allowed: true,
+            },
+            MockEnv::new(ADMIN, lend.overseer.clone()),
+        )
+        .unwrap();
+
+    let err = lend
+        .ensemble
+        .execute(&repay(3 * one_token(18)), MockEnv::new(BOB, lend.overseer.clone()))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err(
+            "Swap output is less than the minimum. Output: 2000000000000000000, Minimum: 3000000000000000000"
+        )
+    );
+
+    lend.ensemble
+        .execute(&repay(2 * one_token(18)), MockEnv::new(BOB, lend.overseer.clone()))
+        .unwrap();
+
+    let info = lend.account_info(BOB, market_1.address.clone());
+    assert_eq!(info.sl_token_balance, Uint256::from(8 * one_token(18)));
+
+    let info = lend.account_info(BOB, market_2.address.clone());
+    assert_eq!(info.borrow_balance, Uint256::from(5 * one_token(18)));
+
+    // move half of the remaining collateral into the other market
+    lend.ensemble
+        .execute(
+            &overseer::HandleMsg::SwapCollateral {
+                from: market_1.address.clone(),
+                amount: Uint256::from(4 * one_token(18)),
+                to: market_2.address.clone(),
+                hops: vec![hop.clone()],
+                min_output: Uint256::from(4 * one_token(18)),
+            },
+            MockEnv::new(BOB, lend.overseer.clone()),
+        )
+        .unwrap();
+
+    let info = lend.account_info(BOB, market_1.address.clone());
+    assert_eq!(info.sl_token_balance, Uint256::from(4 * one_token(18)));
+
+    let info = lend.account_info(BOB, market_2.address.clone());
+    assert_eq!(info.sl_token_balance, Uint256::from(4 * one_token(18)));
+
+    // a bad swap would leave the account without enough collateral
+    let err = lend
+        .ensemble
+        .execute(
+            &overseer::HandleMsg::SwapCollateral {
+                from: market_1.address.clone(),
+                amount: Uint256::from(4 * one_token(18)),
+                to: market_2.address.clone(),
+                hops: vec![bad_hop],
+                min_output: Uint256::zero(),
+            },
+            MockEnv::new(BOB, lend.overseer.clone()),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err(
+            "Operation would leave the account below its target collateral requirement by 1700000000000000000"
+        )
+    );
+
+    let info = lend.account_info(BOB, market_1.address.clone());
+    assert_eq!(info.sl_token_balance, Uint256::from(4 * one_token(18)));
+
+    // markets only accept these from the overseer
+    let err = lend
+        .ensemble
+        .execute(
+            &market::HandleMsg::RedeemTo {
+                owner: BOB.into(),
+                burn_amount: Uint256::from(4 * one_token(18)),
+                recipient: lend.overseer.clone(),
+                msg: Binary::default(),
+            },
+            MockEnv::new(ALICE, market_1.clone()),
+        )
+        .unwrap_err();
+    assert_eq!(err, StdError::unauthorized());
+
+    // routes longer than a single pair need the router
+    let err = lend
+        .ensemble
+        .execute(
+            &overseer::HandleMsg::SwapCollateral {
+                from: market_1.address.clone(),
+                amount: Uint256::from(1 * one_token(18)),
+                to: market_2.address.clone(),
+                hops: vec![hop.clone(), hop],
+                min_output: Uint256::zero(),
+            },
+            MockEnv::new(BOB, lend.overseer.clone()),
+        )
+        .unwrap_err();
+    assert_eq!(err, StdError::generic_err("Swap router is not configured."));
+}
@@ -0,0 +1,189 @@
+use lend_shared::{
+    fadroma::{
+        cosmwasm_std::{to_binary, HumanAddr, StdError, StdResult, Uint128},
+        ensemble::MockEnv,
+        permit::Permit,
+        snip20_impl::msg as snip20,
+        Decimal256, Uint256, one_token
+    },
+    interfaces::{market, overseer},
+};
+
+use crate::setup::{Lend, ADMIN};
+
+const BOB: &str = "Bob";
+const ALICE: &str = "Alice";
+const CHESTER: &str = "Chester";
+
+#[test]
+fn deprecate_and_delist_market() {
+    let mut lend = Lend::default();
+    lend.ensemble.block().freeze();
+
+    let underlying_1 = lend.new_underlying_token("SSCRT", 18).unwrap();
+    let underlying_2 = lend.new_underlying_token("SIENNA", 18).unwrap();
+
+    let market_one = lend
+        .whitelist_market(underlying_1.clone(), Decimal256::percent(90), None, None)
+        .unwrap()
+        .contract;
+
+    let market_two = lend
+        .whitelist_market(underlying_2.clone(), Decimal256::percent(90), None, None)
+        .unwrap()
+        .contract;
+
+    lend.prefund_and_deposit(BOB, Uint128(100 * one_token(18)), market_one.address.clone());
+    lend.prefund_and_deposit(CHESTER, Uint128(100 * one_token(18)), market_two.address.clone());
+
+    lend.ensemble
+        .execute(
+            &overseer::HandleMsg::Enter {
+                markets: vec![market_one.address.clone(), market_two.address.clone()],
+            },
+            MockEnv::new(CHESTER, lend.overseer.clone()),
+        )
+        .unwrap();
+
+    let borrow_amount = Uint256::from(10 * one_token(18));
+
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::Borrow { amount: borrow_amount },
+            MockEnv::new(CHESTER, market_one.clone()),
+        )
+        .unwrap();
+
+    let deprecate = overseer::HandleMsg::DeprecateMarket {
+        market: market_two.address.clone(),
+    };
+
+    lend.ensemble
+        .execute(&deprecate, MockEnv::new(BOB, lend.overseer.clone()))
+        .unwrap_err();
+
+    lend.ensemble
+        .execute(&deprecate, MockEnv::new(ADMIN, lend.overseer.clone()))
+        .unwrap();
+
+    let err = lend.ensemble
+        .execute(
+            &overseer::HandleMsg::DelistMarket {
+                market: market_one.address.clone(),
+            },
+            MockEnv::new(ADMIN, lend.overseer.clone()),
+        )
+        .unwrap_err();
+
+    assert_eq!(err, StdError::generic_err("Only deprecated markets can be delisted."));
+
+    let delist = overseer::HandleMsg::DelistMarket {
+        market: market_two.address.clone(),
+    };
+
+    let err = lend.ensemble
+        .execute(&delist, MockEnv::new(ADMIN, lend.overseer.clone()))
+        .unwrap_err();
+
+    assert_eq!(err, StdError::generic_err("Cannot delist a market that has supply or outstanding borrows."));
+
+    // No new deposits.
+    lend.prefund_user(ALICE, Uint128(one_token(18)), underlying_2.clone());
+
+    lend.ensemble
+        .execute(
+            &snip20::HandleMsg::Send {
+                recipient: market_two.address.clone(),
+                recipient_code_hash: None,
+                amount: Uint128(one_token(18)),
+                msg: Some(to_binary(&market::ReceiverCallbackMsg::Deposit).unwrap()),
+                memo: None,
+                padding: None,
+            },
+            MockEnv::new(ALICE, underlying_2),
+        )
+        .unwrap_err();
+
+    let err = lend.ensemble
+        .execute(
+            &overseer::HandleMsg::Enter {
+                markets: vec![market_two.address.clone()],
+            },
+            MockEnv::new(ALICE, lend.overseer.clone()),
+        )
+        .unwrap_err();
+
+    assert_eq!(err, StdError::generic_err(format!(
+        "Cannot enter deprecated market: {}",
+        market_two.address
+    )));
+
+    // The deprecated market doesn't count as collateral for new borrows.
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::Borrow { amount: borrow_amount },
+            MockEnv::new(CHESTER, market_one.clone()),
+        )
+        .unwrap_err();
+
+    // But it still does for the existing ones.
+    let liquidity = lend
+        .get_liquidity(CHESTER, None, Uint256::zero(), Uint256::zero(), None)
+        .unwrap();
+    assert_eq!(liquidity.shortfall, Uint256::zero());
+
+    // Wind down the position.
+    lend.ensemble
+        .execute(
+            &snip20::HandleMsg::Send {
+                recipient: market_one.address.clone(),
+                recipient_code_hash: None,
+                amount: Uint128(10 * one_token(18)),
+                msg: Some(to_binary(&market::ReceiverCallbackMsg::Repay { borrower: None }).unwrap()),
+                memo: None,
+                padding: None,
+            },
+            MockEnv::new(CHESTER, underlying_1),
+        )
+        .unwrap();
+
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::RedeemToken {
+                burn_amount: Uint256::from(100 * one_token(18)),
+            },
+            MockEnv::new(CHESTER, market_two.clone()),
+        )
+        .unwrap();
+
+    lend.ensemble
+        .execute(&delist, MockEnv::new(ADMIN, lend.overseer.clone()))
+        .unwrap();
+
+    let markets = lend.get_markets().unwrap();
+    assert_eq!(markets.len(), 1);
+    assert_eq!(markets[0].contract.address, market_one.address);
+
+    let result: StdResult<overseer::Market<HumanAddr>> = lend.ensemble.query(
+        lend.overseer.address.clone(),
+        overseer::QueryMsg::Market { address: market_two.address },
+    );
+    assert!(result.is_err());
+
+    let entered: Vec<overseer::Market<HumanAddr>> = lend.ensemble
+        .query(
+            lend.overseer.address.clone(),
+            overseer::QueryMsg::EnteredMarkets {
+                method: Permit::<overseer::OverseerPermissions>::new(
+                    CHESTER,
+                    vec![overseer::OverseerPermissions::AccountInfo],
+                    vec![lend.overseer.address.clone()],
+                    "entered",
+                )
+                .into(),
+            },
+        )
+        .unwrap();
+
+    assert_eq!(entered.len(), 1);
+}
@@ -72,6 +72,7 @@ fn deposit_and_mint() {
                 )
                 .into(),
                 block: None,
+                time: None,
             },
         )
         .unwrap();
@@ -155,6 +156,7 @@ fn redeem_basic() {
                 )
                 .into(),
                 block: None,
+                time: None,
             },
         )
         .unwrap();
@@ -224,6 +226,7 @@ fn redeem_underlying_basic() {
                 )
                 .into(),
                 block: None,
+                time: None,
             },
         )
         .unwrap();
@@ -276,6 +279,7 @@ fn redeem_partial() {
                 )
                 .into(),
                 block: None,
+                time: None,
             },
         )
         .unwrap();
@@ -306,6 +310,7 @@ fn redeem_partial() {
                 )
                 .into(),
                 block: None,
+                time: None,
             },
         )
         .unwrap();
@@ -0,0 +1,200 @@
+use lend_shared::{
+    fadroma::{
+        cosmwasm_std::{StdError, Uint128},
+        ensemble::MockEnv,
+        permit::Permit,
+        Decimal256, Uint256, one_token
+    },
+    interfaces::{market, overseer},
+    core::Pagination
+};
+
+use crate::setup::{Lend, ADMIN};
+
+const BOB: &str = "Bob";
+const CHESTER: &str = "Chester";
+
+#[test]
+fn e_mode_uses_category_parameters() {
+    let mut lend = Lend::default();
+    lend.ensemble.block().freeze();
+
+    let usdc = lend.new_underlying_token("SUSDC", 18).unwrap();
+    let usdt = lend.new_underlying_token("SUSDT", 18).unwrap();
+    let scrt = lend.new_underlying_token("SSCRT", 18).unwrap();
+
+    let usdc_market = lend
+        .whitelist_market(usdc, Decimal256::percent(50), None, None)
+        .unwrap()
+        .contract;
+    let usdt_market = lend
+        .whitelist_market(usdt, Decimal256::percent(50), None, None)
+        .unwrap()
+        .contract;
+    let scrt_market = lend
+        .whitelist_market(scrt, Decimal256::percent(50), None, None)
+        .unwrap()
+        .contract;
+
+    let add_category = overseer::HandleMsg::AddEModeCategory {
+        label: "Stablecoins".into(),
+        ltv_ratio: Decimal256::percent(90),
+        liquidation_threshold: Decimal256::percent(95),
+    };
+
+    lend.ensemble
+        .execute(&add_category, MockEnv::new(BOB, lend.overseer.clone()))
+        .unwrap_err();
+
+    lend.ensemble
+        .execute(&add_category, MockEnv::new(ADMIN, lend.overseer.clone()))
+        .unwrap();
+
+    let err = lend.ensemble
+        .execute(
+            &overseer::HandleMsg::AddEModeCategory {
+                label: "Invalid".into(),
+                ltv_ratio: Decimal256::percent(95),
+                liquidation_threshold: Decimal256::percent(90),
+            },
+            MockEnv::new(ADMIN, lend.overseer.clone()),
+        )
+        .unwrap_err();
+
+    assert_eq!(err, StdError::generic_err("LTV ratio cannot be greater than the liquidation threshold."));
+
+    let categories: overseer::EModeCategoriesResponse = lend.ensemble
+        .query(
+            lend.overseer.address.clone(),
+            overseer::QueryMsg::EModeCategories {
+                pagination: Pagination { start: 0, limit: 10 },
+            },
+        )
+        .unwrap();
+
+    assert_eq!(categories.total, 1);
+    let category = categories.entries[0].id;
+
+    for market in &[usdc_market.address.clone(), usdt_market.address.clone()] {
+        lend.ensemble
+            .execute(
+                &overseer::HandleMsg::SetMarketEModeCategory {
+                    market: market.clone(),
+                    category: Some(category),
+                },
+                MockEnv::new(ADMIN, lend.overseer.clone()),
+            )
+            .unwrap();
+    }
+
+    lend.prefund_and_deposit(BOB, Uint128(500 * one_token(18)), usdt_market.address.clone());
+    lend.prefund_and_deposit(CHESTER, Uint128(100 * one_token(18)), usdc_market.address.clone());
+
+    lend.ensemble
+        .execute(
+            &overseer::HandleMsg::Enter {
+                markets: vec![usdc_market.address.clone(), usdt_market.address.clone()],
+            },
+            MockEnv::new(CHESTER, lend.overseer.clone()),
+        )
+        .unwrap();
+
+    let borrow = market::HandleMsg::Borrow {
+        amount: Uint256::from(80 * one_token(18)),
+    };
+
+    // Only 50 can be borrowed without e-mode.
+    lend.ensemble
+        .execute(&borrow, MockEnv::new(CHESTER, usdt_market.clone()))
+        .unwrap_err();
+
+    lend.ensemble
+        .execute(
+            &overseer::HandleMsg::SetEMode { category: Some(category) },
+            MockEnv::new(CHESTER, lend.overseer.clone()),
+        )
+        .unwrap();
+
+    let e_mode: Option<u64> = lend.ensemble
+        .query(
+            lend.overseer.address.clone(),
+            overseer::QueryMsg::EMode {
+                method: Permit::<overseer::OverseerPermissions>::new(
+                    CHESTER,
+                    vec![overseer::OverseerPermissions::AccountInfo],
+                    vec![lend.overseer.address.clone()],
+                    "e_mode",
+                )
+                .into(),
+            },
+        )
+        .unwrap();
+
+    assert_eq!(e_mode, Some(category));
+
+    lend.ensemble
+        .execute(&borrow, MockEnv::new(CHESTER, usdt_market.clone()))
+        .unwrap();
+
+    // The liquidation threshold determines the account standing.
+    let liquidity = lend
+        .get_liquidity(CHESTER, None, Uint256::zero(), Uint256::zero(), None)
+        .unwrap();
+    assert_eq!(liquidity.liquidity, Uint256::from(15 * one_token(18)));
+
+    // While the LTV ratio applies to further borrows.
+    let liquidity = lend
+        .get_liquidity(
+            CHESTER,
+            Some(usdt_market.address.clone()),
+            Uint256::zero(),
+            Uint256::from(11 * one_token(18)),
+            None
+        )
+        .unwrap();
+    assert_eq!(liquidity.shortfall, Uint256::from(1 * one_token(18)));
+
+    // Opting into a category requires clearing its LTV ratio, not just its liquidation threshold.
+    lend.ensemble
+        .execute(
+            &overseer::HandleMsg::UpdateEModeCategory {
+                id: category,
+                ltv_ratio: Some(Decimal256::percent(75)),
+                liquidation_threshold: None,
+            },
+            MockEnv::new(ADMIN, lend.overseer.clone()),
+        )
+        .unwrap();
+
+    let err = lend.ensemble
+        .execute(
+            &overseer::HandleMsg::SetEMode { category: Some(category) },
+            MockEnv::new(CHESTER, lend.overseer.clone()),
+        )
+        .unwrap_err();
+
+    assert_eq!(err, StdError::generic_err(
+        "This account is currently below its target collateral requirement by 5000000000000000000"
+    ));
+
+    let err = lend.ensemble
+        .execute(
+            &overseer::HandleMsg::SetEMode { category: None },
+            MockEnv::new(CHESTER, lend.overseer.clone()),
+        )
+        .unwrap_err();
+
+    assert_eq!(err, StdError::generic_err(
+        "This account is currently below its target collateral requirement by 30000000000000000000"
+    ));
+
+    // Entering a market outside of the category would disable e-mode.
+    lend.ensemble
+        .execute(
+            &overseer::HandleMsg::Enter {
+                markets: vec![scrt_market.address],
+            },
+            MockEnv::new(CHESTER, lend.overseer.clone()),
+        )
+        .unwrap_err();
+}
@@ -45,7 +45,7 @@ fn initial_exchange_rate_mint() {
         .ensemble
         .query(
             market.contract.address.clone(),
-            market::QueryMsg::State { block: None },
+            market::QueryMsg::State { block: None, time: None },
         )
         .unwrap();
 
@@ -148,6 +148,7 @@ fn accrue_interest_basic() {
                 )
                 .into(),
                 block: None,
+                time: None,
             },
         )
         .unwrap();
@@ -167,6 +168,7 @@ fn accrue_interest_basic() {
                 )
                 .into(),
                 block: Some(112345),
+                time: None,
             },
         )
         .unwrap();
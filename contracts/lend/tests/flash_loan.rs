@@ -0,0 +1,330 @@
+use std::str::FromStr;
+
+use lend_shared::{
+    fadroma::{
+        cosmwasm_std::{to_binary, CosmosMsg, StdError, Uint128, WasmMsg},
+        ensemble::{ContractHarness, MockDeps, MockEnv},
+        snip20_impl::msg::HandleMsg as Snip20HandleMsg,
+        from_binary, Binary, Callback, ContractLink, Decimal256, Env,
+        HandleResponse, HumanAddr, InitResponse, StdResult, Uint256, one_token
+    },
+    interfaces::{market, overseer},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::setup::{Lend, ADMIN};
+
+const BOB: &str = "Bob";
+const ALICE: &str = "Alice";
+
+#[derive(Serialize, Deserialize)]
+struct PayBack {
+    token: ContractLink<HumanAddr>,
+    market: HumanAddr,
+    amount: Uint128,
+}
+
+/// Transfers the given amount back to the market when called.
+struct FlashBorrower;
+
+impl ContractHarness for FlashBorrower {
+    fn init(&self, _deps: &mut MockDeps, _env: Env, _msg: Binary) -> StdResult<InitResponse> {
+        Ok(InitResponse::default())
+    }
+
+    fn handle(&self, _deps: &mut MockDeps, _env: Env, msg: Binary) -> StdResult<HandleResponse> {
+        let msg: PayBack = from_binary(&msg)?;
+
+        Ok(HandleResponse {
+            messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: msg.token.address,
+                callback_code_hash: msg.token.code_hash,
+                msg: to_binary(&Snip20HandleMsg::Transfer {
+                    recipient: msg.market,
+                    amount: msg.amount,
+                    memo: None,
+                    padding: None,
+                })?,
+                send: vec![],
+            })],
+            log: vec![],
+            data: None,
+        })
+    }
+
+    fn query(&self, _deps: &MockDeps, _msg: Binary) -> StdResult<Binary> {
+        Err(StdError::generic_err("Not Implemented"))
+    }
+}
+
+/// Executes the messages it is called with.
+struct Relay;
+
+impl ContractHarness for Relay {
+    fn init(&self, _deps: &mut MockDeps, _env: Env, _msg: Binary) -> StdResult<InitResponse> {
+        Ok(InitResponse::default())
+    }
+
+    fn handle(&self, _deps: &mut MockDeps, _env: Env, msg: Binary) -> StdResult<HandleResponse> {
+        let messages: Vec<CosmosMsg> = from_binary(&msg)?;
+
+        Ok(HandleResponse {
+            messages,
+            log: vec![],
+            data: None,
+        })
+    }
+
+    fn query(&self, _deps: &MockDeps, _msg: Binary) -> StdResult<Binary> {
+        Err(StdError::generic_err("Not Implemented"))
+    }
+}
+
+#[test]
+fn flash_loan() {
+    let mut lend = Lend::default();
+    lend.ensemble.block().freeze();
+
+    let underlying = lend.new_underlying_token("SSCRT", 18).unwrap();
+    let market = lend
+        .whitelist_market(
+            underlying.clone(),
+            Decimal256::percent(75),
+            None,
+            Some(Decimal256::percent(50)),
+        )
+        .unwrap()
+        .contract;
+
+    let borrower = lend.ensemble.register(Box::new(FlashBorrower));
+    let borrower = lend
+        .ensemble
+        .instantiate(
+            borrower.id,
+            &{},
+            MockEnv::new(
+                ADMIN,
+                ContractLink {
+                    address: "flash_borrower".into(),
+                    code_hash: borrower.code_hash,
+                },
+            ),
+        )
+        .unwrap();
+
+    lend.prefund_and_deposit(BOB, Uint128(100 * one_token(18)), market.address.clone());
+    lend.prefund_user(borrower.address.clone(), Uint128(1 * one_token(18)), underlying.clone());
+
+    let loan = |pay_back: u128| market::HandleMsg::FlashLoan {
+        amount: Uint256::from(10 * one_token(18)),
+        callback: Callback {
+            contract: borrower.clone(),
+            msg: to_binary(&PayBack {
+                token: underlying.clone(),
+                market: market.address.clone(),
+                amount: Uint128(pay_back),
+            })
+            .unwrap(),
+        },
+    };
+
+    let err = lend
+        .ensemble
+        .execute(&loan(10 * one_token(18)), MockEnv::new(ALICE, market.clone()))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("Flash loans are not enabled in this market.")
+    );
+
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::UpdateConfig {
+                interest_model: None,
+                reserve_factor: None,
+                borrow_cap: None,
+                flash_loan_fee: Some(Decimal256::percent(1)),
+            },
+            MockEnv::new(ADMIN, market.clone()),
+        )
+        .unwrap();
+
+    // fee isn't paid
+    let err = lend
+        .ensemble
+        .execute(&loan(10 * one_token(18)), MockEnv::new(ALICE, market.clone()))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err(
+            "Flash loan was not paid back in full. Expected balance: 100100000000000000000, Actual: 100000000000000000000"
+        )
+    );
+    assert_eq!(
+        lend.token_balance(market.address.clone(), underlying.address.clone()),
+        Uint128(100 * one_token(18))
+    );
+
+    lend.ensemble
+        .execute(&loan(101 * one_token(17)), MockEnv::new(ALICE, market.clone()))
+        .unwrap();
+
+    assert_eq!(
+        lend.token_balance(market.address.clone(), underlying.address.clone()),
+        Uint128(1001 * one_token(17))
+    );
+
+    // half of the fee goes to reserves, the rest to suppliers
+    let state = lend.state(market.address.clone(), None);
+    assert_eq!(state.total_reserves, Uint256::from(5 * one_token(16)));
+    assert_eq!(
+        lend.exchange_rate(market.address.clone(), None),
+        Decimal256::from_str("1.0005").unwrap()
+    );
+
+    // can only be finished by the market itself
+    let err = lend
+        .ensemble
+        .execute(
+            &market::HandleMsg::FinishFlashLoan {},
+            MockEnv::new(ALICE, market.clone()),
+        )
+        .unwrap_err();
+    assert_eq!(err, StdError::unauthorized());
+}
+
+#[test]
+fn cannot_liquidate_using_flash_loan() {
+    let mut lend = Lend::default();
+    lend.ensemble.block().freeze();
+
+    let underlying_1 = lend.new_underlying_token("ONE", 18).unwrap();
+    let underlying_2 = lend.new_underlying_token("TWO", 18).unwrap();
+
+    let market_1 = lend
+        .whitelist_market(underlying_1.clone(), Decimal256::percent(75), None, None)
+        .unwrap()
+        .contract;
+
+    let market_2 = lend
+        .whitelist_market(underlying_2.clone(), Decimal256::percent(75), None, None)
+        .unwrap()
+        .contract;
+
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::UpdateConfig {
+                interest_model: None,
+                reserve_factor: None,
+                borrow_cap: None,
+                flash_loan_fee: Some(Decimal256::percent(1)),
+            },
+            MockEnv::new(ADMIN, market_1.clone()),
+        )
+        .unwrap();
+
+    let relay = lend.ensemble.register(Box::new(Relay));
+    let relay = lend
+        .ensemble
+        .instantiate(
+            relay.id,
+            &{},
+            MockEnv::new(
+                ADMIN,
+                ContractLink {
+                    address: "relay".into(),
+                    code_hash: relay.code_hash,
+                },
+            ),
+        )
+        .unwrap();
+
+    lend.prefund_and_deposit(BOB, Uint128(2 * one_token(18)), market_1.address.clone());
+    lend.prefund_and_deposit(ALICE, Uint128(100 * one_token(18)), market_1.address.clone());
+    lend.prefund_and_deposit(ALICE, Uint128(2 * one_token(18)), market_2.address.clone());
+
+    lend.prefund_user(relay.address.clone(), Uint128(1 * one_token(18)), underlying_1.clone());
+    lend.prefund_user(relay.address.clone(), Uint128(1 * one_token(18)), underlying_2.clone());
+
+    lend.ensemble
+        .execute(
+            &overseer::HandleMsg::Enter {
+                markets: vec![market_1.address.clone(), market_2.address.clone()],
+            },
+            MockEnv::new(BOB, lend.overseer.clone()),
+        )
+        .unwrap();
+
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::Borrow {
+                amount: Uint256::from(14 * one_token(17)),
+            },
+            MockEnv::new(BOB, market_2.clone()),
+        )
+        .unwrap();
+
+    let borrower = lend.id(BOB, market_2.address.clone());
+
+    // Taking most of the market's cash out lowers the exchange rate, which
+    // would put the borrower in shortfall for the duration of the callback.
+    let liquidate = vec![
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: underlying_2.address.clone(),
+            callback_code_hash: underlying_2.code_hash.clone(),
+            msg: to_binary(&Snip20HandleMsg::Send {
+                recipient: market_2.address.clone(),
+                recipient_code_hash: None,
+                amount: Uint128(5 * one_token(17)),
+                msg: Some(
+                    to_binary(&market::ReceiverCallbackMsg::Liquidate {
+                        borrower: borrower.clone(),
+                        collateral: market_1.address.clone(),
+                    })
+                    .unwrap(),
+                ),
+                memo: None,
+                padding: None,
+            })
+            .unwrap(),
+            send: vec![],
+        }),
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: underlying_1.address.clone(),
+            callback_code_hash: underlying_1.code_hash.clone(),
+            msg: to_binary(&Snip20HandleMsg::Transfer {
+                recipient: market_1.address.clone(),
+                amount: Uint128(909 * one_token(17)),
+                memo: None,
+                padding: None,
+            })
+            .unwrap(),
+            send: vec![],
+        }),
+    ];
+
+    let err = lend
+        .ensemble
+        .execute(
+            &market::HandleMsg::FlashLoan {
+                amount: Uint256::from(90 * one_token(18)),
+                callback: Callback {
+                    contract: relay.clone(),
+                    msg: to_binary(&liquidate).unwrap(),
+                },
+            },
+            MockEnv::new(ALICE, market_1.clone()),
+        )
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Cannot interact with the market while a flash loan is in progress."));
+
+    // nothing was seized
+    let info = lend.account_info(BOB, market_1.address.clone());
+    assert_eq!(info.sl_token_balance, Uint256::from(2 * one_token(18)));
+    assert_eq!(
+        lend.token_balance(market_1.address.clone(), underlying_1.address.clone()),
+        Uint128(102 * one_token(18))
+    );
+}
@@ -0,0 +1,278 @@
+use lend_shared::{
+    fadroma::{
+        cosmwasm_std::{HumanAddr, StdError, Uint128},
+        ensemble::MockEnv,
+        Decimal256, Permit, Uint256, one_token
+    },
+    interfaces::{market, overseer},
+};
+
+use crate::setup::{Lend, ADMIN};
+
+const BOB: &str = "Bob";
+const ALICE: &str = "Alice";
+
+fn unclaimed(lend: &mut Lend, account: &str, market: HumanAddr) -> Uint256 {
+    let block = lend.ensemble.block().height;
+
+    lend.ensemble
+        .query(
+            lend.overseer.address.clone(),
+            overseer::QueryMsg::UnclaimedRewards {
+                method: Permit::<overseer::OverseerPermissions>::new(
+                    account,
+                    vec![overseer::OverseerPermissions::AccountInfo],
+                    vec![lend.overseer.address.clone()],
+                    "balance",
+                )
+                .into(),
+                markets: vec![market],
+                block,
+            },
+        )
+        .unwrap()
+}
+
+#[test]
+fn supply_and_borrow_rewards() {
+    let mut lend = Lend::default();
+    lend.ensemble.block().freeze();
+
+    let sienna = lend.new_underlying_token("SIENNA", 18).unwrap();
+    let underlying = lend.new_underlying_token("TKN", 18).unwrap();
+
+    let market = lend
+        .whitelist_market(underlying, Decimal256::percent(90), None, None)
+        .unwrap()
+        .contract;
+
+    lend.prefund_user(
+        lend.overseer.address.clone(),
+        Uint128(1000 * one_token(18)),
+        sienna.clone(),
+    );
+
+    let speeds = overseer::RewardSpeeds {
+        supply: Uint256::from(one_token(18)),
+        borrow: Uint256::from(one_token(18)),
+    };
+
+    let err = lend
+        .ensemble
+        .execute(
+            &overseer::HandleMsg::SetRewardSpeeds {
+                market: market.address.clone(),
+                speeds,
+            },
+            MockEnv::new(BOB, lend.overseer.clone()),
+        )
+        .unwrap_err();
+    assert_eq!(err, StdError::unauthorized());
+
+    lend.ensemble
+        .execute(
+            &overseer::HandleMsg::SetRewardSpeeds {
+                market: market.address.clone(),
+                speeds,
+            },
+            MockEnv::new(ADMIN, lend.overseer.clone()),
+        )
+        .unwrap();
+
+    lend.prefund_and_deposit(BOB, Uint128(100 * one_token(18)), market.address.clone());
+    lend.prefund_and_deposit(ALICE, Uint128(100 * one_token(18)), market.address.clone());
+
+    lend.ensemble
+        .execute(
+            &overseer::HandleMsg::Enter {
+                markets: vec![market.address.clone()],
+            },
+            MockEnv::new(ALICE, lend.overseer.clone()),
+        )
+        .unwrap();
+
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::Borrow {
+                amount: Uint256::from(10 * one_token(18)),
+            },
+            MockEnv::new(ALICE, market.clone()),
+        )
+        .unwrap();
+
+    // only listed markets can report balances
+    let err = lend
+        .ensemble
+        .execute(
+            &overseer::HandleMsg::UpdateRewards {
+                account: BOB.into(),
+                previous: overseer::RewardBalances {
+                    sl_token_balance: Uint256::from(1000 * one_token(18)),
+                    total_supply: Uint256::from(1000 * one_token(18)),
+                    ..overseer::RewardBalances::default()
+                },
+                current: overseer::RewardBalances::default(),
+            },
+            MockEnv::new(BOB, lend.overseer.clone()),
+        )
+        .unwrap_err();
+    assert_eq!(err, StdError::unauthorized());
+
+    lend.ensemble.block().height += 10;
+
+    // suppliers split 10 tokens in half, Alice is the only borrower
+    assert_eq!(
+        unclaimed(&mut lend, BOB, market.address.clone()),
+        Uint256::from(5 * one_token(18))
+    );
+    assert_eq!(
+        unclaimed(&mut lend, ALICE, market.address.clone()),
+        Uint256::from(15 * one_token(18))
+    );
+
+    let claim = overseer::HandleMsg::ClaimRewards {
+        markets: vec![market.address.clone()],
+    };
+
+    let err = lend
+        .ensemble
+        .execute(&claim, MockEnv::new(BOB, lend.overseer.clone()))
+        .unwrap_err();
+    assert_eq!(err, StdError::generic_err("Rewards token is not configured."));
+
+    lend.ensemble
+        .execute(
+            &overseer::HandleMsg::SetRewardsToken {
+                token: sienna.clone(),
+            },
+            MockEnv::new(ADMIN, lend.overseer.clone()),
+        )
+        .unwrap();
+
+    lend.ensemble
+        .execute(&claim, MockEnv::new(BOB, lend.overseer.clone()))
+        .unwrap();
+    lend.ensemble
+        .execute(&claim, MockEnv::new(ALICE, lend.overseer.clone()))
+        .unwrap();
+
+    assert_eq!(
+        lend.token_balance(BOB, sienna.address.clone()),
+        Uint128(5 * one_token(18))
+    );
+    assert_eq!(
+        lend.token_balance(ALICE, sienna.address.clone()),
+        Uint128(15 * one_token(18))
+    );
+
+    let err = lend
+        .ensemble
+        .execute(&claim, MockEnv::new(BOB, lend.overseer.clone()))
+        .unwrap_err();
+    assert_eq!(err, StdError::generic_err("No rewards to claim."));
+
+    // the slTokens stay in the market and still count as collateral
+    let info = lend.account_info(ALICE, market.address.clone());
+    assert_eq!(info.sl_token_balance, Uint256::from(100 * one_token(18)));
+
+    lend.ensemble.block().height += 10;
+
+    // rewards earned before a balance change are kept
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::RedeemToken {
+                burn_amount: Uint256::from(100 * one_token(18)),
+            },
+            MockEnv::new(BOB, market.clone()),
+        )
+        .unwrap();
+
+    assert_eq!(
+        unclaimed(&mut lend, BOB, market.address.clone()),
+        Uint256::from(5 * one_token(18))
+    );
+
+    lend.ensemble.block().height += 10;
+
+    // Bob has no supply left so Alice gets all of it
+    assert_eq!(
+        unclaimed(&mut lend, BOB, market.address.clone()),
+        Uint256::from(5 * one_token(18))
+    );
+    // the borrow balance is read from the market, so Alice gets all of the borrow rewards
+    let alice = unclaimed(&mut lend, ALICE, market.address.clone());
+    assert!(alice > Uint256::from(349 * one_token(17)));
+    assert!(alice <= Uint256::from(35 * one_token(18)));
+
+    let speeds: overseer::RewardSpeeds = lend
+        .ensemble
+        .query(
+            lend.overseer.address.clone(),
+            overseer::QueryMsg::RewardSpeeds {
+                market: market.address.clone(),
+            },
+        )
+        .unwrap();
+    assert_eq!(speeds.supply, Uint256::from(one_token(18)));
+}
+
+#[test]
+fn existing_positions_earn_once_rewards_are_enabled() {
+    let mut lend = Lend::default();
+    lend.ensemble.block().freeze();
+
+    let underlying = lend.new_underlying_token("TKN", 18).unwrap();
+
+    let market = lend
+        .whitelist_market(underlying, Decimal256::percent(90), None, None)
+        .unwrap()
+        .contract;
+
+    // the market doesn't report the deposit since there are no rewards yet
+    lend.prefund_and_deposit(BOB, Uint128(100 * one_token(18)), market.address.clone());
+
+    lend.ensemble
+        .execute(
+            &overseer::HandleMsg::SetRewardSpeeds {
+                market: market.address.clone(),
+                speeds: overseer::RewardSpeeds {
+                    supply: Uint256::from(one_token(18)),
+                    borrow: Uint256::zero(),
+                },
+            },
+            MockEnv::new(ADMIN, lend.overseer.clone()),
+        )
+        .unwrap();
+
+    let err = lend
+        .ensemble
+        .execute(
+            &market::HandleMsg::EnableRewards {},
+            MockEnv::new(BOB, market.clone()),
+        )
+        .unwrap_err();
+    assert_eq!(err, StdError::unauthorized());
+
+    lend.ensemble.block().height += 10;
+
+    // the position earns from when the speeds were set without being reported
+    assert_eq!(
+        unclaimed(&mut lend, BOB, market.address.clone()),
+        Uint256::from(10 * one_token(18))
+    );
+
+    // the first report credits the balance from before the change
+    lend.prefund_and_deposit(BOB, Uint128(100 * one_token(18)), market.address.clone());
+
+    assert_eq!(
+        unclaimed(&mut lend, BOB, market.address.clone()),
+        Uint256::from(10 * one_token(18))
+    );
+
+    lend.ensemble.block().height += 10;
+
+    assert_eq!(
+        unclaimed(&mut lend, BOB, market.address.clone()),
+        Uint256::from(20 * one_token(18))
+    );
+}
@@ -0,0 +1,176 @@
+use lend_shared::{
+    fadroma::{
+        cosmwasm_std::{to_binary, HumanAddr, StdError, Uint128},
+        ensemble::MockEnv,
+        snip20_impl::msg as snip20,
+        Decimal256, Uint256, one_token
+    },
+    interfaces::{market, overseer},
+};
+
+use crate::setup::{Lend, ADMIN};
+
+const BOB: &str = "Bob";
+const CHESTER: &str = "Chester";
+
+fn isolated_debt(lend: &Lend, market: HumanAddr) -> Uint256 {
+    lend.ensemble
+        .query(
+            lend.overseer.address.clone(),
+            overseer::QueryMsg::IsolatedDebt { market },
+        )
+        .unwrap()
+}
+
+#[test]
+fn isolated_collateral() {
+    let mut lend = Lend::default();
+    lend.ensemble.block().freeze();
+
+    let underlying_1 = lend.new_underlying_token("SSCRT", 6).unwrap();
+    let underlying_2 = lend.new_underlying_token("SIENNA", 18).unwrap();
+    let underlying_3 = lend.new_underlying_token("RISK", 18).unwrap();
+    let underlying_4 = lend.new_underlying_token("MOON", 18).unwrap();
+
+    let isolation = Some(overseer::Isolation {
+        debt_ceiling: Uint256::from(50 * one_token(18)),
+    });
+
+    let mut config = lend
+        .market_config(underlying_1.clone(), Decimal256::percent(90))
+        .unwrap();
+    config.borrowable_in_isolation = true;
+    let market_one = lend.whitelist_market_with(config).unwrap().contract;
+
+    let market_two = lend
+        .whitelist_market(underlying_2, Decimal256::percent(90), None, None)
+        .unwrap()
+        .contract;
+
+    let mut config = lend.market_config(underlying_3, Decimal256::percent(90)).unwrap();
+    config.isolated = isolation;
+    let isolated_one = lend.whitelist_market_with(config).unwrap().contract;
+
+    let mut config = lend.market_config(underlying_4, Decimal256::percent(90)).unwrap();
+    config.isolated = isolation;
+    let isolated_two = lend.whitelist_market_with(config).unwrap().contract;
+
+    lend.prefund_and_deposit(BOB, Uint128(500 * one_token(6)), market_one.address.clone());
+    lend.prefund_and_deposit(BOB, Uint128(500 * one_token(18)), market_two.address.clone());
+    lend.prefund_and_deposit(CHESTER, Uint128(200 * one_token(18)), isolated_one.address.clone());
+
+    lend.ensemble
+        .execute(
+            &overseer::HandleMsg::Enter {
+                markets: vec![
+                    isolated_one.address.clone(),
+                    market_one.address.clone(),
+                    market_two.address.clone(),
+                ],
+            },
+            MockEnv::new(CHESTER, lend.overseer.clone()),
+        )
+        .unwrap();
+
+    let err = lend.ensemble
+        .execute(
+            &overseer::HandleMsg::Enter {
+                markets: vec![isolated_two.address.clone()],
+            },
+            MockEnv::new(CHESTER, lend.overseer.clone()),
+        )
+        .unwrap_err();
+
+    assert_eq!(err, StdError::generic_err("Cannot enter more than one isolated market."));
+
+    // Not approved for borrowing against isolated collateral.
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::Borrow {
+                amount: Uint256::from(10 * one_token(18)),
+            },
+            MockEnv::new(CHESTER, market_two.clone()),
+        )
+        .unwrap_err();
+
+    let borrow_amount = Uint256::from(40 * one_token(6));
+
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::Borrow {
+                amount: borrow_amount,
+            },
+            MockEnv::new(CHESTER, market_one.clone()),
+        )
+        .unwrap();
+
+    assert_eq!(
+        isolated_debt(&lend, isolated_one.address.clone()),
+        Uint256::from(40 * one_token(18))
+    );
+
+    // Would exceed the debt ceiling even though liquidity is sufficient.
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::Borrow {
+                amount: Uint256::from(20 * one_token(6)),
+            },
+            MockEnv::new(CHESTER, market_one.clone()),
+        )
+        .unwrap_err();
+
+    lend.ensemble
+        .execute(
+            &overseer::HandleMsg::ChangeMarket {
+                market: isolated_one.address.clone(),
+                ltv_ratio: None,
+                symbol: None,
+                debt_ceiling: Some(Uint256::from(100 * one_token(18))),
+                borrowable_in_isolation: None,
+            },
+            MockEnv::new(ADMIN, lend.overseer.clone()),
+        )
+        .unwrap();
+
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::Borrow {
+                amount: Uint256::from(20 * one_token(6)),
+            },
+            MockEnv::new(CHESTER, market_one.clone()),
+        )
+        .unwrap();
+
+    assert_eq!(
+        isolated_debt(&lend, isolated_one.address.clone()),
+        Uint256::from(60 * one_token(18))
+    );
+
+    lend.ensemble
+        .execute(
+            &snip20::HandleMsg::Send {
+                recipient: market_one.address.clone(),
+                recipient_code_hash: None,
+                amount: Uint128(60 * one_token(6)),
+                msg: Some(to_binary(&market::ReceiverCallbackMsg::Repay { borrower: None }).unwrap()),
+                memo: None,
+                padding: None,
+            },
+            MockEnv::new(CHESTER, underlying_1),
+        )
+        .unwrap();
+
+    assert_eq!(isolated_debt(&lend, isolated_one.address), Uint256::zero());
+
+    // Only reported by listed markets.
+    lend.ensemble
+        .execute(
+            &overseer::HandleMsg::UpdateIsolatedDebt {
+                borrower: CHESTER.into(),
+                amount: Uint256::from(one_token(18)),
+                repay: false,
+            },
+            MockEnv::new(BOB, lend.overseer.clone()),
+        )
+        .unwrap_err();
+}
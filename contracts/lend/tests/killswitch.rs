@@ -72,6 +72,7 @@ fn killswitch() {
                 interest_model: None,
                 reserve_factor: Some(Decimal256::one()),
                 borrow_cap: None,
+                flash_loan_fee: None,
             },
             MockEnv::new(ADMIN, market.contract.clone()),
         )
@@ -18,6 +18,32 @@ mod killswitch;
 mod reserves;
 #[cfg(test)]
 mod rewards;
+#[cfg(test)]
+mod stable_borrow;
+#[cfg(test)]
+mod adaptive_interest;
+#[cfg(test)]
+mod isolation;
+#[cfg(test)]
+mod e_mode;
+#[cfg(test)]
+mod delisting;
+#[cfg(test)]
+mod pause;
+#[cfg(test)]
+mod liquidatable;
+#[cfg(test)]
+mod bad_debt;
+#[cfg(test)]
+mod flash_loan;
+#[cfg(test)]
+mod collateral_swap;
+#[cfg(test)]
+mod time_accrual;
+#[cfg(test)]
+mod reserve_policy;
+#[cfg(test)]
+mod incentives;
 
 #[macro_export]
 macro_rules! impl_contract_harness_default {
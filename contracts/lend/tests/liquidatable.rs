@@ -0,0 +1,154 @@
+use lend_shared::{
+    fadroma::{
+        cosmwasm_std::{to_binary, Uint128},
+        ensemble::MockEnv,
+        snip20_impl::msg::HandleMsg as Snip20HandleMsg,
+        Decimal256, Uint256, one_token
+    },
+    interfaces::{market, overseer},
+    core::Pagination
+};
+
+use crate::setup::Lend;
+
+const BOB: &str = "Bob";
+const ALICE: &str = "Alice";
+
+fn liquidatable_accounts(
+    lend: &mut Lend,
+    start: u64,
+    limit: u8
+) -> overseer::LiquidatableAccountsResponse {
+    let block = lend.ensemble.block().height;
+
+    lend.ensemble
+        .query(
+            lend.overseer.address.clone(),
+            overseer::QueryMsg::LiquidatableAccounts {
+                block,
+                time: None,
+                pagination: Pagination { start, limit },
+            },
+        )
+        .unwrap()
+}
+
+#[test]
+fn lists_accounts_with_shortfall() {
+    let mut lend = Lend::default();
+    lend.ensemble.block().freeze();
+
+    let underlying_1 = lend.new_underlying_token("ONE", 18).unwrap();
+    let underlying_2 = lend.new_underlying_token("TWO", 18).unwrap();
+
+    let market_1 = lend
+        .whitelist_market(underlying_1, Decimal256::percent(75), None, None)
+        .unwrap();
+
+    let market_2 = lend
+        .whitelist_market(underlying_2.clone(), Decimal256::percent(75), None, None)
+        .unwrap();
+
+    lend.prefund_and_deposit(
+        BOB,
+        Uint128(2 * one_token(18)),
+        market_1.contract.address.clone(),
+    );
+    lend.prefund_and_deposit(
+        ALICE,
+        Uint128(2 * one_token(18)),
+        market_2.contract.address.clone(),
+    );
+    lend.prefund_user(ALICE, Uint128(1 * one_token(18)), underlying_2.clone());
+
+    for user in &[BOB, ALICE] {
+        lend.ensemble
+            .execute(
+                &overseer::HandleMsg::Enter {
+                    markets: vec![
+                        market_1.contract.address.clone(),
+                        market_2.contract.address.clone(),
+                    ],
+                },
+                MockEnv::new(*user, lend.overseer.clone()),
+            )
+            .unwrap();
+    }
+
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::Borrow {
+                amount: Uint256::from(1 * one_token(18)),
+            },
+            MockEnv::new(BOB, market_2.contract.clone()),
+        )
+        .unwrap();
+
+    let result = liquidatable_accounts(&mut lend, 0, 10);
+    assert!(result.entries.is_empty());
+    assert_eq!(result.next, None);
+
+    // crash the price of the collateral
+    lend.set_oracle_price(market_1.symbol.as_bytes(), Uint128(5 * one_token(17)))
+        .unwrap();
+
+    let result = liquidatable_accounts(&mut lend, 0, 10);
+    assert_eq!(result.next, None);
+    assert_eq!(result.entries.len(), 1);
+
+    let entry = &result.entries[0];
+    assert_eq!(entry.id, lend.id(BOB, market_2.contract.address.clone()));
+    assert!(!entry.shortfall.is_zero());
+    assert_eq!(entry.largest_debt.market, market_2.contract.address);
+    assert_eq!(entry.largest_debt.balance, Uint256::from(1 * one_token(18)));
+    assert_eq!(
+        entry.largest_collateral.as_ref().unwrap().market,
+        market_1.contract.address
+    );
+    assert_eq!(entry.max_repay, Uint256::from(1 * one_token(18)));
+    assert!(entry.simulation.is_some());
+
+    let id = entry.id.clone();
+
+    // paginated scan covers all borrowers across markets
+    let result = liquidatable_accounts(&mut lend, 0, 1);
+    assert_eq!(result.entries.len(), 1);
+    assert_eq!(result.next, Some(1));
+
+    let result = liquidatable_accounts(&mut lend, 1, 1);
+    assert!(result.entries.is_empty());
+    assert_eq!(result.next, None);
+
+    // a zero limit would never advance the cursor
+    let block = lend.ensemble.block().height;
+    let result = lend.ensemble.query::<_, overseer::LiquidatableAccountsResponse>(
+        lend.overseer.address.clone(),
+        overseer::QueryMsg::LiquidatableAccounts {
+            block,
+            time: None,
+            pagination: Pagination { start: 0, limit: 0 },
+        },
+    );
+    assert!(result.is_err());
+
+    // the reported id can be used to liquidate directly
+    lend.ensemble
+        .execute(
+            &Snip20HandleMsg::Send {
+                recipient: market_2.contract.address.clone(),
+                recipient_code_hash: None,
+                amount: Uint128(5 * one_token(17)),
+                msg: Some(
+                    to_binary(&market::ReceiverCallbackMsg::Liquidate {
+                        borrower: id,
+                        collateral: market_1.contract.address.clone(),
+                    })
+                    .unwrap(),
+                ),
+                memo: None,
+                padding: None,
+            },
+            MockEnv::new(ALICE, underlying_2),
+        )
+        .unwrap();
+}
@@ -119,6 +119,7 @@ fn liquidate_basic() {
                 )
                 .into(),
                 block: None,
+                time: None,
             },
         )
         .unwrap();
@@ -177,6 +178,7 @@ fn liquidate_basic() {
                 )
                 .into(),
                 block: None,
+                time: None,
             },
         )
         .unwrap();
@@ -195,6 +197,7 @@ fn liquidate_basic() {
                 )
                 .into(),
                 block: None,
+                time: None,
             },
         )
         .unwrap();
@@ -202,6 +205,25 @@ fn liquidate_basic() {
         res.sl_token_balance,
         Uint256::from(3_888_000_000_000_000_000u128)
     );
+
+    let history = lend.history(BOB, market_2.contract.address.clone());
+    assert_eq!(history.total, 2);
+
+    let events: Vec<market::HistoryEvent> = history.entries.into_iter().map(|x| x.event).collect();
+    assert_eq!(events, vec![
+        market::HistoryEvent::Borrow { amount: borrow_amount, stable: false },
+        market::HistoryEvent::Liquidated {
+            amount: borrow_amount,
+            collateral: market_1.contract.address.clone(),
+        },
+    ]);
+
+    let history = lend.history(BOB, market_1.contract.address.clone());
+    assert_eq!(history.total, 1);
+    assert!(matches!(history.entries[0].event, market::HistoryEvent::Seized { .. }));
+
+    // Nothing happened to the liquidator's position.
+    assert_eq!(lend.history(ALICE, market_2.contract.address.clone()).total, 0);
 }
 
 #[test]
@@ -316,6 +338,7 @@ fn borrower_accrues_interest_and_goes_underwater() {
         market_2.contract.address.clone(),
         market::QueryMsg::Borrowers {
             block: height,
+            time: None,
             pagination: Pagination {
                 start: 0,
                 limit: 10
@@ -377,6 +400,7 @@ fn borrower_accrues_interest_and_goes_underwater() {
         market_2.contract.address.clone(),
         market::QueryMsg::Borrowers {
             block: height,
+            time: None,
             pagination: Pagination {
                 start: 0,
                 limit: 10
@@ -422,6 +446,7 @@ fn borrower_accrues_interest_and_goes_underwater() {
         market_2.contract.address.clone(),
         market::QueryMsg::Borrowers {
             block: height,
+            time: None,
             pagination: Pagination {
                 start: 0,
                 limit: 10
@@ -532,6 +557,7 @@ fn close_factor() {
                 )
                 .into(),
                 block: None,
+                time: None,
             },
         )
         .unwrap();
@@ -597,6 +623,7 @@ fn close_factor() {
         market_2.contract.address.clone(),
         market::QueryMsg::Borrowers {
             block: height,
+            time: None,
             pagination: Pagination {
                 start: 0,
                 limit: 10
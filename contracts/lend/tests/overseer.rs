@@ -1,7 +1,7 @@
 use std::str::FromStr;
 
 use lend_shared::{
-    core::Pagination,
+    core::{AccrualMode, Pagination},
     fadroma::{
         admin,
         decimal::one_token,
@@ -29,7 +29,7 @@ impl ContractHarness for MarketImpl {
 
     fn query(&self, deps: &MockDeps, msg: Binary) -> StdResult<Binary> {
         match from_binary(&msg).unwrap() {
-            market::QueryMsg::ExchangeRate { block: _ } => {
+            market::QueryMsg::ExchangeRate { block: _, time: _ } => {
                 let res: Option<Decimal256> = deps.get(b"exchange_rate").unwrap();
                 match res {
                     Some(value) => to_binary(&value),
@@ -66,8 +66,14 @@ fn whitelist() {
                     initial_exchange_rate: Decimal256::one(),
                     reserve_factor: Decimal256::one(),
                     seize_factor: Decimal256::one(),
+                    stable_rate_threshold: None,
+                    stable_rate_rebalance_delta: None,
+                    flash_loan_fee: None,
+                    accrual_mode: AccrualMode::Block,
                 },
                 interest_model_contract: lend.interest_model.clone(),
+                isolated: None,
+                borrowable_in_isolation: false,
             },
         },
         MockEnv::new("fake", lend.overseer.clone()),
@@ -89,8 +95,14 @@ fn whitelist() {
                     initial_exchange_rate: Decimal256::one(),
                     reserve_factor: Decimal256::one(),
                     seize_factor: Decimal256::one(),
+                    stable_rate_threshold: None,
+                    stable_rate_rebalance_delta: None,
+                    flash_loan_fee: None,
+                    accrual_mode: AccrualMode::Block,
                 },
                 interest_model_contract: lend.interest_model.clone(),
+                isolated: None,
+                borrowable_in_isolation: false,
             },
         },
         MockEnv::new(ADMIN, lend.overseer.clone()),
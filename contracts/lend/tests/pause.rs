@@ -0,0 +1,152 @@
+use lend_shared::{
+    fadroma::{
+        cosmwasm_std::{to_binary, HumanAddr, StdError, Uint128},
+        ensemble::MockEnv,
+        snip20_impl::msg as snip20,
+        Decimal256, Uint256, one_token
+    },
+    interfaces::{market, overseer},
+};
+
+use crate::setup::{Lend, ADMIN};
+
+const BOB: &str = "Bob";
+const CHESTER: &str = "Chester";
+const GUARDIAN: &str = "Guardian";
+
+#[test]
+fn guardian_can_only_pause() {
+    let mut lend = Lend::default();
+    lend.ensemble.block().freeze();
+
+    let underlying_1 = lend.new_underlying_token("SSCRT", 18).unwrap();
+    let underlying_2 = lend.new_underlying_token("SIENNA", 18).unwrap();
+
+    let market_one = lend
+        .whitelist_market(underlying_1.clone(), Decimal256::percent(90), None, None)
+        .unwrap()
+        .contract;
+
+    let market_two = lend
+        .whitelist_market(underlying_2, Decimal256::percent(90), None, None)
+        .unwrap()
+        .contract;
+
+    lend.prefund_and_deposit(BOB, Uint128(100 * one_token(18)), market_one.address.clone());
+    lend.prefund_and_deposit(CHESTER, Uint128(100 * one_token(18)), market_two.address.clone());
+
+    lend.ensemble
+        .execute(
+            &overseer::HandleMsg::Enter {
+                markets: vec![market_one.address.clone(), market_two.address.clone()],
+            },
+            MockEnv::new(CHESTER, lend.overseer.clone()),
+        )
+        .unwrap();
+
+    let borrow = market::HandleMsg::Borrow {
+        amount: Uint256::from(10 * one_token(18)),
+    };
+
+    lend.ensemble
+        .execute(&borrow, MockEnv::new(CHESTER, market_one.clone()))
+        .unwrap();
+
+    let set_guardian = overseer::HandleMsg::SetGuardian {
+        guardian: Some(GUARDIAN.into()),
+    };
+
+    lend.ensemble
+        .execute(&set_guardian, MockEnv::new(GUARDIAN, lend.overseer.clone()))
+        .unwrap_err();
+
+    lend.ensemble
+        .execute(&set_guardian, MockEnv::new(ADMIN, lend.overseer.clone()))
+        .unwrap();
+
+    let guardian: Option<HumanAddr> = lend.ensemble
+        .query(lend.overseer.address.clone(), overseer::QueryMsg::Guardian {})
+        .unwrap();
+    assert_eq!(guardian, Some(GUARDIAN.into()));
+
+    let actions = market::PausedActions {
+        deposit: true,
+        borrow: true,
+        ..market::PausedActions::default()
+    };
+
+    let pause = overseer::HandleMsg::PauseMarket {
+        market: market_one.address.clone(),
+        actions,
+    };
+
+    lend.ensemble
+        .execute(&pause, MockEnv::new(BOB, lend.overseer.clone()))
+        .unwrap_err();
+
+    lend.ensemble
+        .execute(&pause, MockEnv::new(GUARDIAN, lend.overseer.clone()))
+        .unwrap();
+
+    let paused: market::PausedActions = lend.ensemble
+        .query(market_one.address.clone(), market::QueryMsg::Paused {})
+        .unwrap();
+    assert_eq!(paused, actions);
+
+    let err = lend.ensemble
+        .execute(&borrow, MockEnv::new(CHESTER, market_one.clone()))
+        .unwrap_err();
+    assert_eq!(err, StdError::generic_err("Borrows are currently paused in this market."));
+
+    // Repaying is still possible.
+    lend.ensemble
+        .execute(
+            &snip20::HandleMsg::Send {
+                recipient: market_one.address.clone(),
+                recipient_code_hash: None,
+                amount: Uint128(10 * one_token(18)),
+                msg: Some(to_binary(&market::ReceiverCallbackMsg::Repay { borrower: None }).unwrap()),
+                memo: None,
+                padding: None,
+            },
+            MockEnv::new(CHESTER, underlying_1),
+        )
+        .unwrap();
+
+    assert_eq!(
+        lend.account_info(CHESTER, market_one.address.clone()).borrow_balance,
+        Uint256::zero()
+    );
+
+    // The guardian cannot unpause, neither through the market directly.
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::SetPaused {
+                actions: market::PausedActions::default(),
+            },
+            MockEnv::new(GUARDIAN, market_one.clone()),
+        )
+        .unwrap_err();
+
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::Pause {
+                actions: market::PausedActions::default(),
+            },
+            MockEnv::new(GUARDIAN, market_one.clone()),
+        )
+        .unwrap_err();
+
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::SetPaused {
+                actions: market::PausedActions::default(),
+            },
+            MockEnv::new(ADMIN, market_one.clone()),
+        )
+        .unwrap();
+
+    lend.ensemble
+        .execute(&borrow, MockEnv::new(CHESTER, market_one))
+        .unwrap();
+}
@@ -0,0 +1,215 @@
+use lend_shared::{
+    fadroma::{
+        cosmwasm_std::{StdError, Uint128},
+        ensemble::MockEnv,
+        Decimal256, Uint256, one_token
+    },
+    interfaces::{market, overseer},
+};
+
+use crate::collateral_swap::new_pair;
+use crate::setup::{Lend, ADMIN};
+
+const BOB: &str = "Bob";
+const ALICE: &str = "Alice";
+const TREASURY: &str = "treasury";
+const REWARDS_POOL: &str = "rewards_pool";
+const KEEPER: &str = "keeper";
+
+#[test]
+fn sweep_reserves() {
+    let mut lend = Lend::default();
+    lend.ensemble.block().freeze();
+
+    let underlying = lend.new_underlying_token("TKN", 18).unwrap();
+    let sienna = lend.new_underlying_token("SIENNA", 18).unwrap();
+
+    let market = lend
+        .whitelist_market(
+            underlying.clone(),
+            Decimal256::percent(75),
+            None,
+            Some(Decimal256::percent(50)),
+        )
+        .unwrap()
+        .contract;
+
+    let hop = new_pair(&mut lend, "pair", &underlying, &sienna, Decimal256::one());
+
+    lend.prefund_and_deposit(BOB, Uint128(3 * one_token(18)), market.address.clone());
+    lend.prefund_and_deposit(ALICE, Uint128(3 * one_token(18)), market.address.clone());
+
+    lend.ensemble
+        .execute(
+            &overseer::HandleMsg::Enter {
+                markets: vec![market.address.clone()],
+            },
+            MockEnv::new(ALICE, lend.overseer.clone()),
+        )
+        .unwrap();
+
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::Borrow {
+                amount: Uint256::from(one_token(18)),
+            },
+            MockEnv::new(ALICE, market.clone()),
+        )
+        .unwrap();
+
+    lend.ensemble.block().height += 100000;
+
+    let sweep = |min_buyback_output: Option<Uint256>| market::HandleMsg::SweepReserves {
+        min_buyback_output,
+    };
+
+    let err = lend
+        .ensemble
+        .execute(&sweep(None), MockEnv::new(KEEPER, market.clone()))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("Reserve sweeping is not enabled in this market.")
+    );
+
+    let policy = |treasury_share: Decimal256, min_reserves: Uint256| market::ReservePolicy {
+        treasury: TREASURY.into(),
+        treasury_share,
+        rewards_pool: REWARDS_POOL.into(),
+        buyback_share: Decimal256::percent(50),
+        buyback_route: vec![hop.clone()],
+        min_reserves,
+        keeper: Some(KEEPER.into()),
+    };
+
+    let err = lend
+        .ensemble
+        .execute(
+            &market::HandleMsg::SetReservePolicy {
+                policy: Some(policy(Decimal256::percent(60), Uint256::zero())),
+            },
+            MockEnv::new(ADMIN, market.clone()),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("Treasury and buyback shares cannot add up to more than 1.")
+    );
+
+    let reserves = lend.state(market.address.clone(), None).total_reserves;
+    assert!(reserves > Uint256::zero());
+
+    let min_reserves = reserves.decimal_mul(Decimal256::percent(25)).unwrap();
+    let policy = policy(Decimal256::percent(50), min_reserves);
+
+    let err = lend
+        .ensemble
+        .execute(
+            &market::HandleMsg::SetReservePolicy {
+                policy: Some(policy.clone()),
+            },
+            MockEnv::new(BOB, market.clone()),
+        )
+        .unwrap_err();
+    assert_eq!(err, StdError::unauthorized());
+
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::SetReservePolicy {
+                policy: Some(policy.clone()),
+            },
+            MockEnv::new(ADMIN, market.clone()),
+        )
+        .unwrap();
+
+    let stored: Option<market::ReservePolicy<_>> = lend
+        .ensemble
+        .query(market.address.clone(), market::QueryMsg::ReservePolicy {})
+        .unwrap();
+    assert_eq!(stored, Some(policy));
+
+    let sweepable = (reserves - min_reserves).unwrap();
+    let to_treasury = sweepable.decimal_mul(Decimal256::percent(50)).unwrap();
+
+    // anyone can sweep to the treasury, but only the admin and the keeper
+    // can buy back, since they set the minimum output
+    let err = lend
+        .ensemble
+        .execute(&sweep(Some(Uint256::zero())), MockEnv::new(BOB, market.clone()))
+        .unwrap_err();
+    assert_eq!(err, StdError::unauthorized());
+
+    lend.ensemble
+        .execute(&sweep(None), MockEnv::new(BOB, market.clone()))
+        .unwrap();
+
+    assert_eq!(
+        Uint256::from(lend.token_balance(TREASURY, underlying.address.clone())),
+        to_treasury
+    );
+    assert_eq!(lend.token_balance(REWARDS_POOL, sienna.address.clone()), Uint128::zero());
+
+    // the buyback share stays in the reserves
+    let reserves = (reserves - to_treasury).unwrap();
+    assert_eq!(lend.state(market.address.clone(), None).total_reserves, reserves);
+
+    let sweepable = (reserves - min_reserves).unwrap();
+    let share = sweepable.decimal_mul(Decimal256::percent(50)).unwrap();
+
+    let err = lend
+        .ensemble
+        .execute(
+            &sweep(Some((share + Uint256::from(1u128)).unwrap())),
+            MockEnv::new(KEEPER, market.clone()),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("Operation fell short of expected_return")
+    );
+
+    lend.ensemble
+        .execute(&sweep(Some(share)), MockEnv::new(KEEPER, market.clone()))
+        .unwrap();
+
+    assert_eq!(
+        Uint256::from(lend.token_balance(TREASURY, underlying.address.clone())),
+        (to_treasury + share).unwrap()
+    );
+    assert_eq!(
+        Uint256::from(lend.token_balance(REWARDS_POOL, sienna.address.clone())),
+        share
+    );
+
+    let state = lend.state(market.address.clone(), None);
+    assert_eq!(state.total_reserves, ((reserves - share).unwrap() - share).unwrap());
+    assert!(state.total_reserves >= min_reserves);
+
+    let err = lend
+        .ensemble
+        .execute(&sweep(Some(Uint256::zero())), MockEnv::new(ADMIN, market.clone()))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err(format!(
+            "Nothing to sweep. Reserves: {}, Minimum: {}",
+            state.total_reserves, min_reserves
+        ))
+    );
+
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::SetReservePolicy { policy: None },
+            MockEnv::new(ADMIN, market.clone()),
+        )
+        .unwrap();
+
+    let err = lend
+        .ensemble
+        .execute(&sweep(None), MockEnv::new(ADMIN, market))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("Reserve sweeping is not enabled in this market.")
+    );
+}
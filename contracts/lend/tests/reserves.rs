@@ -81,6 +81,7 @@ fn reduce_all_reserves() {
                 )
                 .into(),
                 block: None,
+                time: None,
             },
         )
         .unwrap();
@@ -122,6 +123,7 @@ fn reduce_all_reserves() {
                 )
                 .into(),
                 block: Some(height),
+                time: None,
             },
         )
         .unwrap();
@@ -142,6 +144,7 @@ fn reduce_all_reserves() {
                 )
                 .into(),
                 block: Some(height),
+                time: None,
             },
         )
         .unwrap();
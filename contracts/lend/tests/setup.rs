@@ -12,7 +12,7 @@ use lend_shared::{
         to_binary, Binary, Composable, ContractInstantiationInfo, ContractLink, Decimal256, Env,
         HandleResponse, HumanAddr, InitResponse, Permit, StdError, StdResult, Uint128, Uint256
     },
-    core::Pagination
+    core::{AccrualMode, Pagination}
 };
 
 use lend_shared::interfaces::{interest_model, market, overseer};
@@ -148,6 +148,7 @@ pub struct Lend {
     pub interest_model: ContractLink<HumanAddr>,
     pub mock_band: ContractLink<HumanAddr>,
     pub token: ContractInstantiationInfo,
+    pub interest: ContractInstantiationInfo,
 }
 
 impl Lend {
@@ -185,12 +186,14 @@ impl Lend {
                     jump_multiplier_year: Decimal256::from_str("0.02").unwrap(),
                     jump_threshold: Decimal256::from_str("0.09").unwrap(),
                     blocks_year: None,
+                    stable_premium_year: Some(Decimal256::from_str("0.02").unwrap()),
+                    accrual_mode: None,
                 },
                 MockEnv::new(
                     ADMIN,
                     ContractLink {
                         address: "interest_model".into(),
-                        code_hash: interest.code_hash,
+                        code_hash: interest.code_hash.clone(),
                     },
                 ),
             )
@@ -225,6 +228,7 @@ impl Lend {
             interest_model,
             mock_band,
             token,
+            interest,
         }
     }
 
@@ -250,6 +254,7 @@ impl Lend {
                 redeem_amount,
                 borrow_amount,
                 block,
+                time: None,
             },
         )
     }
@@ -276,6 +281,25 @@ impl Lend {
         exchange_rate: Option<Decimal256>,
         reserve_factor: Option<Decimal256>,
     ) -> StdResult<overseer::Market<HumanAddr>> {
+        let mut config = self.market_config(underlying_asset, ltv_ratio)?;
+
+        if let Some(exchange_rate) = exchange_rate {
+            config.config.initial_exchange_rate = exchange_rate;
+        }
+
+        if let Some(reserve_factor) = reserve_factor {
+            config.config.reserve_factor = reserve_factor;
+        }
+
+        self.whitelist_market_with(config)
+    }
+
+    /// The config that `whitelist_market` uses, for tests that need to change more of it.
+    pub fn market_config(
+        &self,
+        underlying_asset: ContractLink<HumanAddr>,
+        ltv_ratio: Decimal256,
+    ) -> StdResult<MarketInitConfig> {
         let result = self.ensemble.query(
             underlying_asset.address.clone(),
             Snip20QueryMsg::TokenInfo {},
@@ -286,23 +310,34 @@ impl Lend {
             _ => panic!("Expecting Snip20QueryResp::TokenInfo"),
         };
 
-        self.ensemble.execute(
-            &overseer::HandleMsg::Whitelist {
-                config: MarketInitConfig {
-                    admin: None,
-                    token_symbol,
-                    prng_seed: Binary::from(b"seed_for_sienna_market"),
-                    entropy: Binary::from(b"entropy_for_sienna_market"),
-                    underlying_asset,
-                    ltv_ratio,
-                    config: market::Config {
-                        initial_exchange_rate: exchange_rate.unwrap_or(Decimal256::one()),
-                        reserve_factor: reserve_factor.unwrap_or(Decimal256::zero()),
-                        seize_factor: Decimal256::from_str("0.028").unwrap(),
-                    },
-                    interest_model_contract: self.interest_model.clone(),
-                },
+        Ok(MarketInitConfig {
+            admin: None,
+            token_symbol,
+            prng_seed: Binary::from(b"seed_for_sienna_market"),
+            entropy: Binary::from(b"entropy_for_sienna_market"),
+            underlying_asset,
+            ltv_ratio,
+            config: market::Config {
+                initial_exchange_rate: Decimal256::one(),
+                reserve_factor: Decimal256::zero(),
+                seize_factor: Decimal256::from_str("0.028").unwrap(),
+                stable_rate_threshold: None,
+                stable_rate_rebalance_delta: None,
+                flash_loan_fee: None,
+                accrual_mode: AccrualMode::Block,
             },
+            interest_model_contract: self.interest_model.clone(),
+            isolated: None,
+            borrowable_in_isolation: false,
+        })
+    }
+
+    pub fn whitelist_market_with(
+        &mut self,
+        config: MarketInitConfig,
+    ) -> StdResult<overseer::Market<HumanAddr>> {
+        self.ensemble.execute(
+            &overseer::HandleMsg::Whitelist { config },
             MockEnv::new(ADMIN, self.overseer.clone()),
         )?;
 
@@ -412,6 +447,7 @@ impl Lend {
             market,
             market::QueryMsg::SimulateLiquidation {
                 block,
+                time: None,
                 borrower,
                 collateral,
                 amount
@@ -436,6 +472,32 @@ impl Lend {
                 )
                 .into(),
                 block: None,
+                time: None,
+            },
+        )
+        .unwrap()
+    }
+
+    #[inline]
+    pub fn history(
+        &self,
+        address: impl Into<HumanAddr>,
+        market: HumanAddr,
+    ) -> market::HistoryResponse {
+        self.ensemble.query(
+            market.clone(),
+            market::QueryMsg::History {
+                method: Permit::new(
+                    address,
+                    vec![market::MarketPermissions::AccountInfo],
+                    vec![market],
+                    "history",
+                )
+                .into(),
+                pagination: Pagination {
+                    start: 0,
+                    limit: 10,
+                },
             },
         )
         .unwrap()
@@ -484,12 +546,16 @@ impl Lend {
 
     #[inline]
     pub fn state(&self, market: HumanAddr, block: Option<u64>) -> market::State {
-        self.ensemble.query(market, market::QueryMsg::State { block }).unwrap()
+        self.ensemble
+            .query(market, market::QueryMsg::State { block, time: None })
+            .unwrap()
     }
 
     #[inline]
     pub fn exchange_rate(&self, market: HumanAddr, block: Option<u64>) -> Decimal256 {
-        self.ensemble.query(market, market::QueryMsg::ExchangeRate { block }).unwrap()
+        self.ensemble
+            .query(market, market::QueryMsg::ExchangeRate { block, time: None })
+            .unwrap()
     }
 }
 
@@ -0,0 +1,286 @@
+use lend_shared::{
+    fadroma::{
+        cosmwasm_std::{to_binary, StdError, Uint128},
+        ensemble::MockEnv,
+        snip20_impl::msg as snip20,
+        Decimal256, Uint256, one_token
+    },
+    interfaces::{market, overseer},
+};
+
+use crate::setup::Lend;
+
+const BOB: &str = "Bob";
+const ALICE: &str = "Alice";
+const CHESTER: &str = "Chester";
+
+#[test]
+fn borrow_and_repay_stable() {
+    let mut lend = Lend::default();
+    lend.ensemble.block().freeze();
+
+    let underlying_1 = lend.new_underlying_token("SSCRT", 6).unwrap();
+    let underlying_2 = lend.new_underlying_token("SIENNA", 18).unwrap();
+
+    let mut config = lend
+        .market_config(underlying_1.clone(), Decimal256::percent(90))
+        .unwrap();
+    config.config.stable_rate_threshold = Some(Decimal256::percent(80));
+    config.config.stable_rate_rebalance_delta = None;
+
+    let market_one = lend.whitelist_market_with(config).unwrap().contract;
+
+    let market_two = lend
+        .whitelist_market(underlying_2, Decimal256::percent(90), None, None)
+        .unwrap()
+        .contract;
+
+    let prefund_amount = Uint128(500 * one_token(6));
+    let collateral_amount = Uint128(200 * one_token(18));
+    let borrow_amount = Uint256::from(100 * one_token(6));
+
+    lend.prefund_and_deposit(BOB, prefund_amount, market_one.address.clone());
+    lend.prefund_and_deposit(CHESTER, collateral_amount, market_two.address.clone());
+
+    lend.ensemble
+        .execute(
+            &overseer::HandleMsg::Enter {
+                markets: vec![market_one.address.clone(), market_two.address.clone()],
+            },
+            MockEnv::new(CHESTER, lend.overseer.clone()),
+        )
+        .unwrap();
+
+    let err = lend.ensemble
+        .execute(
+            &market::HandleMsg::BorrowStable {
+                amount: borrow_amount,
+            },
+            MockEnv::new(CHESTER, market_two.clone()),
+        )
+        .unwrap_err();
+
+    assert_eq!(err, StdError::generic_err("Stable rate borrowing is not enabled in this market."));
+
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::BorrowStable {
+                amount: borrow_amount,
+            },
+            MockEnv::new(CHESTER, market_one.clone()),
+        )
+        .unwrap();
+
+    let variable_rate: Decimal256 = lend.ensemble
+        .query(
+            market_one.address.clone(),
+            market::QueryMsg::BorrowRate { block: None, time: None },
+        )
+        .unwrap();
+    let stable_rate: Decimal256 = lend.ensemble
+        .query(
+            market_one.address.clone(),
+            market::QueryMsg::StableBorrowRate { block: None, time: None },
+        )
+        .unwrap();
+
+    assert!(stable_rate > variable_rate);
+
+    let info = lend.account_info(CHESTER, market_one.address.clone());
+    assert_eq!(info.borrow_balance, Uint256::zero());
+    assert_eq!(info.stable_borrow_balance, borrow_amount);
+
+    let state = lend.state(market_one.address.clone(), None);
+    assert_eq!(state.total_borrows, Uint256::zero());
+    assert_eq!(state.total_stable_borrows, borrow_amount);
+
+    lend.ensemble.block().height += 1000;
+
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::AccrueInterest {},
+            MockEnv::new(BOB, market_one.clone()),
+        )
+        .unwrap();
+
+    let info = lend.account_info(CHESTER, market_one.address.clone());
+    assert!(info.stable_borrow_balance > borrow_amount);
+
+    let state = lend.state(market_one.address.clone(), None);
+    assert_eq!(state.total_stable_borrows, info.stable_borrow_balance);
+    assert!(state.average_stable_rate > Decimal256::zero());
+
+    let owed = info.stable_borrow_balance;
+
+    // Repay more than owed, the remainder should be sent back.
+    let repay_amount = Uint128(110 * one_token(6));
+    lend.prefund_user(CHESTER, Uint128(10 * one_token(6)), underlying_1.clone());
+
+    lend.ensemble
+        .execute(
+            &snip20::HandleMsg::Send {
+                recipient: market_one.address.clone(),
+                recipient_code_hash: None,
+                amount: repay_amount,
+                msg: Some(to_binary(&market::ReceiverCallbackMsg::RepayStable { borrower: None }).unwrap()),
+                memo: None,
+                padding: None,
+            },
+            MockEnv::new(CHESTER, underlying_1.clone()),
+        )
+        .unwrap();
+
+    let info = lend.account_info(CHESTER, market_one.address.clone());
+    assert_eq!(info.stable_borrow_balance, Uint256::zero());
+
+    let state = lend.state(market_one.address.clone(), None);
+    assert_eq!(state.total_stable_borrows, Uint256::zero());
+
+    let balance = lend.token_balance(CHESTER, underlying_1.address);
+    assert_eq!(Uint256::from(balance), (Uint256::from(repay_amount) - owed).unwrap());
+
+    let history = lend.history(CHESTER, market_one.address);
+    assert_eq!(history.entries[1].event, market::HistoryEvent::Repay { amount: owed, stable: true });
+}
+
+#[test]
+fn rebalance_stable_rate() {
+    let mut lend = Lend::default();
+    lend.ensemble.block().freeze();
+
+    let underlying_1 = lend.new_underlying_token("SSCRT", 6).unwrap();
+    let underlying_2 = lend.new_underlying_token("SIENNA", 18).unwrap();
+
+    let mut config = lend
+        .market_config(underlying_1.clone(), Decimal256::percent(90))
+        .unwrap();
+    config.config.stable_rate_threshold = Some(Decimal256::percent(80));
+    config.config.stable_rate_rebalance_delta = Some(Decimal256::percent(10));
+
+    let market_one = lend.whitelist_market_with(config).unwrap().contract;
+
+    let market_two = lend
+        .whitelist_market(underlying_2, Decimal256::percent(90), None, None)
+        .unwrap()
+        .contract;
+
+    lend.prefund_and_deposit(BOB, Uint128(500 * one_token(6)), market_one.address.clone());
+    lend.prefund_and_deposit(CHESTER, Uint128(200 * one_token(18)), market_two.address.clone());
+    lend.prefund_and_deposit(ALICE, Uint128(400 * one_token(18)), market_two.address.clone());
+
+    for user in &[CHESTER, ALICE] {
+        lend.ensemble
+            .execute(
+                &overseer::HandleMsg::Enter {
+                    markets: vec![market_one.address.clone(), market_two.address.clone()],
+                },
+                MockEnv::new(*user, lend.overseer.clone()),
+            )
+            .unwrap();
+    }
+
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::BorrowStable {
+                amount: Uint256::from(100 * one_token(6)),
+            },
+            MockEnv::new(CHESTER, market_one.clone()),
+        )
+        .unwrap();
+
+    let chester = lend.id(CHESTER, market_one.address.clone());
+
+    // Utilization is at 20% and the rate hasn't changed.
+    let err = lend.ensemble
+        .execute(
+            &market::HandleMsg::RebalanceStableRate {
+                borrower: chester.clone(),
+            },
+            MockEnv::new(BOB, market_one.clone()),
+        )
+        .unwrap_err();
+
+    assert!(matches!(err, StdError::GenericErr { msg, .. } if msg.starts_with("Stable rate cannot be rebalanced.")));
+
+    let rate_before = lend.state(market_one.address.clone(), None).average_stable_rate;
+
+    // Push utilization to 84%.
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::Borrow {
+                amount: Uint256::from(320 * one_token(6)),
+            },
+            MockEnv::new(ALICE, market_one.clone()),
+        )
+        .unwrap();
+
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::RebalanceStableRate {
+                borrower: chester.clone(),
+            },
+            MockEnv::new(BOB, market_one.clone()),
+        )
+        .unwrap();
+
+    let state = lend.state(market_one.address.clone(), None);
+    assert!(state.average_stable_rate > rate_before);
+    assert_eq!(state.total_stable_borrows, Uint256::from(100 * one_token(6)));
+
+    // Already at the current rate.
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::RebalanceStableRate {
+                borrower: chester.clone(),
+            },
+            MockEnv::new(BOB, market_one.clone()),
+        )
+        .unwrap_err();
+
+    let repay = |lend: &mut Lend, amount: u128| {
+        lend.ensemble
+            .execute(
+                &snip20::HandleMsg::Send {
+                    recipient: market_one.address.clone(),
+                    recipient_code_hash: None,
+                    amount: Uint128(amount * one_token(6)),
+                    msg: Some(to_binary(&market::ReceiverCallbackMsg::Repay { borrower: None }).unwrap()),
+                    memo: None,
+                    padding: None,
+                },
+                MockEnv::new(ALICE, underlying_1.clone()),
+            )
+            .unwrap();
+    };
+
+    // Utilization drops to 80%, the rate is lower but not by the delta.
+    repay(&mut lend, 20);
+
+    let err = lend.ensemble
+        .execute(
+            &market::HandleMsg::RebalanceStableRate {
+                borrower: chester.clone(),
+            },
+            MockEnv::new(BOB, market_one.clone()),
+        )
+        .unwrap_err();
+
+    assert!(matches!(err, StdError::GenericErr { msg, .. } if msg.starts_with("Stable rate cannot be rebalanced.")));
+
+    // Back to 20%, now it can be rebalanced down.
+    repay(&mut lend, 300);
+
+    let rate_after_up = lend.state(market_one.address.clone(), None).average_stable_rate;
+
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::RebalanceStableRate {
+                borrower: chester,
+            },
+            MockEnv::new(BOB, market_one.clone()),
+        )
+        .unwrap();
+
+    let state = lend.state(market_one.address.clone(), None);
+    assert!(state.average_stable_rate < rate_after_up);
+}
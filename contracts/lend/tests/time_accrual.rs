@@ -0,0 +1,189 @@
+use std::str::FromStr;
+
+use lend_shared::{
+    fadroma::{
+        cosmwasm_std::{HumanAddr, StdError, Uint128},
+        ensemble::MockEnv,
+        ContractLink, Decimal256, Uint256, one_token
+    },
+    interfaces::{interest_model, market, overseer},
+    core::AccrualMode,
+};
+
+use crate::setup::{Lend, ADMIN};
+
+const BOB: &str = "Bob";
+const ALICE: &str = "Alice";
+
+fn new_time_interest_model(lend: &mut Lend) -> ContractLink<HumanAddr> {
+    lend.ensemble
+        .instantiate(
+            lend.interest.id,
+            &interest_model::InitMsg {
+                admin: None,
+                base_rate_year: Decimal256::from_str("0.02").unwrap(),
+                multiplier_year: Decimal256::from_str("0.02").unwrap(),
+                jump_multiplier_year: Decimal256::from_str("0.02").unwrap(),
+                jump_threshold: Decimal256::from_str("0.09").unwrap(),
+                blocks_year: None,
+                stable_premium_year: None,
+                accrual_mode: Some(AccrualMode::Time),
+            },
+            MockEnv::new(
+                ADMIN,
+                ContractLink {
+                    address: "time_interest_model".into(),
+                    code_hash: lend.interest.code_hash.clone(),
+                },
+            ),
+        )
+        .unwrap()
+}
+
+fn accrue(lend: &mut Lend, market: &ContractLink<HumanAddr>) {
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::AccrueInterest {},
+            MockEnv::new(BOB, market.clone()),
+        )
+        .unwrap();
+}
+
+#[test]
+fn accrues_by_elapsed_time() {
+    let mut lend = Lend::default();
+    lend.ensemble.block().freeze();
+
+    let underlying_1 = lend.new_underlying_token("ONE", 18).unwrap();
+    let underlying_2 = lend.new_underlying_token("TWO", 18).unwrap();
+
+    // the interest model must use the same mode as the market
+    let mut config = lend
+        .market_config(underlying_1.clone(), Decimal256::percent(90))
+        .unwrap();
+    config.config.accrual_mode = AccrualMode::Time;
+
+    let err = lend.whitelist_market_with(config).unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err(
+            "The interest model accrues in Block mode, but the market accrues in Time mode."
+        )
+    );
+
+    let mut config = lend.market_config(underlying_1, Decimal256::percent(90)).unwrap();
+    config.config.accrual_mode = AccrualMode::Time;
+    config.interest_model_contract = new_time_interest_model(&mut lend);
+
+    let market_one = lend.whitelist_market_with(config).unwrap().contract;
+
+    // and can't be swapped for one that doesn't
+    let err = lend
+        .ensemble
+        .execute(
+            &market::HandleMsg::UpdateConfig {
+                interest_model: Some(lend.interest_model.clone()),
+                reserve_factor: None,
+                borrow_cap: None,
+                flash_loan_fee: None,
+            },
+            MockEnv::new(ADMIN, market_one.clone()),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err(
+            "The interest model accrues in Block mode, but the market accrues in Time mode."
+        )
+    );
+    let market_two = lend
+        .whitelist_market(underlying_2, Decimal256::percent(90), None, None)
+        .unwrap()
+        .contract;
+
+    lend.prefund_and_deposit(BOB, Uint128(100 * one_token(18)), market_one.address.clone());
+    lend.prefund_and_deposit(ALICE, Uint128(100 * one_token(18)), market_two.address.clone());
+
+    lend.ensemble
+        .execute(
+            &overseer::HandleMsg::Enter {
+                markets: vec![market_one.address.clone(), market_two.address.clone()],
+            },
+            MockEnv::new(ALICE, lend.overseer.clone()),
+        )
+        .unwrap();
+
+    // liquidity is checked across markets that accrue differently
+    let borrow_amount = Uint256::from(10 * one_token(18));
+    lend.ensemble
+        .execute(
+            &market::HandleMsg::Borrow { amount: borrow_amount },
+            MockEnv::new(ALICE, market_one.clone()),
+        )
+        .unwrap();
+
+    let time = lend.ensemble.block().time;
+    let state = lend.state(market_one.address.clone(), None);
+    assert_eq!(state.accrual_block, time);
+    assert_eq!(state.total_borrows, borrow_amount);
+
+    // blocks passing without time passing don't accrue anything
+    lend.ensemble.block().height += 1000;
+    accrue(&mut lend, &market_one);
+
+    let state = lend.state(market_one.address.clone(), None);
+    assert_eq!(state.accrual_block, time);
+    assert_eq!(state.total_borrows, borrow_amount);
+
+    let simulated: market::State = lend
+        .ensemble
+        .query(
+            market_one.address.clone(),
+            market::QueryMsg::State {
+                block: None,
+                time: Some(time + 1000),
+            },
+        )
+        .unwrap();
+    assert!(simulated.total_borrows > borrow_amount);
+
+    lend.ensemble.block().time += 1000;
+    accrue(&mut lend, &market_one);
+
+    let state = lend.state(market_one.address.clone(), None);
+    assert_eq!(state.accrual_block, time + 1000);
+    assert_eq!(state.total_borrows, simulated.total_borrows);
+
+    let info = lend.account_info(ALICE, market_one.address.clone());
+    assert_eq!(info.borrow_balance, simulated.total_borrows);
+}
+
+#[test]
+fn updating_the_interest_model_keeps_its_accrual_mode() {
+    let mut lend = Lend::default();
+    let time_interest_model = new_time_interest_model(&mut lend);
+
+    lend.ensemble
+        .execute(
+            &interest_model::HandleMsg::UpdateConfig {
+                base_rate_year: Decimal256::from_str("0.03").unwrap(),
+                multiplier_year: Decimal256::from_str("0.03").unwrap(),
+                jump_multiplier_year: Decimal256::from_str("0.03").unwrap(),
+                jump_threshold: Decimal256::from_str("0.09").unwrap(),
+                blocks_year: None,
+                stable_premium_year: None,
+                accrual_mode: None,
+            },
+            MockEnv::new(ADMIN, time_interest_model.clone()),
+        )
+        .unwrap();
+
+    let mode: AccrualMode = lend
+        .ensemble
+        .query(
+            time_interest_model.address,
+            interest_model::QueryMsg::AccrualMode {},
+        )
+        .unwrap();
+    assert_eq!(mode, AccrualMode::Time);
+}
@@ -73,6 +73,7 @@ fn transfer_basic() {
                 )
                 .into(),
                 block: None,
+                time: None,
             },
         )
         .unwrap();
@@ -92,6 +93,7 @@ fn transfer_basic() {
                 )
                 .into(),
                 block: None,
+                time: None,
             },
         )
         .unwrap();
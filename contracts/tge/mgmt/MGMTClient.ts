@@ -77,11 +77,61 @@ export abstract class MGMTClient extends Client {
       return this.execute({ claim: {} })
     }
 
+    /** claim accumulated portions on behalf of a recipient (operator only, if the recipient allows it) */
+    claimFor(recipient: string) {
+      return this.execute({ claim_for: { recipient } })
+    }
+
+    /** allow or forbid the operator to claim on behalf of the sender */
+    allowOperatorClaims(allowed: boolean) {
+      return this.execute({ allow_operator_claims: { allowed } })
+    }
+
+    /** propose a different address to receive the claims of the sender */
+    proposePayoutAddress(address: string) {
+      return this.execute({ propose_payout_address: { address } })
+    }
+
+    /** accept receiving the claims of a recipient */
+    acceptPayoutAddress(recipient: string) {
+      return this.execute({ accept_payout_address: { recipient } })
+    }
+
+    /** set or unset the operator that can claim on behalf of recipients */
+    setOperator(operator?: string) {
+      return this.execute({ set_operator: { operator } })
+    }
+
     /** add a new account to a pool */
     add(pool_name: any, account: any) {
       return this.execute({ add_account: { pool_name, account } })
     }
 
+    /** set how long schedule changes wait before they can be executed (before launch only) */
+    setTimelock(timelock: number) {
+      return this.execute({ set_timelock: { timelock } })
+    }
+
+    /** queue a schedule change (after launch) */
+    proposeChange(change: any) {
+      return this.execute({ propose_change: { change } })
+    }
+
+    /** apply a queued schedule change once its timelock has passed */
+    executeChange(id: number) {
+      return this.execute({ execute_change: { id } })
+    }
+
+    /** drop a queued schedule change */
+    cancelChange(id: number) {
+      return this.execute({ cancel_change: { id } })
+    }
+
+    /** queue stopping the vesting of a revocable account (after launch) */
+    revoke(pool: string, name: string, treasury?: string) {
+      return this.proposeChange({ revoke_account: { pool, name, treasury } })
+    }
+
     /** Change the admin of the contract, requires the other user to accept */
     change_admin(new_admin: any) {
       return this.execute({ auth: { change_admin: { address: new_admin } } })
@@ -101,16 +151,53 @@ export abstract class MGMTClient extends Client {
       return this.query({ history: { start, limit } })
     }
 
+    /** claims and revocations of a single address, optionally within [from, to] (in seconds) */
+    claimantHistory(address: string, start: number, limit: number, range?: { from?: number, to?: number }) {
+      return this.query({ claimant_history: { address, range, pagination: { start, limit } } })
+    }
+
+    /** claims and revocations of the accounts in a pool, optionally within [from, to] (in seconds) */
+    poolHistory(pool: string, start: number, limit: number, range?: { from?: number, to?: number }) {
+      return this.query({ pool_history: { pool, range, pagination: { start, limit } } })
+    }
+
     config() {
       return this.query({ config: {} })
     }
 
+    payoutAddress(address: string): Promise<{ address: string, proposed: string|null, operator_allowed: boolean }> {
+      return this.query({ payout_address: { address } })
+    }
+
+    operator(): Promise<string|null> {
+      return this.query({ operator: {} })
+    }
+
+    /** unlocked, claimed and locked totals at a certain time */
+    async supply(time = +new Date()): Promise<{
+      elapsed: number
+      total: string
+      unlocked: string
+      claimed: string
+      unclaimed: string
+      locked: string
+      pools: { name: string, total: string, unlocked: string, locked: string }[]
+    }> {
+      time = Math.floor(time / 1000) // JS msec -> CosmWasm seconds
+      return this.query({ supply: { time } })
+    }
+
+    pendingChanges(): Promise<{ timelock: number, changes: any[] }> {
+      return this.query({ pending_changes: {} })
+    }
+
     async progress(address: any, time = +new Date()): Promise<{
       time: number
       launcher: number
       elapsed: number
       unlocked: string
       claimed: string
+      revoked: boolean
     }> {
       time = Math.floor(time / 1000) // JS msec -> CosmWasm seconds
       const { progress } = await this.query({ progress: { address, time } })
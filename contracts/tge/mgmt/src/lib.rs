@@ -1,14 +1,15 @@
 mod migration;
 mod state;
 
-pub use state::{Claim, Pagination};
+pub use state::{Claim, Event, Pagination, Proposal, ScheduleChange, TimeRange};
 
 use fadroma::{
     admin,
     admin::assert_admin,
     cosmwasm_std,
     cosmwasm_std::{
-        log, CanonicalAddr, HandleResponse, HumanAddr, InitResponse, StdError, StdResult, Uint128,
+        log, Api, CanonicalAddr, CosmosMsg, Env, Extern, HandleResponse, HumanAddr, InitResponse,
+        Querier, StdError, StdResult, Storage, Uint128,
     },
     derive_contract::*,
     killswitch, require_admin, schemars,
@@ -18,7 +19,7 @@ use fadroma::{
 use serde::{Deserialize, Serialize};
 
 use migration::MgmtKillswitch;
-use state::{Config, History, Participant};
+use state::{Config, History, OperatorClaims, Participant, Payout, Proposals};
 
 use sienna_schedule::{validate::Validation, vesting::Vesting, Account, Pool, Schedule, Seconds};
 
@@ -46,6 +47,18 @@ macro_rules! MGMTError {
     (ADD_ACCOUNT) => {
         "Can't add account - pool full".to_string()
     };
+    (TIMELOCKED) => {
+        "Schedule changes after launch must be proposed and wait for the timelock.".to_string()
+    };
+    (NO_CHANGE, $id:expr) => {
+        format!("No pending schedule change with id {}", $id)
+    };
+    (TOO_EARLY, $executable_at:expr) => {
+        format!("This change can't be executed before {}", $executable_at)
+    };
+    (NO_PROPOSAL) => {
+        "No payout address has been proposed for this recipient.".to_string()
+    };
     (PREFUND, $balance:expr, $required:expr) => {
         format!(
             "Required prefund balance: {}, actual balance: {}",
@@ -118,19 +131,90 @@ pub trait Mgmt {
         })
     }
 
+    /// Only possible before launch. Afterwards, use `ProposeChange`.
     #[handle]
     #[require_admin]
     fn add_account(pool_name: String, account: Account<HumanAddr>) -> StdResult<HandleResponse> {
-        let account = account.canonize(&deps.api)?;
+        assert_not_timelocked(&deps.storage)?;
+
+        let change = ScheduleChange::AddAccount { pool_name, account };
+        apply_change(deps, &env, change.canonize(&deps.api)?)?;
+
+        Ok(HandleResponse {
+            messages: vec![],
+            log: vec![log("action", "add_account")],
+            data: None,
+        })
+    }
+
+    /// Only possible before launch, after which the timelock can't be shortened.
+    #[handle]
+    #[require_admin]
+    fn set_timelock(timelock: Seconds) -> StdResult<HandleResponse> {
+        Config::assert_not_launched(&deps.storage)?;
+        Config::set_timelock(&mut deps.storage, timelock)?;
+
+        Ok(HandleResponse {
+            messages: vec![],
+            log: vec![log("action", "set_timelock"), log("timelock", timelock)],
+            data: None,
+        })
+    }
 
+    /// Schedule changes after launch are queued and can be inspected by
+    /// everyone via `PendingChanges` until the timelock passes.
+    /// This includes revoking accounts, which only stops their vesting
+    /// once the change is executed.
+    #[handle]
+    #[require_admin]
+    fn propose_change(change: ScheduleChange<HumanAddr>) -> StdResult<HandleResponse> {
+        let launched = Config::assert_launched(&deps.storage)?;
+        let elapsed = get_elapsed(env.block.time, launched);
+
+        let change = change.canonize(&deps.api)?;
+
+        // Fail early if the change could never be applied.
         let mut schedule = Config::load_schedule(&deps.storage)?;
-        schedule.add_account(&pool_name, account)?;
+        change_schedule(&mut schedule, change.clone(), elapsed)?;
 
-        Config::save_schedule(deps, schedule.humanize(&deps.api)?)?;
+        let id = Proposals::push(&mut deps.storage, change, env.block.time)?;
 
         Ok(HandleResponse {
             messages: vec![],
-            log: vec![log("action", "add_account")],
+            log: vec![log("action", "propose_change"), log("id", id)],
+            data: None,
+        })
+    }
+
+    #[handle]
+    #[require_admin]
+    fn execute_change(id: u64) -> StdResult<HandleResponse> {
+        let proposal = Proposals::take(&mut deps.storage, id)?;
+
+        if env.block.time < proposal.executable_at {
+            return Err(StdError::generic_err(MGMTError!(
+                TOO_EARLY,
+                proposal.executable_at
+            )));
+        }
+
+        let messages = apply_change(deps, &env, proposal.change)?;
+
+        Ok(HandleResponse {
+            messages,
+            log: vec![log("action", "execute_change"), log("id", id)],
+            data: None,
+        })
+    }
+
+    #[handle]
+    #[require_admin]
+    fn cancel_change(id: u64) -> StdResult<HandleResponse> {
+        Proposals::take(&mut deps.storage, id)?;
+
+        Ok(HandleResponse {
+            messages: vec![],
+            log: vec![log("action", "cancel_change"), log("id", id)],
             data: None,
         })
     }
@@ -198,76 +282,113 @@ pub trait Mgmt {
     /// receive the gains that they have accumulated so far.
     #[handle]
     fn claim() -> StdResult<HandleResponse> {
-        let launched = Config::assert_launched(&deps.storage)?;
-        let elapsed = get_elapsed(env.block.time, launched);
+        let recipient = env.message.sender.clone();
 
-        let mut claimant = Participant::new(deps, &env.message.sender)?;
-        let schedule = Config::load_schedule(&deps.storage)?;
+        claim_portion(deps, env, recipient)
+    }
+
+    /// Lets the operator claim on behalf of a recipient, so that payouts
+    /// land even when the recipient is inactive. The funds still go to
+    /// the payout address of the recipient. Only possible for recipients
+    /// that have opted in via `AllowOperatorClaims`.
+    #[handle]
+    fn claim_for(recipient: HumanAddr) -> StdResult<HandleResponse> {
+        let sender = env.message.sender.canonize(&deps.api)?;
+        let canonical = recipient.canonize(&deps.api)?;
+
+        if sender != canonical
+            && (Config::load_operator(&deps.storage)? != Some(sender)
+                || !OperatorClaims::is_allowed(&deps.storage, &canonical)?)
+        {
+            return Err(StdError::unauthorized());
+        }
 
-        let (unlocked, claimable) = portion(&schedule, &claimant, elapsed);
+        claim_portion(deps, env, recipient)
+    }
+
+    /// Allow or forbid the operator to claim on behalf of the sender.
+    #[handle]
+    fn allow_operator_claims(allowed: bool) -> StdResult<HandleResponse> {
+        let recipient = env.message.sender.canonize(&deps.api)?;
+        OperatorClaims::set_allowed(&mut deps.storage, &recipient, allowed)?;
+
+        Ok(HandleResponse {
+            messages: vec![],
+            log: vec![
+                log("action", "allow_operator_claims"),
+                log("allowed", allowed),
+            ],
+            data: None,
+        })
+    }
+
+    /// Propose a different address to receive the claims of the sender.
+    /// Takes effect once the proposed address accepts it.
+    #[handle]
+    fn propose_payout_address(address: HumanAddr) -> StdResult<HandleResponse> {
+        let recipient = env.message.sender.canonize(&deps.api)?;
+        Payout::propose(&mut deps.storage, &recipient, &address.canonize(&deps.api)?)?;
+
+        Ok(HandleResponse {
+            messages: vec![],
+            log: vec![
+                log("action", "propose_payout_address"),
+                log("address", address),
+            ],
+            data: None,
+        })
+    }
+
+    /// Called by the proposed address to start receiving the claims of `recipient`.
+    #[handle]
+    fn accept_payout_address(recipient: HumanAddr) -> StdResult<HandleResponse> {
+        let canonical = recipient.canonize(&deps.api)?;
+        let sender = env.message.sender.canonize(&deps.api)?;
 
-        if claimable.eq(&u128::MIN) {
-            return Err(StdError::generic_err(MGMTError!(NOTHING)));
+        match Payout::load_proposed(&deps.storage, &canonical)? {
+            Some(proposed) if proposed == sender => {
+                Payout::accept(&mut deps.storage, &canonical, &sender)?
+            }
+            Some(_) => return Err(StdError::unauthorized()),
+            None => return Err(StdError::generic_err(MGMTError!(NO_PROPOSAL))),
         }
 
-        claimant.set_claimed(&mut deps.storage, unlocked.into())?;
-        History::push(
-            &mut deps.storage,
-            Claim::new(claimant, &env.block, claimable.into()),
-        )?;
+        Ok(HandleResponse {
+            messages: vec![],
+            log: vec![
+                log("action", "accept_payout_address"),
+                log("recipient", recipient),
+            ],
+            data: None,
+        })
+    }
 
-        let token = Config::load_token(deps)?;
+    /// Set or unset the address that is allowed to claim on behalf of recipients.
+    #[handle]
+    #[require_admin]
+    fn set_operator(operator: Option<HumanAddr>) -> StdResult<HandleResponse> {
+        Config::save_operator(deps, operator)?;
 
         Ok(HandleResponse {
-            messages: vec![snip20::transfer_msg(
-                env.message.sender,
-                claimable.into(),
-                None,
-                None,
-                BLOCK_SIZE,
-                token.code_hash,
-                token.address,
-            )?],
-            log: vec![log("action", "claim"), log("claimed", claimable)],
+            messages: vec![],
+            log: vec![log("action", "set_operator")],
             data: None,
         })
     }
+
     #[handle]
     #[require_admin]
     fn increase_allocation(
         total_increment: Uint128,
         pool: Pool<HumanAddr>,
     ) -> StdResult<HandleResponse> {
-        let mut schedule = Config::load_schedule(&deps.storage)?;
-        let token = Config::load_token(&deps)?;
-        let pool = pool.canonize(&deps.api)?;
-
-        schedule.total += total_increment;
-        schedule.pools.push(pool);
-
-        schedule.validate()?;
+        assert_not_timelocked(&deps.storage)?;
 
-        let total_claimed = History::total_claimed(&deps.storage)?;
-
-        let balance = snip20::balance_query(
-            &deps.querier,
-            env.contract.address,
-            VIEWING_KEY.into(),
-            BLOCK_SIZE,
-            token.code_hash,
-            token.address,
-        )?
-        .amount;
-
-        if balance < (schedule.total - total_claimed).unwrap() {
-            return Err(StdError::generic_err(MGMTError!(
-                PREFUND,
-                balance,
-                schedule.total
-            )));
-        }
-        
-        Config::save_schedule(deps, schedule.humanize(&deps.api)?)?;
+        let change = ScheduleChange::IncreaseAllocation {
+            total_increment,
+            pool,
+        };
+        apply_change(deps, &env, change.canonize(&deps.api)?)?;
 
         Ok(HandleResponse::default())
     }
@@ -282,11 +403,95 @@ pub trait Mgmt {
         let schedule = Config::load_schedule(&deps.storage)?;
         let unlocked = schedule.unlocked(elapsed, &participant.address).into();
 
+        let revoked = schedule.pools.iter().any(|pool| {
+            pool.accounts
+                .iter()
+                .any(|a| a.address == participant.address && a.revoked_at.is_some())
+        });
+
         Ok(ProgressResponse {
             launched,
             elapsed,
             unlocked,
             claimed: participant.claimed(),
+            revoked,
+        })
+    }
+
+    /// Totals needed to compute the circulating supply at a given time.
+    #[query]
+    fn supply(time: Seconds) -> StdResult<SupplyResponse> {
+        let launched = Config::assert_launched(&deps.storage)?;
+        let elapsed = get_elapsed(time, launched);
+
+        let schedule = Config::load_schedule(&deps.storage)?;
+
+        let pools = schedule
+            .pools
+            .iter()
+            .map(|pool| {
+                let unlocked = pool
+                    .accounts
+                    .iter()
+                    .fold(0, |total, account| total + account.vested(elapsed));
+
+                PoolSupply {
+                    name: pool.name.clone(),
+                    total: pool.total,
+                    unlocked: unlocked.into(),
+                    locked: (pool.total.u128() - unlocked).into(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let unlocked = pools.iter().fold(0, |total, pool| total + pool.unlocked.u128());
+        let claimed = History::total_claimed(&deps.storage)?;
+
+        Ok(SupplyResponse {
+            elapsed,
+            total: schedule.total,
+            unlocked: unlocked.into(),
+            claimed,
+            unclaimed: unlocked.saturating_sub(claimed.u128()).into(),
+            locked: (schedule.total.u128() - unlocked).into(),
+            pools,
+        })
+    }
+
+    #[query]
+    fn payout_address(address: HumanAddr) -> StdResult<PayoutAddressResponse> {
+        let recipient = address.canonize(&deps.api)?;
+
+        let proposed = match Payout::load_proposed(&deps.storage, &recipient)? {
+            Some(proposed) => Some(proposed.humanize(&deps.api)?),
+            None => None,
+        };
+
+        Ok(PayoutAddressResponse {
+            address: Payout::load(&deps.storage, &recipient)?.humanize(&deps.api)?,
+            proposed,
+            operator_allowed: OperatorClaims::is_allowed(&deps.storage, &recipient)?,
+        })
+    }
+
+    #[query]
+    fn operator() -> StdResult<Option<HumanAddr>> {
+        match Config::load_operator(&deps.storage)? {
+            Some(operator) => Ok(Some(operator.humanize(&deps.api)?)),
+            None => Ok(None),
+        }
+    }
+
+    #[query]
+    fn pending_changes() -> StdResult<PendingChangesResponse> {
+        let changes = Proposals::pending(&deps.storage)?
+            .into_iter()
+            .map(|proposal| proposal.humanize(&deps.api))
+            .collect::<StdResult<Vec<Proposal<HumanAddr>>>>()?;
+
+        Ok(PendingChangesResponse {
+            timelock: Config::get_timelock(&deps.storage)?,
+            changes,
         })
     }
 
@@ -303,6 +508,26 @@ pub trait Mgmt {
         History::list(deps, pagination)
     }
 
+    /// Claims and revocations of a single address.
+    #[query]
+    fn claimant_history(
+        address: HumanAddr,
+        range: Option<TimeRange>,
+        pagination: Pagination,
+    ) -> StdResult<HistoryResponse> {
+        History::list_by_claimant(deps, &address, range.unwrap_or_default(), pagination)
+    }
+
+    /// Claims by addresses that have accounts in the pool, and revocations in the pool.
+    #[query]
+    fn pool_history(
+        pool: String,
+        range: Option<TimeRange>,
+        pagination: Pagination,
+    ) -> StdResult<HistoryResponse> {
+        History::list_by_pool(deps, &pool, range.unwrap_or_default(), pagination)
+    }
+
     #[query]
     fn schedule() -> StdResult<Schedule<HumanAddr>> {
         let schedule = Config::load_schedule(&deps.storage)?;
@@ -332,6 +557,228 @@ pub struct ProgressResponse {
     pub elapsed: Seconds,
     pub unlocked: Uint128,
     pub claimed: Uint128,
+    /// Whether any of the accounts of the address have been revoked.
+    pub revoked: bool,
+}
+
+#[derive(Serialize, Deserialize, schemars::JsonSchema, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SupplyResponse {
+    pub elapsed: Seconds,
+    pub total: Uint128,
+    /// Vested so far across all pools.
+    pub unlocked: Uint128,
+    /// Transferred out of the contract to the recipients.
+    pub claimed: Uint128,
+    /// Vested, but still held by the contract.
+    pub unclaimed: Uint128,
+    /// Not vested yet, including funds that aren't allocated to any account.
+    pub locked: Uint128,
+    pub pools: Vec<PoolSupply>,
+}
+
+#[derive(Serialize, Deserialize, schemars::JsonSchema, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct PoolSupply {
+    pub name: String,
+    pub total: Uint128,
+    pub unlocked: Uint128,
+    pub locked: Uint128,
+}
+
+#[derive(Serialize, Deserialize, schemars::JsonSchema, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct PayoutAddressResponse {
+    /// The address that receives the claims of the recipient.
+    pub address: HumanAddr,
+    /// An address that has been proposed but not yet accepted.
+    pub proposed: Option<HumanAddr>,
+    /// Whether the operator can claim on behalf of the recipient.
+    pub operator_allowed: bool,
+}
+
+#[derive(Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PendingChangesResponse {
+    pub timelock: Seconds,
+    pub changes: Vec<Proposal<HumanAddr>>,
+}
+
+fn assert_not_timelocked(storage: &impl Storage) -> StdResult<()> {
+    match Config::get_launched(storage)? {
+        Some(_) => Err(StdError::generic_err(MGMTError!(TIMELOCKED))),
+        None => Ok(()),
+    }
+}
+
+/// Returns the unvested remainder of a revoked account, or 0 for other changes.
+fn change_schedule(
+    schedule: &mut Schedule<CanonicalAddr>,
+    change: ScheduleChange<CanonicalAddr>,
+    elapsed: Seconds,
+) -> StdResult<u128> {
+    match change {
+        ScheduleChange::AddAccount { pool_name, account } => {
+            schedule.add_account(&pool_name, account)?;
+
+            Ok(0)
+        }
+        ScheduleChange::IncreaseAllocation {
+            total_increment,
+            pool,
+        } => {
+            schedule.total += total_increment;
+            schedule.pools.push(pool);
+            schedule.validate()?;
+
+            Ok(0)
+        }
+        ScheduleChange::RevokeAccount {
+            pool,
+            name,
+            treasury,
+        } => schedule.revoke_account(&pool, &name, elapsed, treasury.is_some()),
+    }
+}
+
+fn apply_change<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    change: ScheduleChange<CanonicalAddr>,
+) -> StdResult<Vec<CosmosMsg>> {
+    let increases_total = matches!(change, ScheduleChange::IncreaseAllocation { .. });
+
+    let revoked = match &change {
+        ScheduleChange::RevokeAccount {
+            pool,
+            name,
+            treasury,
+        } => Some((pool.clone(), name.clone(), treasury.clone())),
+        _ => None,
+    };
+
+    let elapsed = match Config::get_launched(&deps.storage)? {
+        Some(launched) => get_elapsed(env.block.time, launched),
+        None => 0,
+    };
+
+    let mut schedule = Config::load_schedule(&deps.storage)?;
+    let unvested = change_schedule(&mut schedule, change, elapsed)?;
+
+    let mut messages = vec![];
+
+    if increases_total {
+        let token = Config::load_token(deps)?;
+        let total_claimed = History::total_claimed(&deps.storage)?;
+
+        let balance = snip20::balance_query(
+            &deps.querier,
+            env.contract.address.clone(),
+            VIEWING_KEY.into(),
+            BLOCK_SIZE,
+            token.code_hash,
+            token.address,
+        )?
+        .amount;
+
+        if balance < (schedule.total - total_claimed).unwrap() {
+            return Err(StdError::generic_err(MGMTError!(
+                PREFUND,
+                balance,
+                schedule.total
+            )));
+        }
+    }
+
+    if let Some((pool, name, treasury)) = revoked {
+        let address = schedule
+            .pools
+            .iter()
+            .find(|p| p.name == pool)
+            .and_then(|p| p.accounts.iter().find(|a| a.name == name))
+            .map(|a| a.address.clone())
+            .ok_or_else(|| StdError::generic_err(MGMTError!(CORRUPTED)))?;
+
+        History::push(
+            &mut deps.storage,
+            Claim::revoked(address, &env.block, unvested.into()),
+            &[pool],
+        )?;
+
+        if let Some(treasury) = treasury {
+            if unvested > 0 {
+                let token = Config::load_token(deps)?;
+
+                messages.push(snip20::transfer_msg(
+                    treasury.humanize(&deps.api)?,
+                    unvested.into(),
+                    None,
+                    None,
+                    BLOCK_SIZE,
+                    token.code_hash,
+                    token.address,
+                )?);
+            }
+        }
+    }
+
+    Config::save_schedule(deps, schedule.humanize(&deps.api)?)?;
+
+    Ok(messages)
+}
+
+fn claim_portion<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    recipient: HumanAddr,
+) -> StdResult<HandleResponse> {
+    let launched = Config::assert_launched(&deps.storage)?;
+    let elapsed = get_elapsed(env.block.time, launched);
+
+    let mut claimant = Participant::new(deps, &recipient)?;
+    let schedule = Config::load_schedule(&deps.storage)?;
+
+    let (unlocked, claimable) = portion(&schedule, &claimant, elapsed);
+
+    if claimable.eq(&u128::MIN) {
+        return Err(StdError::generic_err(MGMTError!(NOTHING)));
+    }
+
+    let payout = Payout::load(&deps.storage, &claimant.address)?.humanize(&deps.api)?;
+
+    let pools = schedule
+        .pools
+        .iter()
+        .filter(|pool| pool.accounts.iter().any(|a| a.address == claimant.address))
+        .map(|pool| pool.name.clone())
+        .collect::<Vec<_>>();
+
+    claimant.set_claimed(&mut deps.storage, unlocked.into())?;
+    History::push(
+        &mut deps.storage,
+        Claim::new(claimant, &env.block, claimable.into()),
+        &pools,
+    )?;
+
+    let token = Config::load_token(deps)?;
+
+    Ok(HandleResponse {
+        messages: vec![snip20::transfer_msg(
+            payout.clone(),
+            claimable.into(),
+            None,
+            None,
+            BLOCK_SIZE,
+            token.code_hash,
+            token.address,
+        )?],
+        log: vec![
+            log("action", "claim"),
+            log("claimed", claimable),
+            log("recipient", payout),
+        ],
+        data: None,
+    })
 }
 
 fn get_elapsed(t1: Seconds, t2: Seconds) -> Seconds {
@@ -4,11 +4,11 @@ use fadroma::{
         Uint128,
     },
     schemars,
-    storage::{load, ns_load, ns_save, save, IterableStorage},
+    storage::{load, ns_load, ns_remove, ns_save, save, IterableStorage},
     Canonize, ContractLink, Humanize,
 };
 use serde::{Deserialize, Serialize};
-use sienna_schedule::{Schedule, Seconds};
+use sienna_schedule::{Account, Pool, Schedule, Seconds};
 
 use crate::{HistoryResponse, MGMTError};
 
@@ -19,6 +19,11 @@ impl Config {
     const KEY_LAUNCHED: &'static [u8] = b"launched";
     const KEY_SCHEDULE: &'static [u8] = b"schedule";
     const KEY_PREFUNDED: &'static [u8] = b"prefunded";
+    const KEY_OPERATOR: &'static [u8] = b"operator";
+    const KEY_TIMELOCK: &'static [u8] = b"timelock";
+
+    /// How long proposed schedule changes wait before they can be executed, unless configured.
+    pub const DEFAULT_TIMELOCK: Seconds = 3 * 24 * 60 * 60;
 
     pub fn save_token<S: Storage, A: Api, Q: Querier>(
         deps: &mut Extern<S, A, Q>,
@@ -86,6 +91,185 @@ impl Config {
     pub fn load_schedule(storage: &impl Storage) -> StdResult<Schedule<CanonicalAddr>> {
         Ok(load(storage, Self::KEY_SCHEDULE)?.unwrap())
     }
+
+    pub fn save_operator<S: Storage, A: Api, Q: Querier>(
+        deps: &mut Extern<S, A, Q>,
+        operator: Option<HumanAddr>,
+    ) -> StdResult<()> {
+        let operator = match operator {
+            Some(operator) => Some(operator.canonize(&deps.api)?),
+            None => None,
+        };
+
+        save(&mut deps.storage, Self::KEY_OPERATOR, &operator)
+    }
+
+    #[inline]
+    pub fn load_operator(storage: &impl Storage) -> StdResult<Option<CanonicalAddr>> {
+        Ok(load(storage, Self::KEY_OPERATOR)?.unwrap_or_default())
+    }
+
+    #[inline]
+    pub fn set_timelock(storage: &mut impl Storage, timelock: Seconds) -> StdResult<()> {
+        save(storage, Self::KEY_TIMELOCK, &timelock)
+    }
+
+    #[inline]
+    pub fn get_timelock(storage: &impl Storage) -> StdResult<Seconds> {
+        Ok(load(storage, Self::KEY_TIMELOCK)?.unwrap_or(Self::DEFAULT_TIMELOCK))
+    }
+}
+
+/// A change to the schedule of a launched instance.
+#[derive(Serialize, Deserialize, schemars::JsonSchema, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleChange<T> {
+    AddAccount {
+        pool_name: String,
+        account: Account<T>,
+    },
+    IncreaseAllocation {
+        total_increment: Uint128,
+        pool: Pool<T>,
+    },
+    /// Stop the vesting of a revocable account when the change is executed.
+    /// Whatever has vested up to then can still be claimed. The unvested
+    /// remainder is sent to the `treasury` if one is given, otherwise it
+    /// returns to the unallocated funds of the pool.
+    RevokeAccount {
+        pool: String,
+        name: String,
+        treasury: Option<T>,
+    },
+}
+
+/// A schedule change waiting for its timelock to pass.
+#[derive(Serialize, Deserialize, schemars::JsonSchema, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Proposal<T> {
+    pub id: u64,
+    pub change: ScheduleChange<T>,
+    pub proposed_at: Seconds,
+    pub executable_at: Seconds,
+}
+
+pub struct Proposals;
+
+impl Proposals {
+    const KEY: &'static [u8] = b"proposals";
+    const KEY_NEXT_ID: &'static [u8] = b"proposals_next_id";
+
+    pub fn push(
+        storage: &mut impl Storage,
+        change: ScheduleChange<CanonicalAddr>,
+        now: Seconds,
+    ) -> StdResult<u64> {
+        let id: u64 = load(storage, Self::KEY_NEXT_ID)?.unwrap_or_default();
+        save(storage, Self::KEY_NEXT_ID, &(id + 1))?;
+
+        let mut pending = Self::pending(storage)?;
+        pending.push(Proposal {
+            id,
+            change,
+            proposed_at: now,
+            executable_at: now + Config::get_timelock(storage)?,
+        });
+
+        save(storage, Self::KEY, &pending)?;
+
+        Ok(id)
+    }
+
+    /// Removes the proposal from the pending list and returns it.
+    pub fn take(storage: &mut impl Storage, id: u64) -> StdResult<Proposal<CanonicalAddr>> {
+        let mut pending = Self::pending(storage)?;
+
+        let index = pending
+            .iter()
+            .position(|p| p.id == id)
+            .ok_or_else(|| StdError::generic_err(MGMTError!(NO_CHANGE, id)))?;
+        let proposal = pending.remove(index);
+
+        save(storage, Self::KEY, &pending)?;
+
+        Ok(proposal)
+    }
+
+    #[inline]
+    pub fn pending(storage: &impl Storage) -> StdResult<Vec<Proposal<CanonicalAddr>>> {
+        Ok(load(storage, Self::KEY)?.unwrap_or_default())
+    }
+}
+
+/// Where the claims of a recipient are sent. Changing it is a two-step process:
+/// the recipient proposes a new address, which then has to accept it.
+pub struct Payout;
+
+impl Payout {
+    const KEY: &'static [u8] = b"payout";
+    const KEY_PROPOSED: &'static [u8] = b"payout_proposed";
+
+    /// The recipient itself, unless it has redirected its claims.
+    #[inline]
+    pub fn load(storage: &impl Storage, recipient: &CanonicalAddr) -> StdResult<CanonicalAddr> {
+        Ok(ns_load(storage, Self::KEY, recipient.as_slice())?
+            .unwrap_or_else(|| recipient.clone()))
+    }
+
+    #[inline]
+    pub fn load_proposed(
+        storage: &impl Storage,
+        recipient: &CanonicalAddr,
+    ) -> StdResult<Option<CanonicalAddr>> {
+        ns_load(storage, Self::KEY_PROPOSED, recipient.as_slice())
+    }
+
+    #[inline]
+    pub fn propose(
+        storage: &mut impl Storage,
+        recipient: &CanonicalAddr,
+        address: &CanonicalAddr,
+    ) -> StdResult<()> {
+        ns_save(storage, Self::KEY_PROPOSED, recipient.as_slice(), address)
+    }
+
+    pub fn accept(
+        storage: &mut impl Storage,
+        recipient: &CanonicalAddr,
+        address: &CanonicalAddr,
+    ) -> StdResult<()> {
+        ns_remove(storage, Self::KEY_PROPOSED, recipient.as_slice());
+
+        ns_save(storage, Self::KEY, recipient.as_slice(), address)
+    }
+}
+
+/// Recipients that let the operator claim on their behalf. Off by default,
+/// so that contracts which claim for themselves (e.g. RPT) keep control
+/// over when their funds arrive.
+pub struct OperatorClaims;
+
+impl OperatorClaims {
+    const KEY: &'static [u8] = b"operator_claims";
+
+    #[inline]
+    pub fn is_allowed(storage: &impl Storage, recipient: &CanonicalAddr) -> StdResult<bool> {
+        Ok(ns_load(storage, Self::KEY, recipient.as_slice())?.unwrap_or_default())
+    }
+
+    pub fn set_allowed(
+        storage: &mut impl Storage,
+        recipient: &CanonicalAddr,
+        allowed: bool,
+    ) -> StdResult<()> {
+        if allowed {
+            ns_save(storage, Self::KEY, recipient.as_slice(), &allowed)
+        } else {
+            ns_remove(storage, Self::KEY, recipient.as_slice());
+
+            Ok(())
+        }
+    }
 }
 
 pub struct Participant {
@@ -129,6 +313,24 @@ pub struct Claim<T> {
     pub claimant: T,
     pub amount: Uint128,
     pub timestamp: u64,
+    #[serde(default)]
+    pub event: Event,
+}
+
+/// The kind of a history entry.
+#[derive(Serialize, Deserialize, schemars::JsonSchema, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Event {
+    /// The claimant received the amount.
+    Claim,
+    /// An account of the claimant was revoked and the amount was left unvested.
+    Revoke,
+}
+
+impl Default for Event {
+    fn default() -> Self {
+        Self::Claim
+    }
 }
 
 #[derive(Serialize, Deserialize, schemars::JsonSchema, Debug)]
@@ -138,26 +340,70 @@ pub struct Pagination {
     pub limit: u8,
 }
 
+/// Inclusive bounds on the timestamp of history entries.
+#[derive(Serialize, Deserialize, schemars::JsonSchema, Default, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct TimeRange {
+    pub from: Option<Seconds>,
+    pub to: Option<Seconds>,
+}
+
+impl TimeRange {
+    #[inline]
+    pub fn contains(&self, timestamp: Seconds) -> bool {
+        self.from.map_or(true, |from| timestamp >= from)
+            && self.to.map_or(true, |to| timestamp <= to)
+    }
+}
+
 impl Claim<CanonicalAddr> {
     pub fn new(participant: Participant, info: &BlockInfo, amount: Uint128) -> Self {
         Self {
             claimant: participant.address,
             timestamp: info.time,
             amount,
+            event: Event::Claim,
+        }
+    }
+
+    pub fn revoked(address: CanonicalAddr, info: &BlockInfo, unvested: Uint128) -> Self {
+        Self {
+            claimant: address,
+            timestamp: info.time,
+            amount: unvested,
+            event: Event::Revoke,
         }
     }
 }
 
 impl History {
     const KEY: &'static [u8] = b"history";
+    const NS_BY_CLAIMANT: &'static [u8] = b"history_by_claimant";
+    const NS_BY_POOL: &'static [u8] = b"history_by_pool";
     const CURRENT_TOTAL: &'static [u8] = b"current_total";
     const LIMIT: u8 = 30;
 
-    pub fn push(storage: &mut impl Storage, claim: Claim<CanonicalAddr>) -> StdResult<()> {
+    /// Also indexes the entry by claimant and under each of the `pools`
+    /// that the claimant has accounts in.
+    pub fn push(
+        storage: &mut impl Storage,
+        claim: Claim<CanonicalAddr>,
+        pools: &[String],
+    ) -> StdResult<()> {
         let mut history = IterableStorage::new(Self::KEY);
-        history.push(storage, &claim)?;
+        let index = history.push(storage, &claim)?;
+
+        let key = [Self::NS_BY_CLAIMANT, claim.claimant.as_slice()].concat();
+        IterableStorage::<u64>::new(&key).push(storage, &index)?;
 
-        Self::increment_total(storage, claim.amount)?;
+        for pool in pools {
+            let key = [Self::NS_BY_POOL, pool.as_bytes()].concat();
+            IterableStorage::<u64>::new(&key).push(storage, &index)?;
+        }
+
+        if claim.event == Event::Claim {
+            Self::increment_total(storage, claim.amount)?;
+        }
 
         Ok(())
     }
@@ -197,6 +443,63 @@ impl History {
             total: history.len(&deps.storage)?,
         })
     }
+
+    pub fn list_by_claimant<S: Storage, A: Api, Q: Querier>(
+        deps: &Extern<S, A, Q>,
+        claimant: &HumanAddr,
+        range: TimeRange,
+        pagination: Pagination,
+    ) -> StdResult<HistoryResponse> {
+        let claimant = claimant.canonize(&deps.api)?;
+        let key = [Self::NS_BY_CLAIMANT, claimant.as_slice()].concat();
+
+        Self::list_indexed(deps, &key, range, pagination)
+    }
+
+    pub fn list_by_pool<S: Storage, A: Api, Q: Querier>(
+        deps: &Extern<S, A, Q>,
+        pool: &str,
+        range: TimeRange,
+        pagination: Pagination,
+    ) -> StdResult<HistoryResponse> {
+        let key = [Self::NS_BY_POOL, pool.as_bytes()].concat();
+
+        Self::list_indexed(deps, &key, range, pagination)
+    }
+
+    /// `total` is the number of entries in the index that fall within `range`.
+    fn list_indexed<S: Storage, A: Api, Q: Querier>(
+        deps: &Extern<S, A, Q>,
+        key: &[u8],
+        range: TimeRange,
+        pagination: Pagination,
+    ) -> StdResult<HistoryResponse> {
+        let history = IterableStorage::<Claim<CanonicalAddr>>::new(Self::KEY);
+        let index = IterableStorage::<u64>::new(key);
+
+        let limit = pagination.limit.min(Self::LIMIT) as usize;
+
+        let mut entries = vec![];
+        let mut total = 0;
+
+        for id in index.iter(&deps.storage)? {
+            let claim = history
+                .get_at(&deps.storage, id?)?
+                .ok_or_else(|| StdError::generic_err(MGMTError!(CORRUPTED)))?;
+
+            if !range.contains(claim.timestamp) {
+                continue;
+            }
+
+            if total >= pagination.start && entries.len() < limit {
+                entries.push(claim.humanize(&deps.api)?);
+            }
+
+            total += 1;
+        }
+
+        Ok(HistoryResponse { entries, total })
+    }
 }
 
 impl Canonize for Claim<HumanAddr> {
@@ -207,6 +510,7 @@ impl Canonize for Claim<HumanAddr> {
             claimant: self.claimant.canonize(api)?,
             amount: self.amount,
             timestamp: self.timestamp,
+            event: self.event,
         })
     }
 }
@@ -219,6 +523,84 @@ impl Humanize for Claim<CanonicalAddr> {
             claimant: self.claimant.humanize(api)?,
             amount: self.amount,
             timestamp: self.timestamp,
+            event: self.event,
+        })
+    }
+}
+
+impl Canonize for ScheduleChange<HumanAddr> {
+    type Output = ScheduleChange<CanonicalAddr>;
+
+    fn canonize(self, api: &impl Api) -> StdResult<Self::Output> {
+        Ok(match self {
+            Self::AddAccount { pool_name, account } => ScheduleChange::AddAccount {
+                pool_name,
+                account: account.canonize(api)?,
+            },
+            Self::IncreaseAllocation {
+                total_increment,
+                pool,
+            } => ScheduleChange::IncreaseAllocation {
+                total_increment,
+                pool: pool.canonize(api)?,
+            },
+            Self::RevokeAccount {
+                pool,
+                name,
+                treasury,
+            } => ScheduleChange::RevokeAccount {
+                pool,
+                name,
+                treasury: match treasury {
+                    Some(treasury) => Some(treasury.canonize(api)?),
+                    None => None,
+                },
+            },
+        })
+    }
+}
+
+impl Humanize for ScheduleChange<CanonicalAddr> {
+    type Output = ScheduleChange<HumanAddr>;
+
+    fn humanize(self, api: &impl Api) -> StdResult<Self::Output> {
+        Ok(match self {
+            Self::AddAccount { pool_name, account } => ScheduleChange::AddAccount {
+                pool_name,
+                account: account.humanize(api)?,
+            },
+            Self::IncreaseAllocation {
+                total_increment,
+                pool,
+            } => ScheduleChange::IncreaseAllocation {
+                total_increment,
+                pool: pool.humanize(api)?,
+            },
+            Self::RevokeAccount {
+                pool,
+                name,
+                treasury,
+            } => ScheduleChange::RevokeAccount {
+                pool,
+                name,
+                treasury: match treasury {
+                    Some(treasury) => Some(treasury.humanize(api)?),
+                    None => None,
+                },
+            },
+        })
+    }
+}
+
+impl Humanize for Proposal<CanonicalAddr> {
+    type Output = Proposal<HumanAddr>;
+
+    fn humanize(self, api: &impl Api) -> StdResult<Self::Output> {
+        Ok(Proposal {
+            id: self.id,
+            change: self.change.humanize(api)?,
+            proposed_at: self.proposed_at,
+            executable_at: self.executable_at,
         })
     }
 }
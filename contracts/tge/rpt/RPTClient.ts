@@ -40,6 +40,16 @@ export abstract class RPTClient extends Client {
       return this.execute({ vest: {} });
     }
 
+    /** receive the amount credited to the sender by previous vests */
+    claim() {
+      return this.execute({ claim: {} });
+    }
+
+    /** amount credited to an address that it can claim */
+    pending(address: string): Promise<string> {
+      return this.query({ pending: { address } });
+    }
+
   }
 }
 
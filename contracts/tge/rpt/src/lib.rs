@@ -3,13 +3,13 @@ use std::ops::Sub;
 use fadroma::{
     admin,
     admin::assert_admin,
-    cosmwasm_std::{self, HandleResponse, InitResponse, StdResult, Uint128},
+    cosmwasm_std::{self, log, HandleResponse, InitResponse, StdResult, Uint128},
     derive_contract::{contract, init},
     require_admin,
     schemars::{self, JsonSchema},
     secret_toolkit::snip20,
-    space_pad, to_binary, to_cosmos_msg, Api, ContractLink, CosmosMsg, Env, Extern, HumanAddr,
-    LogAttribute, Querier, StdError, Storage, WasmQuery, BLOCK_SIZE,
+    space_pad, to_binary, to_cosmos_msg, Api, ContractLink, Env, Extern, HumanAddr, Querier,
+    StdError, Storage, WasmQuery, BLOCK_SIZE,
 };
 pub mod state;
 use fadroma::{derive_contract::*, killswitch};
@@ -38,6 +38,9 @@ macro_rules! RPTError {
     (MGMT) => {
         "Main vesting contract returned unexpected response."
     };
+    (NOTHING) => {
+        "Nothing to claim right now."
+    };
 }
 
 #[contract(entry, component(path = "admin"), component(path = "killswitch"))]
@@ -95,33 +98,75 @@ pub trait RPT {
         Ok(HandleResponse::default())
     }
 
+    /// Claims from MGMT and credits each recipient with its share.
+    /// Whatever doesn't add up to a whole portion is carried forward to the next vest.
     #[handle]
     fn vest() -> StdResult<HandleResponse> {
         let mgmt = State::load_mgmt(deps)?;
-        let token = State::load_token(deps)?;
         let portion = State::load_portion(deps)?.u128();
         let distribution = State::load_distribution(deps)?;
         let claimable = query_claimable(&deps, &env, &mgmt)?.u128();
-        let portions = claimable / portion;
-        let remainder = claimable % portion;
-
-        let messages = build_messages(distribution, portions, token, mgmt)?;
-
-        let mut logs = vec![];
-        if remainder > 0 {
-            logs.push(LogAttribute {
-                key: "remainder (locked forever)".to_string(),
-                value: remainder.to_string(),
-                encrypted: false,
-            })
+        let available = claimable + State::load_remainder(&deps.storage)?.u128();
+        let portions = available / portion;
+        let remainder = available % portion;
+
+        for (addr, amount) in distribution.0.iter() {
+            let balance = State::load_balance(deps, addr)?;
+            State::save_balance(deps, addr, balance + Uint128(amount.u128() * portions))?;
+        }
+
+        State::save_remainder(&mut deps.storage, Uint128(remainder))?;
+
+        let claim_msg = to_cosmos_msg(
+            mgmt.address.clone(),
+            mgmt.code_hash.clone(),
+            &MGMTHandle::Claim {},
+        )?;
+
+        Ok(HandleResponse {
+            data: None,
+            log: vec![
+                log("action", "vest"),
+                log("portions", portions),
+                log("remainder", remainder),
+            ],
+            messages: vec![claim_msg],
+        })
+    }
+
+    /// Recipients call this to receive what has been credited to them by `vest`.
+    #[handle]
+    fn claim() -> StdResult<HandleResponse> {
+        let balance = State::load_balance(deps, &env.message.sender)?;
+
+        if balance.is_zero() {
+            return Err(StdError::generic_err(RPTError!(NOTHING)));
         }
 
+        State::save_balance(deps, &env.message.sender, Uint128::zero())?;
+
+        let token = State::load_token(deps)?;
+
         Ok(HandleResponse {
             data: None,
-            log: logs,
-            messages,
+            log: vec![log("action", "claim"), log("claimed", balance)],
+            messages: vec![snip20::transfer_msg(
+                env.message.sender,
+                balance,
+                None,
+                None,
+                BLOCK_SIZE,
+                token.code_hash,
+                token.address,
+            )?],
         })
     }
+
+    /// Amount credited to `address` that it can claim.
+    #[query]
+    fn pending(address: HumanAddr) -> StdResult<Uint128> {
+        State::load_balance(deps, &address)
+    }
 }
 
 fn query_claimable<S, A, Q>(
@@ -151,79 +196,6 @@ where
     Ok(progress.unlocked.sub(progress.claimed)?)
 }
 
-#[cfg(feature = "batch_transfer")]
-fn build_messages(
-    distribution: Distribution<HumanAddr>,
-    portions: u128,
-    token: ContractLink<HumanAddr>,
-    mgmt: ContractLink<HumanAddr>,
-) -> StdResult<Vec<CosmosMsg>> {
-    let transfers = distribution
-        .0
-        .iter()
-        .map(|(addr, amount)| {
-            snip20::batch::TransferAction::new(
-                addr.clone(),
-                Uint128(amount.u128() * portions),
-                None,
-            )
-        })
-        .collect::<Vec<_>>();
-
-    let batch_transfer_msg = snip20::batch_transfer_msg(
-        transfers,
-        None,
-        BLOCK_SIZE,
-        token.code_hash.clone(),
-        token.address.clone(),
-    )?;
-
-    let claim_msg = to_cosmos_msg(
-        mgmt.address.clone(),
-        mgmt.code_hash.clone(),
-        &MGMTHandle::Claim {},
-    )?;
-
-    Ok(vec![claim_msg, batch_transfer_msg])
-}
-#[cfg(not(feature = "batch_transfer"))]
-fn build_messages(
-    distribution: Distribution<HumanAddr>,
-    portions: u128,
-    token: ContractLink<HumanAddr>,
-    mgmt: ContractLink<HumanAddr>,
-) -> StdResult<Vec<CosmosMsg>> {
-    let mut transfers = distribution
-        .0
-        .iter()
-        .map(|(addr, amount)| {
-            let transfer = snip20::transfer_msg(
-                addr.clone(),
-                Uint128(amount.u128() * portions),
-                None,
-                None,
-                BLOCK_SIZE,
-                token.code_hash.clone(),
-                token.address.clone(),
-            )
-            .unwrap();
-
-            transfer
-        })
-        .collect::<Vec<_>>();
-
-    let claim_msg = to_cosmos_msg(
-        mgmt.address.clone(),
-        mgmt.code_hash.clone(),
-        &MGMTHandle::Claim {},
-    )?;
-
-    let mut messages = vec![claim_msg];
-    messages.append(&mut transfers);
-
-    Ok(messages)
-}
-
 fn validate<T>(portion: Uint128, config: &Distribution<T>) -> StdResult<()> {
     let total = sum_config(&config);
     if portion == total {
@@ -1,6 +1,8 @@
 use fadroma::{
-    load, save, Api, CanonicalAddr, Canonize, ContractLink, Extern, HumanAddr,
-    Humanize, Querier, StdResult, Storage, Uint128,
+    load, save,
+    storage::{ns_load, ns_save},
+    Api, CanonicalAddr, Canonize, ContractLink, Extern, HumanAddr, Humanize, Querier, StdResult,
+    Storage, Uint128,
 };
 
 use crate::Distribution;
@@ -14,6 +16,8 @@ impl State {
     const KEY_DISTRIBUTION: &'static [u8] = b"config";
     const KEY_TOKEN: &'static [u8] = b"token";
     const KEY_MGMT: &'static [u8] = b"mgmt";
+    const KEY_REMAINDER: &'static [u8] = b"remainder";
+    const NS_BALANCES: &'static [u8] = b"balances";
 
     pub fn save_portion<S, A, Q>(deps: &mut Extern<S, A, Q>, portion: Portion) -> StdResult<()>
     where
@@ -106,4 +110,37 @@ impl State {
 
         Ok(distribution.humanize(&deps.api)?)
     }
+    /// Part of the vested funds that didn't add up to a whole portion,
+    /// carried forward to the next vest.
+    pub fn save_remainder(storage: &mut impl Storage, remainder: Uint128) -> StdResult<()> {
+        save(storage, Self::KEY_REMAINDER, &remainder)
+    }
+    pub fn load_remainder(storage: &impl Storage) -> StdResult<Uint128> {
+        Ok(load(storage, Self::KEY_REMAINDER)?.unwrap_or_default())
+    }
+    /// Amount credited to a recipient that it hasn't claimed yet.
+    pub fn save_balance<S, A, Q>(
+        deps: &mut Extern<S, A, Q>,
+        address: &HumanAddr,
+        balance: Uint128,
+    ) -> StdResult<()>
+    where
+        S: Storage,
+        A: Api,
+        Q: Querier,
+    {
+        let address = address.canonize(&deps.api)?;
+
+        ns_save(&mut deps.storage, Self::NS_BALANCES, address.as_slice(), &balance)
+    }
+    pub fn load_balance<S, A, Q>(deps: &Extern<S, A, Q>, address: &HumanAddr) -> StdResult<Uint128>
+    where
+        S: Storage,
+        A: Api,
+        Q: Querier,
+    {
+        let address = address.canonize(&deps.api)?;
+
+        Ok(ns_load(&deps.storage, Self::NS_BALANCES, address.as_slice())?.unwrap_or_default())
+    }
 }
@@ -6,15 +6,17 @@ use cosmwasm_std::{
 };
 
 use crate::msg::{
-    space_pad, ContractStatusLevel, HandleAnswer, HandleMsg, InitMsg, QueryAnswer, QueryMsg,
-    ResponseStatus::Success,
+    space_pad, BurnFromAction, ContractStatusLevel, HandleAnswer, HandleMsg, InitMsg, MintAction,
+    QueryAnswer, QueryMsg, QueryPermission, QueryWithPermit, ResponseStatus::Success, SendAction,
+    SendFromAction, TransferAction, TransferFromAction,
 };
+use crate::permit::Permit;
 use crate::rand::sha_256;
 use crate::receiver::Snip20ReceiveMsg;
 use crate::state::{
-    get_receiver_hash, get_transfers, read_allowance, read_viewing_key, set_receiver_hash,
-    store_transfer, write_allowance, write_viewing_key, Balances, Config, Constants,
-    ReadonlyBalances, ReadonlyConfig,
+    get_receiver_hash, get_transfers, read_allowance, read_viewing_key, revoke_permit,
+    set_receiver_hash, store_transfer, write_allowance, write_viewing_key, Balances, Config,
+    Constants, ReadonlyBalances, ReadonlyConfig,
 };
 use crate::viewing_key::{ViewingKey, VIEWING_KEY_SIZE};
 
@@ -76,6 +78,7 @@ pub fn init<S: Storage, A: Api, Q: Querier>(
     config.set_total_supply(total_supply);
     config.set_contract_status(ContractStatusLevel::NormalRun);
     config.set_minters(Vec::from([admin]))?;
+    config.set_contract_address(&env.contract.address)?;
 
     Ok(InitResponse::default())
 }
@@ -120,8 +123,10 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
         HandleMsg::Mint {
             recipient, amount, ..
         } => try_mint(deps, env, recipient, amount),
+        HandleMsg::BatchMint { actions, .. } => try_batch_mint(deps, env, actions),
         HandleMsg::Burn { amount, .. } => try_burn(deps, env, amount),
         HandleMsg::BurnFrom { owner, amount, .. } => try_burn_from(deps, env, &owner, amount),
+        HandleMsg::BatchBurnFrom { actions, .. } => try_batch_burn_from(deps, env, actions),
 
         HandleMsg::Deposit { .. } => Err(StdError::generic_err("not allowed.")),
         HandleMsg::Redeem { .. } => Err(StdError::generic_err("not allowed.")),
@@ -148,10 +153,17 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
             msg,
             ..
         } => try_send_from(deps, env, &owner, &recipient, amount, msg),
+        HandleMsg::BatchTransfer { actions, .. } => try_batch_transfer(deps, env, actions),
+        HandleMsg::BatchSend { actions, .. } => try_batch_send(deps, env, actions),
+        HandleMsg::BatchTransferFrom { actions, .. } => {
+            try_batch_transfer_from(deps, env, actions)
+        }
+        HandleMsg::BatchSendFrom { actions, .. } => try_batch_send_from(deps, env, actions),
 
         HandleMsg::CreateViewingKey { entropy, .. } => try_create_key(deps, env, entropy),
         HandleMsg::SetViewingKey { key, .. } => try_set_key(deps, env, key),
         HandleMsg::RegisterReceive { code_hash, .. } => try_register_receive(deps, env, code_hash),
+        HandleMsg::RevokePermit { permit_name, .. } => try_revoke_permit(deps, env, permit_name),
 
         HandleMsg::IncreaseAllowance {
             spender,
@@ -175,10 +187,55 @@ pub fn query<S: Storage, A: Api, Q: Querier>(deps: &Extern<S, A, Q>, msg: QueryM
         QueryMsg::TokenInfo {} => query_token_info(&deps.storage),
         QueryMsg::ExchangeRate {} => query_exchange_rate(),
         QueryMsg::Minters { .. } => query_minters(deps),
+        QueryMsg::WithPermit { permit, query } => permit_queries(deps, permit, query),
         _ => authenticated_queries(deps, msg),
     }
 }
 
+fn permit_queries<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    permit: Permit,
+    query: QueryWithPermit,
+) -> QueryResult {
+    let token_address = ReadonlyConfig::from_storage(&deps.storage).contract_address()?;
+    let account = permit.validate(&deps.storage, &deps.api, &token_address)?;
+
+    let check_permission = |permission: QueryPermission| {
+        if permit.check_permission(&permission) || permit.check_permission(&QueryPermission::Owner)
+        {
+            Ok(())
+        } else {
+            Err(StdError::generic_err(format!(
+                "No permission to query {:?}, got permissions {:?}",
+                permission, permit.params.permissions
+            )))
+        }
+    };
+
+    match query {
+        QueryWithPermit::Balance {} => {
+            check_permission(QueryPermission::Balance)?;
+            query_balance(deps, &account)
+        }
+        QueryWithPermit::TransferHistory { page, page_size } => {
+            check_permission(QueryPermission::History)?;
+            query_transactions(deps, &account, page.unwrap_or(0), page_size)
+        }
+        QueryWithPermit::Allowance { owner, spender } => {
+            check_permission(QueryPermission::Allowance)?;
+            if account != owner && account != spender {
+                return Err(StdError::generic_err(format!(
+                    "Cannot query allowance. Requires permit for either owner {:?} or spender {:?}, got permit for {:?}",
+                    owner.as_str(),
+                    spender.as_str(),
+                    account.as_str()
+                )));
+            }
+            try_check_allowance(deps, owner, spender)
+        }
+    }
+}
+
 pub fn authenticated_queries<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     msg: QueryMsg,
@@ -295,21 +352,63 @@ fn change_admin<S: Storage, A: Api, Q: Querier>(
     })
 }
 
+fn check_if_minter<S: Storage>(config: &mut Config<S>, account: &HumanAddr) -> StdResult<()> {
+    let minters = config.minters();
+    if !minters.contains(account) {
+        return Err(StdError::generic_err(
+            "Minting is allowed to minter accounts only",
+        ));
+    }
+
+    Ok(())
+}
+
 fn try_mint<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
     address: HumanAddr,
     amount: Uint128,
 ) -> StdResult<HandleResponse> {
-    let mut config = Config::from_storage(&mut deps.storage);
+    check_if_minter(&mut Config::from_storage(&mut deps.storage), &env.message.sender)?;
 
-    let minters = config.minters();
-    if !minters.contains(&env.message.sender) {
-        return Err(StdError::generic_err(
-            "Minting is allowed to minter accounts only",
-        ));
+    try_mint_impl(deps, &address, amount)?;
+
+    let res = HandleResponse {
+        messages: vec![],
+        log: vec![],
+        data: Some(to_binary(&HandleAnswer::Mint { status: Success })?),
+    };
+
+    Ok(res)
+}
+
+fn try_batch_mint<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    actions: Vec<MintAction>,
+) -> StdResult<HandleResponse> {
+    check_if_minter(&mut Config::from_storage(&mut deps.storage), &env.message.sender)?;
+
+    for action in actions {
+        try_mint_impl(deps, &action.recipient, action.amount)?;
     }
 
+    let res = HandleResponse {
+        messages: vec![],
+        log: vec![],
+        data: Some(to_binary(&HandleAnswer::BatchMint { status: Success })?),
+    };
+
+    Ok(res)
+}
+
+fn try_mint_impl<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    address: &HumanAddr,
+    amount: Uint128,
+) -> StdResult<()> {
+    let mut config = Config::from_storage(&mut deps.storage);
+
     let amount = amount.u128();
 
     let mut total_supply = config.total_supply();
@@ -322,7 +421,7 @@ fn try_mint<S: Storage, A: Api, Q: Querier>(
     }
     config.set_total_supply(total_supply);
 
-    let receipient_account = &deps.api.canonical_address(&address)?;
+    let receipient_account = &deps.api.canonical_address(address)?;
 
     let mut balances = Balances::from_storage(&mut deps.storage);
 
@@ -342,13 +441,7 @@ fn try_mint<S: Storage, A: Api, Q: Querier>(
 
     balances.set_account_balance(receipient_account, account_balance);
 
-    let res = HandleResponse {
-        messages: vec![],
-        log: vec![],
-        data: Some(to_binary(&HandleAnswer::Mint { status: Success })?),
-    };
-
-    Ok(res)
+    Ok(())
 }
 
 pub fn try_set_key<S: Storage, A: Api, Q: Querier>(
@@ -408,6 +501,20 @@ fn set_contract_status<S: Storage, A: Api, Q: Querier>(
     })
 }
 
+fn try_revoke_permit<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    permit_name: String,
+) -> StdResult<HandleResponse> {
+    revoke_permit(&mut deps.storage, &env.message.sender, &permit_name);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![],
+        data: Some(to_binary(&HandleAnswer::RevokePermit { status: Success })?),
+    })
+}
+
 pub fn try_check_allowance<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
     owner: HumanAddr,
@@ -429,7 +536,7 @@ pub fn try_check_allowance<S: Storage, A: Api, Q: Querier>(
 
 fn try_transfer_impl<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
-    env: Env,
+    env: &Env,
     recipient: &HumanAddr,
     amount: Uint128,
 ) -> StdResult<()> {
@@ -463,7 +570,7 @@ fn try_transfer<S: Storage, A: Api, Q: Querier>(
     recipient: &HumanAddr,
     amount: Uint128,
 ) -> StdResult<HandleResponse> {
-    try_transfer_impl(deps, env, recipient, amount)?;
+    try_transfer_impl(deps, &env, recipient, amount)?;
 
     let res = HandleResponse {
         messages: vec![],
@@ -473,6 +580,23 @@ fn try_transfer<S: Storage, A: Api, Q: Querier>(
     Ok(res)
 }
 
+fn try_batch_transfer<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    actions: Vec<TransferAction>,
+) -> StdResult<HandleResponse> {
+    for action in actions {
+        try_transfer_impl(deps, &env, &action.recipient, action.amount)?;
+    }
+
+    let res = HandleResponse {
+        messages: vec![],
+        log: vec![],
+        data: Some(to_binary(&HandleAnswer::BatchTransfer { status: Success })?),
+    };
+    Ok(res)
+}
+
 fn try_add_receiver_api_callback<S: ReadonlyStorage>(
     messages: &mut Vec<CosmosMsg>,
     storage: &S,
@@ -501,7 +625,7 @@ fn try_send<S: Storage, A: Api, Q: Querier>(
     msg: Option<Binary>,
 ) -> StdResult<HandleResponse> {
     let sender = env.message.sender.clone();
-    try_transfer_impl(deps, env, recipient, amount)?;
+    try_transfer_impl(deps, &env, recipient, amount)?;
 
     let mut messages = vec![];
 
@@ -523,6 +647,36 @@ fn try_send<S: Storage, A: Api, Q: Querier>(
     Ok(res)
 }
 
+fn try_batch_send<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    actions: Vec<SendAction>,
+) -> StdResult<HandleResponse> {
+    let sender = env.message.sender.clone();
+    let mut messages = vec![];
+
+    for action in actions {
+        try_transfer_impl(deps, &env, &action.recipient, action.amount)?;
+
+        try_add_receiver_api_callback(
+            &mut messages,
+            &deps.storage,
+            &action.recipient,
+            action.msg,
+            sender.clone(),
+            sender.clone(),
+            action.amount,
+        )?;
+    }
+
+    let res = HandleResponse {
+        messages,
+        log: vec![],
+        data: Some(to_binary(&HandleAnswer::BatchSend { status: Success })?),
+    };
+    Ok(res)
+}
+
 fn try_register_receive<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
@@ -548,7 +702,7 @@ fn insufficient_allowance(allowance: u128, required: u128) -> StdError {
 
 fn try_transfer_from_impl<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
-    env: Env,
+    env: &Env,
     owner: &HumanAddr,
     recipient: &HumanAddr,
     amount: Uint128,
@@ -611,7 +765,7 @@ fn try_transfer_from<S: Storage, A: Api, Q: Querier>(
     recipient: &HumanAddr,
     amount: Uint128,
 ) -> StdResult<HandleResponse> {
-    try_transfer_from_impl(deps, env, owner, recipient, amount)?;
+    try_transfer_from_impl(deps, &env, owner, recipient, amount)?;
 
     let res = HandleResponse {
         messages: vec![],
@@ -621,6 +775,25 @@ fn try_transfer_from<S: Storage, A: Api, Q: Querier>(
     Ok(res)
 }
 
+fn try_batch_transfer_from<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    actions: Vec<TransferFromAction>,
+) -> StdResult<HandleResponse> {
+    for action in actions {
+        try_transfer_from_impl(deps, &env, &action.owner, &action.recipient, action.amount)?;
+    }
+
+    let res = HandleResponse {
+        messages: vec![],
+        log: vec![],
+        data: Some(to_binary(&HandleAnswer::BatchTransferFrom {
+            status: Success,
+        })?),
+    };
+    Ok(res)
+}
+
 fn try_send_from<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
@@ -630,7 +803,7 @@ fn try_send_from<S: Storage, A: Api, Q: Querier>(
     msg: Option<Binary>,
 ) -> StdResult<HandleResponse> {
     let sender = env.message.sender.clone();
-    try_transfer_from_impl(deps, env, owner, recipient, amount)?;
+    try_transfer_from_impl(deps, &env, owner, recipient, amount)?;
 
     let mut messages = vec![];
 
@@ -652,12 +825,77 @@ fn try_send_from<S: Storage, A: Api, Q: Querier>(
     Ok(res)
 }
 
+fn try_batch_send_from<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    actions: Vec<SendFromAction>,
+) -> StdResult<HandleResponse> {
+    let sender = env.message.sender.clone();
+    let mut messages = vec![];
+
+    for action in actions {
+        try_transfer_from_impl(deps, &env, &action.owner, &action.recipient, action.amount)?;
+
+        try_add_receiver_api_callback(
+            &mut messages,
+            &deps.storage,
+            &action.recipient,
+            action.msg,
+            sender.clone(),
+            action.owner,
+            action.amount,
+        )?;
+    }
+
+    let res = HandleResponse {
+        messages,
+        log: vec![],
+        data: Some(to_binary(&HandleAnswer::BatchSendFrom { status: Success })?),
+    };
+    Ok(res)
+}
+
 fn try_burn_from<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
     owner: &HumanAddr,
     amount: Uint128,
 ) -> StdResult<HandleResponse> {
+    try_burn_from_impl(deps, &env, owner, amount)?;
+
+    let res = HandleResponse {
+        messages: vec![],
+        log: vec![],
+        data: Some(to_binary(&HandleAnswer::BurnFrom { status: Success })?),
+    };
+
+    Ok(res)
+}
+
+fn try_batch_burn_from<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    actions: Vec<BurnFromAction>,
+) -> StdResult<HandleResponse> {
+    for action in actions {
+        try_burn_from_impl(deps, &env, &action.owner, action.amount)?;
+    }
+
+    let res = HandleResponse {
+        messages: vec![],
+        log: vec![],
+        data: Some(to_binary(&HandleAnswer::BatchBurnFrom { status: Success })?),
+    };
+
+    Ok(res)
+}
+
+fn try_burn_from_impl<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    owner: &HumanAddr,
+    amount: Uint128,
+) -> StdResult<()> {
     let spender_address = deps.api.canonical_address(&env.message.sender)?;
     let owner_address = deps.api.canonical_address(owner)?;
     let amount = amount.u128();
@@ -714,13 +952,7 @@ fn try_burn_from<S: Storage, A: Api, Q: Querier>(
     }
     config.set_total_supply(total_supply);
 
-    let res = HandleResponse {
-        messages: vec![],
-        log: vec![],
-        data: Some(to_binary(&HandleAnswer::BurnFrom { status: Success })?),
-    };
-
-    Ok(res)
+    Ok(())
 }
 
 fn try_increase_allowance<S: Storage, A: Api, Q: Querier>(
@@ -1023,10 +1255,17 @@ mod tests {
             | HandleAnswer::Burn { status }
             | HandleAnswer::RegisterReceive { status }
             | HandleAnswer::SetViewingKey { status }
+            | HandleAnswer::RevokePermit { status }
             | HandleAnswer::TransferFrom { status }
             | HandleAnswer::SendFrom { status }
             | HandleAnswer::BurnFrom { status }
+            | HandleAnswer::BatchTransfer { status }
+            | HandleAnswer::BatchSend { status }
+            | HandleAnswer::BatchTransferFrom { status }
+            | HandleAnswer::BatchSendFrom { status }
+            | HandleAnswer::BatchBurnFrom { status }
             | HandleAnswer::Mint { status }
+            | HandleAnswer::BatchMint { status }
             | HandleAnswer::ChangeAdmin { status }
             | HandleAnswer::SetContractStatus { status }
             | HandleAnswer::SetMinters { status }
@@ -1180,6 +1419,121 @@ mod tests {
         })));
     }
 
+    #[test]
+    fn test_handle_batch_transfer() {
+        let (init_result, mut deps) = init_helper(vec![InitialBalance {
+            address: HumanAddr("bob".to_string()),
+            amount: Uint128(5000),
+        }]);
+        assert!(
+            init_result.is_ok(),
+            "Init failed: {}",
+            init_result.err().unwrap()
+        );
+
+        let handle_msg = HandleMsg::BatchTransfer {
+            actions: vec![
+                TransferAction {
+                    recipient: HumanAddr("alice".to_string()),
+                    amount: Uint128(1000),
+                },
+                TransferAction {
+                    recipient: HumanAddr("carol".to_string()),
+                    amount: Uint128(1500),
+                },
+            ],
+            padding: None,
+        };
+        let handle_result = handle(&mut deps, mock_env("bob", &[]), handle_msg);
+        let result = handle_result.unwrap();
+        assert!(ensure_success(result));
+        let balances = ReadonlyBalances::from_storage(&deps.storage);
+        for (name, amount) in &[("bob", 2500), ("alice", 1000), ("carol", 1500)] {
+            let canonical = deps
+                .api
+                .canonical_address(&HumanAddr(name.to_string()))
+                .unwrap();
+            assert_eq!(*amount, balances.account_amount(&canonical));
+        }
+
+        let handle_msg = HandleMsg::BatchTransfer {
+            actions: vec![
+                TransferAction {
+                    recipient: HumanAddr("alice".to_string()),
+                    amount: Uint128(2000),
+                },
+                TransferAction {
+                    recipient: HumanAddr("carol".to_string()),
+                    amount: Uint128(2000),
+                },
+            ],
+            padding: None,
+        };
+        let handle_result = handle(&mut deps, mock_env("bob", &[]), handle_msg);
+        let error = extract_error_msg(handle_result);
+        assert!(error.contains("insufficient funds"));
+    }
+
+    #[test]
+    fn test_handle_batch_send() {
+        let (init_result, mut deps) = init_helper(vec![InitialBalance {
+            address: HumanAddr("bob".to_string()),
+            amount: Uint128(5000),
+        }]);
+        assert!(
+            init_result.is_ok(),
+            "Init failed: {}",
+            init_result.err().unwrap()
+        );
+
+        let handle_msg = HandleMsg::RegisterReceive {
+            code_hash: "this_is_a_hash_of_a_code".to_string(),
+            padding: None,
+        };
+        let handle_result = handle(&mut deps, mock_env("contract", &[]), handle_msg);
+        assert!(ensure_success(handle_result.unwrap()));
+
+        let handle_msg = HandleMsg::BatchSend {
+            actions: vec![
+                SendAction {
+                    recipient: HumanAddr("contract".to_string()),
+                    amount: Uint128(100),
+                    msg: Some(to_binary("hey hey you you").unwrap()),
+                },
+                SendAction {
+                    recipient: HumanAddr("alice".to_string()),
+                    amount: Uint128(200),
+                    msg: None,
+                },
+            ],
+            padding: None,
+        };
+        let handle_result = handle(&mut deps, mock_env("bob", &[]), handle_msg);
+        let result = handle_result.unwrap();
+        assert!(ensure_success(result.clone()));
+        // Only registered receivers get a callback
+        assert_eq!(result.messages.len(), 1);
+        assert!(result.messages.contains(&CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr("contract".to_string()),
+            callback_code_hash: "this_is_a_hash_of_a_code".to_string(),
+            msg: Snip20ReceiveMsg::new(
+                HumanAddr("bob".to_string()),
+                HumanAddr("bob".to_string()),
+                Uint128(100),
+                Some(to_binary("hey hey you you").unwrap())
+            )
+            .into_binary()
+            .unwrap(),
+            send: vec![]
+        })));
+        let bob_canonical = deps
+            .api
+            .canonical_address(&HumanAddr("bob".to_string()))
+            .unwrap();
+        let balances = ReadonlyBalances::from_storage(&deps.storage);
+        assert_eq!(5000 - 300, balances.account_amount(&bob_canonical));
+    }
+
     #[test]
     fn test_handle_register_receive() {
         let (init_result, mut deps) = init_helper(vec![InitialBalance {
@@ -1600,6 +1954,97 @@ mod tests {
         assert!(error.contains("insufficient allowance"));
     }
 
+    #[test]
+    fn test_handle_batch_transfer_from_and_burn_from() {
+        let (init_result, mut deps) = init_helper(vec![
+            InitialBalance {
+                address: HumanAddr("bob".to_string()),
+                amount: Uint128(5000),
+            },
+            InitialBalance {
+                address: HumanAddr("carol".to_string()),
+                amount: Uint128(5000),
+            },
+        ]);
+        assert!(
+            init_result.is_ok(),
+            "Init failed: {}",
+            init_result.err().unwrap()
+        );
+
+        for owner in &["bob", "carol"] {
+            let handle_msg = HandleMsg::IncreaseAllowance {
+                spender: HumanAddr("alice".to_string()),
+                amount: Uint128(2000),
+                padding: None,
+                expiration: None,
+            };
+            let handle_result = handle(&mut deps, mock_env(*owner, &[]), handle_msg);
+            assert!(
+                handle_result.is_ok(),
+                "handle() failed: {}",
+                handle_result.err().unwrap()
+            );
+        }
+
+        let handle_msg = HandleMsg::BatchTransferFrom {
+            actions: vec![
+                TransferFromAction {
+                    owner: HumanAddr("bob".to_string()),
+                    recipient: HumanAddr("dave".to_string()),
+                    amount: Uint128(1000),
+                },
+                TransferFromAction {
+                    owner: HumanAddr("carol".to_string()),
+                    recipient: HumanAddr("dave".to_string()),
+                    amount: Uint128(500),
+                },
+            ],
+            padding: None,
+        };
+        let handle_result = handle(&mut deps, mock_env("alice", &[]), handle_msg);
+        assert!(ensure_success(handle_result.unwrap()));
+
+        let handle_msg = HandleMsg::BatchBurnFrom {
+            actions: vec![
+                BurnFromAction {
+                    owner: HumanAddr("bob".to_string()),
+                    amount: Uint128(1000),
+                },
+                BurnFromAction {
+                    owner: HumanAddr("carol".to_string()),
+                    amount: Uint128(1500),
+                },
+            ],
+            padding: None,
+        };
+        let handle_result = handle(&mut deps, mock_env("alice", &[]), handle_msg);
+        assert!(ensure_success(handle_result.unwrap()));
+
+        let balances = ReadonlyBalances::from_storage(&deps.storage);
+        for (name, amount) in &[("bob", 3000), ("carol", 3000), ("dave", 1500)] {
+            let canonical = deps
+                .api
+                .canonical_address(&HumanAddr(name.to_string()))
+                .unwrap();
+            assert_eq!(*amount, balances.account_amount(&canonical));
+        }
+        let total_supply = ReadonlyConfig::from_storage(&deps.storage).total_supply();
+        assert_eq!(total_supply, 10000 - 2500);
+
+        // Both allowances are used up
+        let handle_msg = HandleMsg::BatchBurnFrom {
+            actions: vec![BurnFromAction {
+                owner: HumanAddr("carol".to_string()),
+                amount: Uint128(1),
+            }],
+            padding: None,
+        };
+        let handle_result = handle(&mut deps, mock_env("alice", &[]), handle_msg);
+        let error = extract_error_msg(handle_result);
+        assert!(error.contains("insufficient allowance"));
+    }
+
     #[test]
     fn test_handle_decrease_allowance() {
         let (init_result, mut deps) = init_helper(vec![InitialBalance {
@@ -1866,6 +2311,54 @@ mod tests {
         assert_eq!(new_supply, supply + mint_amount);
     }
 
+    #[test]
+    fn test_handle_batch_mint() {
+        let (init_result, mut deps) = init_helper(vec![InitialBalance {
+            address: HumanAddr("lebron".to_string()),
+            amount: Uint128(5000),
+        }]);
+        assert!(
+            init_result.is_ok(),
+            "Init failed: {}",
+            init_result.err().unwrap()
+        );
+
+        let actions = vec![
+            MintAction {
+                recipient: HumanAddr("lebron".to_string()),
+                amount: Uint128(100),
+            },
+            MintAction {
+                recipient: HumanAddr("giannis".to_string()),
+                amount: Uint128(200),
+            },
+        ];
+
+        let handle_msg = HandleMsg::BatchMint {
+            actions: actions.clone(),
+            padding: None,
+        };
+        let handle_result = handle(&mut deps, mock_env("lebron", &[]), handle_msg);
+        let error = extract_error_msg(handle_result);
+        assert!(error.contains("allowed to minter accounts only"));
+
+        let handle_msg = HandleMsg::BatchMint {
+            actions,
+            padding: None,
+        };
+        let handle_result = handle(&mut deps, mock_env("admin", &[]), handle_msg);
+        assert!(ensure_success(handle_result.unwrap()));
+
+        let new_supply = ReadonlyConfig::from_storage(&deps.storage).total_supply();
+        assert_eq!(new_supply, 5000 + 300);
+        let giannis_canonical = deps
+            .api
+            .canonical_address(&HumanAddr("giannis".to_string()))
+            .unwrap();
+        let balances = ReadonlyBalances::from_storage(&deps.storage);
+        assert_eq!(200, balances.account_amount(&giannis_canonical));
+    }
+
     #[test]
     fn test_handle_admin_commands() {
         let admin_err = "Admin commands can only be run from admin address".to_string();
@@ -2190,6 +2683,116 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_permit_queries() {
+        let (init_result, mut deps) = init_helper(vec![InitialBalance {
+            address: HumanAddr("giannis".to_string()),
+            amount: Uint128(5000),
+        }]);
+        assert!(
+            init_result.is_ok(),
+            "Init failed: {}",
+            init_result.err().unwrap()
+        );
+
+        let token = HumanAddr(MOCK_CONTRACT_ADDR.to_string());
+
+        let permit = Permit::new(
+            "giannis",
+            vec![QueryPermission::Balance],
+            vec![token.clone()],
+            "balance",
+        );
+        let query_msg = QueryMsg::WithPermit {
+            permit: permit.clone(),
+            query: QueryWithPermit::Balance {},
+        };
+        let query_response = query(&deps, query_msg).unwrap();
+        let balance = match from_binary(&query_response).unwrap() {
+            QueryAnswer::Balance { amount } => amount,
+            _ => panic!("Unexpected result from query"),
+        };
+        assert_eq!(balance, Uint128(5000));
+
+        // Permissions are scoped
+        let query_msg = QueryMsg::WithPermit {
+            permit: permit.clone(),
+            query: QueryWithPermit::TransferHistory {
+                page: None,
+                page_size: 10,
+            },
+        };
+        let error = extract_error_msg(query(&deps, query_msg));
+        assert!(error.contains("No permission to query History"));
+
+        // Owner permission allows everything
+        let owner_permit = Permit::new(
+            "giannis",
+            vec![QueryPermission::Owner],
+            vec![token.clone()],
+            "owner",
+        );
+        let query_msg = QueryMsg::WithPermit {
+            permit: owner_permit,
+            query: QueryWithPermit::TransferHistory {
+                page: None,
+                page_size: 10,
+            },
+        };
+        let query_response = query(&deps, query_msg).unwrap();
+        let txs = match from_binary(&query_response).unwrap() {
+            QueryAnswer::TransferHistory { txs } => txs,
+            _ => panic!("Unexpected result from query"),
+        };
+        assert!(txs.is_empty());
+
+        // Allowances can only be queried by the owner or the spender
+        let allowance_permit = Permit::new(
+            "giannis",
+            vec![QueryPermission::Allowance],
+            vec![token.clone()],
+            "allowance",
+        );
+        let query_msg = QueryMsg::WithPermit {
+            permit: allowance_permit,
+            query: QueryWithPermit::Allowance {
+                owner: HumanAddr("lebron".to_string()),
+                spender: HumanAddr("kobe".to_string()),
+            },
+        };
+        let error = extract_error_msg(query(&deps, query_msg));
+        assert!(error.contains("Cannot query allowance"));
+
+        // Permits are bound to a token
+        let other_token_permit = Permit::new(
+            "giannis",
+            vec![QueryPermission::Balance],
+            vec![HumanAddr("other_token".to_string())],
+            "balance",
+        );
+        let query_msg = QueryMsg::WithPermit {
+            permit: other_token_permit,
+            query: QueryWithPermit::Balance {},
+        };
+        let error = extract_error_msg(query(&deps, query_msg));
+        assert!(error.contains("Permit doesn't apply to token"));
+
+        // Revoked permits stop working
+        let handle_msg = HandleMsg::RevokePermit {
+            permit_name: "balance".to_string(),
+            padding: None,
+        };
+        let handle_result = handle(&mut deps, mock_env("giannis", &[]), handle_msg);
+        assert!(ensure_success(handle_result.unwrap()));
+
+        let query_msg = QueryMsg::WithPermit {
+            permit,
+            query: QueryWithPermit::Balance {},
+        };
+        let error = extract_error_msg(query(&deps, query_msg));
+        assert!(error.contains("was revoked"));
+    }
+
     #[test]
     fn test_query_token_info() {
         let init_name = "sec-sec".to_string();
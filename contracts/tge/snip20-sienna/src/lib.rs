@@ -1,5 +1,6 @@
 pub mod contract;
 pub mod msg;
+pub mod permit;
 mod rand;
 pub mod receiver;
 pub mod state;
@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::{Binary, HumanAddr, StdError, StdResult, Uint128};
 
+use crate::permit::Permit;
 use crate::state::Tx;
 use crate::viewing_key::ViewingKey;
 
@@ -88,6 +89,12 @@ pub enum HandleMsg {
         padding: Option<String>,
     },
 
+    // Permit
+    RevokePermit {
+        permit_name: String,
+        padding: Option<String>,
+    },
+
     // Allowance
     IncreaseAllowance {
         spender: HumanAddr,
@@ -120,12 +127,38 @@ pub enum HandleMsg {
         padding: Option<String>,
     },
 
+    // Batch
+    BatchTransfer {
+        actions: Vec<TransferAction>,
+        padding: Option<String>,
+    },
+    BatchSend {
+        actions: Vec<SendAction>,
+        padding: Option<String>,
+    },
+    BatchTransferFrom {
+        actions: Vec<TransferFromAction>,
+        padding: Option<String>,
+    },
+    BatchSendFrom {
+        actions: Vec<SendFromAction>,
+        padding: Option<String>,
+    },
+    BatchBurnFrom {
+        actions: Vec<BurnFromAction>,
+        padding: Option<String>,
+    },
+
     // Mint
     Mint {
         recipient: HumanAddr,
         amount: Uint128,
         padding: Option<String>,
     },
+    BatchMint {
+        actions: Vec<MintAction>,
+        padding: Option<String>,
+    },
     AddMinters {
         minters: Vec<HumanAddr>,
         padding: Option<String>,
@@ -150,6 +183,55 @@ pub enum HandleMsg {
     },
 }
 
+// Batch actions, as described in SNIP-22:
+// https://github.com/SecretFoundation/SNIPs/blob/master/SNIP-22.md
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TransferAction {
+    pub recipient: HumanAddr,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SendAction {
+    pub recipient: HumanAddr,
+    pub amount: Uint128,
+    pub msg: Option<Binary>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TransferFromAction {
+    pub owner: HumanAddr,
+    pub recipient: HumanAddr,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SendFromAction {
+    pub owner: HumanAddr,
+    pub recipient: HumanAddr,
+    pub amount: Uint128,
+    pub msg: Option<Binary>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct MintAction {
+    pub recipient: HumanAddr,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BurnFromAction {
+    pub owner: HumanAddr,
+    pub amount: Uint128,
+}
+
 #[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum HandleAnswer {
@@ -181,6 +263,11 @@ pub enum HandleAnswer {
         status: ResponseStatus,
     },
 
+    // Permit
+    RevokePermit {
+        status: ResponseStatus,
+    },
+
     // Allowance
     IncreaseAllowance {
         spender: HumanAddr,
@@ -202,10 +289,30 @@ pub enum HandleAnswer {
         status: ResponseStatus,
     },
 
+    // Batch
+    BatchTransfer {
+        status: ResponseStatus,
+    },
+    BatchSend {
+        status: ResponseStatus,
+    },
+    BatchTransferFrom {
+        status: ResponseStatus,
+    },
+    BatchSendFrom {
+        status: ResponseStatus,
+    },
+    BatchBurnFrom {
+        status: ResponseStatus,
+    },
+
     // Mint
     Mint {
         status: ResponseStatus,
     },
+    BatchMint {
+        status: ResponseStatus,
+    },
     AddMinters {
         status: ResponseStatus,
     },
@@ -246,6 +353,36 @@ pub enum QueryMsg {
         page_size: u32,
     },
     Minters {},
+    WithPermit {
+        permit: Permit,
+        query: QueryWithPermit,
+    },
+}
+
+/// Queries that can be authenticated with a permit instead of a viewing key.
+/// The account is the one that signed the permit.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryWithPermit {
+    Allowance {
+        owner: HumanAddr,
+        spender: HumanAddr,
+    },
+    Balance {},
+    TransferHistory {
+        page: Option<u32>,
+        page_size: u32,
+    },
+}
+
+/// What a permit allows its bearer to query. `Owner` allows everything.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryPermission {
+    Allowance,
+    Balance,
+    History,
+    Owner,
 }
 
 impl QueryMsg {
@@ -0,0 +1,299 @@
+/// Query permits, as described in SNIP-24:
+/// https://github.com/SecretFoundation/SNIPs/blob/master/SNIP-24.md
+use cosmwasm_std::{to_binary, Api, Binary, CanonicalAddr, HumanAddr, StdError, StdResult, Storage, Uint128};
+use ripemd160::{Digest, Ripemd160};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[cfg(target_arch = "wasm32")]
+use secp256k1::Secp256k1;
+use sha2::Sha256;
+
+use crate::msg::QueryPermission;
+use crate::state::is_permit_revoked;
+
+/// Outside of wasm32 builds, the signature is replaced with the address of the signer
+/// so that permits can be used in tests.
+#[cfg(target_arch = "wasm32")]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub struct Permit {
+    pub params: PermitParams,
+    pub signature: PermitSignature,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub struct Permit {
+    pub params: PermitParams,
+    pub address: HumanAddr,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Permit {
+    pub fn new(
+        address: impl Into<HumanAddr>,
+        permissions: Vec<QueryPermission>,
+        allowed_tokens: Vec<HumanAddr>,
+        permit_name: impl Into<String>,
+    ) -> Self {
+        Self {
+            params: PermitParams {
+                permissions,
+                permit_name: permit_name.into(),
+                allowed_tokens,
+                chain_id: "cosmos-testnet-14002".into(),
+            },
+            address: address.into(),
+        }
+    }
+}
+
+impl Permit {
+    #[inline]
+    pub fn check_permission(&self, permission: &QueryPermission) -> bool {
+        self.params.permissions.contains(permission)
+    }
+
+    /// Returns the address of the account that signed the permit
+    /// if it applies to `token` and hasn't been revoked.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn validate<S: Storage, A: Api>(
+        &self,
+        storage: &S,
+        _api: &A,
+        token: &HumanAddr,
+    ) -> StdResult<HumanAddr> {
+        self.check_token(token)?;
+        self.check_not_revoked(storage, &self.address)?;
+
+        Ok(self.address.clone())
+    }
+
+    /// Returns the address of the account that signed the permit
+    /// if it applies to `token` and hasn't been revoked.
+    #[cfg(target_arch = "wasm32")]
+    pub fn validate<S: Storage, A: Api>(
+        &self,
+        storage: &S,
+        api: &A,
+        token: &HumanAddr,
+    ) -> StdResult<HumanAddr> {
+        self.check_token(token)?;
+
+        // Derive account from pubkey
+        let pubkey = &self.signature.pub_key.value;
+        let account = api.human_address(&pubkey_to_account(pubkey))?;
+
+        self.check_not_revoked(storage, &account)?;
+
+        // Validate signature, reference: https://github.com/enigmampc/SecretNetwork/blob/f591ed0cb3af28608df3bf19d6cfb733cca48100/cosmwasm/packages/wasmi-runtime/src/crypto/secp256k1.rs#L49-L82
+        let signed_bytes = to_binary(&SignedPermit::from_params(&self.params))?;
+        let signed_bytes_hash = Sha256::digest(signed_bytes.as_slice());
+
+        let secp256k1_msg =
+            secp256k1::Message::from_slice(signed_bytes_hash.as_slice()).map_err(|err| {
+                StdError::generic_err(format!(
+                    "Failed to create a secp256k1 message from signed_bytes: {:?}",
+                    err
+                ))
+            })?;
+
+        let secp256k1_verifier = Secp256k1::verification_only();
+
+        let secp256k1_signature =
+            secp256k1::Signature::from_compact(&self.signature.signature.0)
+                .map_err(|err| StdError::generic_err(format!("Malformed signature: {:?}", err)))?;
+
+        let secp256k1_pubkey = secp256k1::PublicKey::from_slice(pubkey.0.as_slice())
+            .map_err(|err| StdError::generic_err(format!("Malformed pubkey: {:?}", err)))?;
+
+        secp256k1_verifier
+            .verify(&secp256k1_msg, &secp256k1_signature, &secp256k1_pubkey)
+            .map_err(|err| {
+                StdError::generic_err(format!(
+                    "Failed to verify signatures for the given permit: {:?}",
+                    err
+                ))
+            })?;
+
+        Ok(account)
+    }
+
+    fn check_token(&self, token: &HumanAddr) -> StdResult<()> {
+        if self.params.allowed_tokens.contains(token) {
+            return Ok(());
+        }
+
+        Err(StdError::generic_err(format!(
+            "Permit doesn't apply to token {}, allowed tokens: {}",
+            token.0,
+            self.params
+                .allowed_tokens
+                .iter()
+                .map(|a| a.0.as_str())
+                .collect::<Vec<&str>>()
+                .join(", ")
+        )))
+    }
+
+    fn check_not_revoked<S: Storage>(&self, storage: &S, account: &HumanAddr) -> StdResult<()> {
+        if is_permit_revoked(storage, account, &self.params.permit_name) {
+            return Err(StdError::generic_err(format!(
+                "Permit {:?} was revoked by account {:?}",
+                self.params.permit_name,
+                account.as_str()
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+pub fn pubkey_to_account(pubkey: &Binary) -> CanonicalAddr {
+    let mut hasher = Ripemd160::new();
+    hasher.update(Sha256::digest(&pubkey.0));
+    CanonicalAddr(Binary(hasher.finalize().to_vec()))
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub struct PermitParams {
+    pub allowed_tokens: Vec<HumanAddr>,
+    pub permit_name: String,
+    pub chain_id: String,
+    pub permissions: Vec<QueryPermission>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub struct PermitSignature {
+    pub pub_key: PubKey,
+    pub signature: Binary,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub struct PubKey {
+    /// ignored, but must be "tendermint/PubKeySecp256k1" otherwise the verification will fail
+    pub r#type: String,
+    /// Secp256k1 PubKey
+    pub value: Binary,
+}
+
+// Note: The order of fields in this struct is important for the permit signature verification!
+#[remain::sorted]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub struct SignedPermit {
+    /// ignored
+    pub account_number: Uint128,
+    /// ignored, no Env in query
+    pub chain_id: String,
+    /// ignored
+    pub fee: Fee,
+    /// ignored
+    pub memo: String,
+    /// the signed message
+    pub msgs: Vec<PermitMsg>,
+    /// ignored
+    pub sequence: Uint128,
+}
+
+impl SignedPermit {
+    pub fn from_params(params: &PermitParams) -> Self {
+        Self {
+            account_number: Uint128::zero(),
+            chain_id: params.chain_id.clone(),
+            fee: Fee::new(),
+            memo: String::new(),
+            msgs: vec![PermitMsg::from_content(PermitContent::from_params(params))],
+            sequence: Uint128::zero(),
+        }
+    }
+}
+
+// Note: The order of fields in this struct is important for the permit signature verification!
+#[remain::sorted]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub struct Fee {
+    pub amount: Vec<Coin>,
+    pub gas: Uint128,
+}
+
+impl Fee {
+    pub fn new() -> Self {
+        Self {
+            amount: vec![Coin::new()],
+            gas: Uint128(1),
+        }
+    }
+}
+
+// Note: The order of fields in this struct is important for the permit signature verification!
+#[remain::sorted]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub struct Coin {
+    pub amount: Uint128,
+    pub denom: String,
+}
+
+impl Coin {
+    pub fn new() -> Self {
+        Self {
+            amount: Uint128::zero(),
+            denom: "uscrt".to_string(),
+        }
+    }
+}
+
+// Note: The order of fields in this struct is important for the permit signature verification!
+#[remain::sorted]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub struct PermitMsg {
+    pub r#type: String,
+    pub value: PermitContent,
+}
+
+impl PermitMsg {
+    pub fn from_content(content: PermitContent) -> Self {
+        Self {
+            r#type: "query_permit".to_string(),
+            value: content,
+        }
+    }
+}
+
+// Note: The order of fields in this struct is important for the permit signature verification!
+#[remain::sorted]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub struct PermitContent {
+    pub allowed_tokens: Vec<HumanAddr>,
+    pub permissions: Vec<QueryPermission>,
+    pub permit_name: String,
+}
+
+impl PermitContent {
+    pub fn from_params(params: &PermitParams) -> Self {
+        Self {
+            allowed_tokens: params.allowed_tokens.clone(),
+            permit_name: params.permit_name.clone(),
+            permissions: params.permissions.clone(),
+        }
+    }
+}
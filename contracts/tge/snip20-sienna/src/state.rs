@@ -23,12 +23,14 @@ pub const KEY_TOTAL_SUPPLY: &[u8] = b"total_supply";
 pub const KEY_CONTRACT_STATUS: &[u8] = b"contract_status";
 pub const KEY_MINTERS: &[u8] = b"minters";
 pub const KEY_TX_COUNT: &[u8] = b"tx-count";
+pub const KEY_CONTRACT_ADDRESS: &[u8] = b"contract_address";
 
 pub const PREFIX_CONFIG: &[u8] = b"config";
 pub const PREFIX_BALANCES: &[u8] = b"balances";
 pub const PREFIX_ALLOWANCES: &[u8] = b"allowances";
 pub const PREFIX_VIEW_KEY: &[u8] = b"viewingkey";
 pub const PREFIX_RECEIVERS: &[u8] = b"receivers";
+pub const PREFIX_REVOKED_PERMITS: &[u8] = b"revoked_permits";
 
 // Note that id is a globally incrementing counter.
 // Since it's 64 bits long, even at 50 tx/s it would take
@@ -196,6 +198,10 @@ impl<'a, S: ReadonlyStorage> ReadonlyConfig<'a, S> {
     pub fn tx_count(&self) -> u64 {
         self.as_readonly().tx_count()
     }
+
+    pub fn contract_address(&self) -> StdResult<HumanAddr> {
+        self.as_readonly().contract_address()
+    }
 }
 
 fn set_bin_data<T: Serialize, S: Storage>(storage: &mut S, key: &[u8], data: &T) -> StdResult<()> {
@@ -289,6 +295,14 @@ impl<'a, S: Storage> Config<'a, S> {
     pub fn set_tx_count(&mut self, count: u64) -> StdResult<()> {
         set_bin_data(&mut self.storage, KEY_TX_COUNT, &count)
     }
+
+    pub fn contract_address(&self) -> StdResult<HumanAddr> {
+        self.as_readonly().contract_address()
+    }
+
+    pub fn set_contract_address(&mut self, address: &HumanAddr) -> StdResult<()> {
+        set_bin_data(&mut self.storage, KEY_CONTRACT_ADDRESS, address)
+    }
 }
 
 /// This struct refactors out the readonly methods that we need for `Config` and `ReadonlyConfig`
@@ -335,6 +349,10 @@ impl<'a, S: ReadonlyStorage> ReadonlyConfigImpl<'a, S> {
     pub fn tx_count(&self) -> u64 {
         get_bin_data(self.0, KEY_TX_COUNT).unwrap_or_default()
     }
+
+    fn contract_address(&self) -> StdResult<HumanAddr> {
+        get_bin_data(self.0, KEY_CONTRACT_ADDRESS)
+    }
 }
 
 // Balances
@@ -447,6 +465,20 @@ pub fn read_viewing_key<S: Storage>(store: &S, owner: &CanonicalAddr) -> Option<
     balance_store.get(owner.as_slice())
 }
 
+// Permits
+
+pub fn revoke_permit<S: Storage>(store: &mut S, owner: &HumanAddr, permit_name: &str) {
+    let mut permit_store =
+        PrefixedStorage::multilevel(&[PREFIX_REVOKED_PERMITS, owner.as_str().as_bytes()], store);
+    permit_store.set(permit_name.as_bytes(), &[1]);
+}
+
+pub fn is_permit_revoked<S: ReadonlyStorage>(store: &S, owner: &HumanAddr, permit_name: &str) -> bool {
+    let permit_store =
+        ReadonlyPrefixedStorage::multilevel(&[PREFIX_REVOKED_PERMITS, owner.as_str().as_bytes()], store);
+    permit_store.get(permit_name.as_bytes()).is_some()
+}
+
 // Receiver Interface
 
 pub fn get_receiver_hash<S: ReadonlyStorage>(
@@ -9,6 +9,8 @@ use sienna_schedule::{Account, Pool, Schedule};
 
 use crate::setup::{ADMIN, TGE};
 
+const DEFAULT_TIMELOCK: u64 = 3 * 24 * 60 * 60;
+
 #[test]
 fn init() {
     let tge = TGE::new(false);
@@ -162,6 +164,9 @@ fn claim() {
             start_at: 0,
             interval,
             duration: 0,
+            revocable: false,
+            revoked_at: None,
+            curve: Default::default(),
         },
     )
     .unwrap();
@@ -378,6 +383,9 @@ fn can_increase_allocation() {
             start_at: 0,
             interval,
             duration: 0,
+            revocable: false,
+            revoked_at: None,
+            curve: Default::default(),
         },
     )
     .unwrap();
@@ -403,7 +411,8 @@ fn can_increase_allocation() {
         .execute(&mgmt::HandleMsg::Claim {}, tge.get_mgmt_env(claimant))
         .unwrap();
 
-    tge.ensemble
+    let err = tge
+        .ensemble
         .execute(
             &mgmt::HandleMsg::IncreaseAllocation {
                 total_increment: Uint128(100),
@@ -411,6 +420,28 @@ fn can_increase_allocation() {
             },
             tge.get_mgmt_env_as_admin(),
         )
+        .unwrap_err();
+    assert_eq!(err, StdError::generic_err(mgmt::MGMTError!(TIMELOCKED)));
+
+    tge.ensemble
+        .execute(
+            &mgmt::HandleMsg::ProposeChange {
+                change: mgmt::ScheduleChange::IncreaseAllocation {
+                    total_increment: Uint128(100),
+                    pool: Pool::partial("allocated_new", 100, &vec![]),
+                },
+            },
+            tge.get_mgmt_env_as_admin(),
+        )
+        .unwrap();
+
+    tge.ensemble.block().time = launch_time + interval + DEFAULT_TIMELOCK;
+
+    tge.ensemble
+        .execute(
+            &mgmt::HandleMsg::ExecuteChange { id: 0 },
+            tge.get_mgmt_env_as_admin(),
+        )
         .unwrap();
 
     let schedule: Schedule<HumanAddr> = tge
@@ -420,3 +451,623 @@ fn can_increase_allocation() {
 
     assert_eq!(schedule.pools[1].name, "allocated_new")
 }
+
+#[test]
+fn revoke_account() {
+    let mut tge = TGE::new(false);
+
+    let claimant = "claimant";
+    let treasury = "treasury";
+    let launch_time = 1000;
+
+    tge.add_account(
+        "TEST".into(),
+        Account::periodic("revocable", &claimant.into(), 20, 0, 0, 10, 40).revocable(),
+    )
+    .unwrap();
+    tge.add_account(
+        "TEST".into(),
+        Account::periodic("other", &"other".into(), 5, 0, 0, 10, 40),
+    )
+    .unwrap();
+
+    tge.ensemble
+        .execute(
+            &mgmt::HandleMsg::SetTimelock { timelock: 5 },
+            tge.get_mgmt_env_as_admin(),
+        )
+        .unwrap();
+
+    let revoke = |name: &str| mgmt::HandleMsg::ProposeChange {
+        change: mgmt::ScheduleChange::RevokeAccount {
+            pool: "TEST".into(),
+            name: name.into(),
+            treasury: Some(treasury.into()),
+        },
+    };
+
+    let err = tge
+        .ensemble
+        .execute(&revoke("revocable"), tge.get_mgmt_env_as_admin())
+        .unwrap_err();
+    assert_eq!(err, StdError::generic_err(mgmt::MGMTError!(PRELAUNCH)));
+
+    tge.ensemble.block().time = launch_time;
+    tge.ensemble
+        .execute(&mgmt::HandleMsg::Launch {}, tge.get_mgmt_env_as_admin())
+        .unwrap();
+
+    tge.ensemble.block().time = launch_time + 10;
+
+    let err = tge
+        .ensemble
+        .execute(&revoke("revocable"), tge.get_mgmt_env(claimant))
+        .unwrap_err();
+    assert_eq!(err, StdError::unauthorized());
+
+    let err = tge
+        .ensemble
+        .execute(&revoke("other"), tge.get_mgmt_env_as_admin())
+        .unwrap_err();
+    assert_eq!(err, StdError::generic_err("account other: can't be revoked"));
+
+    tge.ensemble
+        .execute(&revoke("revocable"), tge.get_mgmt_env_as_admin())
+        .unwrap();
+
+    // the revocation only takes effect once the timelock passes
+    let query_progress = |time| mgmt::QueryMsg::Progress {
+        address: claimant.into(),
+        time,
+    };
+
+    let progress: mgmt::ProgressResponse = tge
+        .ensemble
+        .query(tge.mgmt.address.clone(), query_progress(launch_time + 10))
+        .unwrap();
+    assert!(!progress.revoked);
+    assert_eq!(tge.query_balance(treasury), Uint128::zero());
+
+    let execute = mgmt::HandleMsg::ExecuteChange { id: 0 };
+
+    let err = tge
+        .ensemble
+        .execute(&execute, tge.get_mgmt_env_as_admin())
+        .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err(mgmt::MGMTError!(TOO_EARLY, launch_time + 15))
+    );
+
+    tge.ensemble.block().time = launch_time + 15;
+
+    tge.ensemble
+        .execute(&execute, tge.get_mgmt_env_as_admin())
+        .unwrap();
+
+    // two portions of 5 have vested, the rest goes to the treasury
+    assert_eq!(tge.query_balance(treasury), Uint128(10));
+
+    let err = tge
+        .ensemble
+        .execute(&revoke("revocable"), tge.get_mgmt_env_as_admin())
+        .unwrap_err();
+    assert_eq!(err, StdError::generic_err("account revocable: already revoked"));
+
+    let schedule = tge.query_schedule();
+    assert_eq!(schedule.total, Uint128(15));
+    assert_eq!(schedule.pools[0].total, Uint128(15));
+
+    tge.ensemble.block().time = launch_time + 100;
+
+    let progress: mgmt::ProgressResponse = tge
+        .ensemble
+        .query(tge.mgmt.address.clone(), query_progress(launch_time + 100))
+        .unwrap();
+    assert_eq!(progress.unlocked, Uint128(10));
+    assert!(progress.revoked);
+
+    tge.ensemble
+        .execute(&mgmt::HandleMsg::Claim {}, tge.get_mgmt_env(claimant))
+        .unwrap();
+    assert_eq!(tge.query_balance(claimant), Uint128(10));
+
+    let err = tge
+        .ensemble
+        .execute(&mgmt::HandleMsg::Claim {}, tge.get_mgmt_env(claimant))
+        .unwrap_err();
+    assert_eq!(err, StdError::generic_err(mgmt::MGMTError!(NOTHING)));
+
+    let history: mgmt::HistoryResponse = tge
+        .ensemble
+        .query(
+            tge.mgmt.address,
+            mgmt::QueryMsg::History {
+                pagination: mgmt::Pagination {
+                    start: 0,
+                    limit: 50,
+                },
+            },
+        )
+        .unwrap();
+
+    assert_eq!(history.total, 2);
+    assert_eq!(history.entries[0].event, mgmt::Event::Revoke);
+    assert_eq!(history.entries[0].amount, Uint128(10));
+    assert_eq!(history.entries[1].event, mgmt::Event::Claim);
+    assert_eq!(history.entries[1].amount, Uint128(10));
+}
+
+#[test]
+fn claim_on_behalf_and_redirect() {
+    let mut tge = TGE::new(false);
+
+    let claimant = "claimant";
+    let operator = "operator";
+    let payout = "payout";
+    let launch_time = 1000;
+
+    tge.add_account(
+        "TEST".into(),
+        Account::periodic("claimant", &claimant.into(), 20, 0, 0, 10, 20),
+    )
+    .unwrap();
+
+    tge.ensemble.block().time = launch_time;
+    tge.ensemble
+        .execute(&mgmt::HandleMsg::Launch {}, tge.get_mgmt_env_as_admin())
+        .unwrap();
+
+    let claim_for = mgmt::HandleMsg::ClaimFor {
+        recipient: claimant.into(),
+    };
+
+    let err = tge
+        .ensemble
+        .execute(&claim_for, tge.get_mgmt_env(operator))
+        .unwrap_err();
+    assert_eq!(err, StdError::unauthorized());
+
+    let set_operator = mgmt::HandleMsg::SetOperator {
+        operator: Some(operator.into()),
+    };
+
+    let err = tge
+        .ensemble
+        .execute(&set_operator, tge.get_mgmt_env(operator))
+        .unwrap_err();
+    assert_eq!(err, StdError::unauthorized());
+
+    tge.ensemble
+        .execute(&set_operator, tge.get_mgmt_env_as_admin())
+        .unwrap();
+
+    let current: Option<HumanAddr> = tge
+        .ensemble
+        .query(tge.mgmt.address.clone(), mgmt::QueryMsg::Operator {})
+        .unwrap();
+    assert_eq!(current, Some(operator.into()));
+
+    // recipients have to opt in before the operator can claim for them
+    let err = tge
+        .ensemble
+        .execute(&claim_for, tge.get_mgmt_env(operator))
+        .unwrap_err();
+    assert_eq!(err, StdError::unauthorized());
+
+    tge.ensemble
+        .execute(
+            &mgmt::HandleMsg::AllowOperatorClaims { allowed: true },
+            tge.get_mgmt_env(claimant),
+        )
+        .unwrap();
+
+    let accept = mgmt::HandleMsg::AcceptPayoutAddress {
+        recipient: claimant.into(),
+    };
+
+    let err = tge
+        .ensemble
+        .execute(&accept, tge.get_mgmt_env(payout))
+        .unwrap_err();
+    assert_eq!(err, StdError::generic_err(mgmt::MGMTError!(NO_PROPOSAL)));
+
+    tge.ensemble
+        .execute(
+            &mgmt::HandleMsg::ProposePayoutAddress {
+                address: payout.into(),
+            },
+            tge.get_mgmt_env(claimant),
+        )
+        .unwrap();
+
+    let err = tge
+        .ensemble
+        .execute(&accept, tge.get_mgmt_env("rando"))
+        .unwrap_err();
+    assert_eq!(err, StdError::unauthorized());
+
+    let query_payout = || mgmt::QueryMsg::PayoutAddress {
+        address: claimant.into(),
+    };
+
+    // proposing alone doesn't redirect anything
+    let response: mgmt::PayoutAddressResponse = tge
+        .ensemble
+        .query(tge.mgmt.address.clone(), query_payout())
+        .unwrap();
+    assert_eq!(response.address, HumanAddr::from(claimant));
+    assert_eq!(response.proposed, Some(payout.into()));
+    assert!(response.operator_allowed);
+
+    tge.ensemble
+        .execute(&accept, tge.get_mgmt_env(payout))
+        .unwrap();
+
+    let response: mgmt::PayoutAddressResponse = tge
+        .ensemble
+        .query(tge.mgmt.address.clone(), query_payout())
+        .unwrap();
+    assert_eq!(response.address, HumanAddr::from(payout));
+    assert_eq!(response.proposed, None);
+
+    tge.ensemble
+        .execute(&claim_for, tge.get_mgmt_env(operator))
+        .unwrap();
+
+    assert_eq!(tge.query_balance(payout), Uint128(10));
+    assert_eq!(tge.query_balance(operator), Uint128::zero());
+    assert_eq!(tge.query_balance(claimant), Uint128::zero());
+
+    tge.ensemble.block().time = launch_time + 10;
+
+    tge.ensemble
+        .execute(&mgmt::HandleMsg::Claim {}, tge.get_mgmt_env(claimant))
+        .unwrap();
+
+    assert_eq!(tge.query_balance(payout), Uint128(20));
+    assert_eq!(tge.query_balance(claimant), Uint128::zero());
+
+    let history: mgmt::HistoryResponse = tge
+        .ensemble
+        .query(
+            tge.mgmt.address,
+            mgmt::QueryMsg::History {
+                pagination: mgmt::Pagination {
+                    start: 0,
+                    limit: 50,
+                },
+            },
+        )
+        .unwrap();
+
+    assert_eq!(history.total, 2);
+    assert!(history
+        .entries
+        .iter()
+        .all(|entry| entry.claimant == HumanAddr::from(claimant)));
+}
+
+#[test]
+fn schedule_change_timelock() {
+    let mut tge = TGE::new(false);
+
+    let launch_time = 1000;
+    let timelock = 100;
+
+    let err = tge
+        .ensemble
+        .execute(
+            &mgmt::HandleMsg::SetTimelock { timelock },
+            tge.get_mgmt_env("rando"),
+        )
+        .unwrap_err();
+    assert_eq!(err, StdError::unauthorized());
+
+    tge.ensemble
+        .execute(
+            &mgmt::HandleMsg::SetTimelock { timelock },
+            tge.get_mgmt_env_as_admin(),
+        )
+        .unwrap();
+
+    let add = |name: &str, amount: u128| mgmt::ScheduleChange::AddAccount {
+        pool_name: "TEST".into(),
+        account: Account::immediate(name, &HumanAddr::from(name), amount),
+    };
+
+    // proposals only make sense after launch
+    let err = tge
+        .ensemble
+        .execute(
+            &mgmt::HandleMsg::ProposeChange { change: add("alice", 10) },
+            tge.get_mgmt_env_as_admin(),
+        )
+        .unwrap_err();
+    assert_eq!(err, StdError::generic_err(mgmt::MGMTError!(PRELAUNCH)));
+
+    tge.ensemble.block().time = launch_time;
+    tge.ensemble
+        .execute(&mgmt::HandleMsg::Launch {}, tge.get_mgmt_env_as_admin())
+        .unwrap();
+
+    let err = tge
+        .ensemble
+        .execute(
+            &mgmt::HandleMsg::SetTimelock { timelock: 0 },
+            tge.get_mgmt_env_as_admin(),
+        )
+        .unwrap_err();
+    assert_eq!(err, StdError::generic_err(mgmt::MGMTError!(UNDERWAY)));
+
+    let err = tge
+        .add_account("TEST".into(), Account::immediate("alice", &"alice".into(), 10))
+        .unwrap_err();
+    assert_eq!(err, StdError::generic_err(mgmt::MGMTError!(TIMELOCKED)));
+
+    // changes that can't be applied are rejected right away
+    let err = tge
+        .ensemble
+        .execute(
+            &mgmt::HandleMsg::ProposeChange { change: add("alice", 30) },
+            tge.get_mgmt_env_as_admin(),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("pool TEST: account (30) > unallocated funds in pool (25)")
+    );
+
+    let err = tge
+        .ensemble
+        .execute(
+            &mgmt::HandleMsg::ProposeChange { change: add("alice", 10) },
+            tge.get_mgmt_env("rando"),
+        )
+        .unwrap_err();
+    assert_eq!(err, StdError::unauthorized());
+
+    tge.ensemble
+        .execute(
+            &mgmt::HandleMsg::ProposeChange { change: add("alice", 10) },
+            tge.get_mgmt_env_as_admin(),
+        )
+        .unwrap();
+
+    tge.ensemble
+        .execute(
+            &mgmt::HandleMsg::ProposeChange { change: add("carol", 10) },
+            tge.get_mgmt_env_as_admin(),
+        )
+        .unwrap();
+
+    let pending: mgmt::PendingChangesResponse = tge
+        .ensemble
+        .query(tge.mgmt.address.clone(), mgmt::QueryMsg::PendingChanges {})
+        .unwrap();
+
+    assert_eq!(pending.timelock, timelock);
+    assert_eq!(pending.changes.len(), 2);
+    assert_eq!(pending.changes[0].id, 0);
+    assert_eq!(pending.changes[0].change, add("alice", 10));
+    assert_eq!(pending.changes[0].executable_at, launch_time + timelock);
+    assert_eq!(pending.changes[1].id, 1);
+
+    let err = tge
+        .ensemble
+        .execute(
+            &mgmt::HandleMsg::ExecuteChange { id: 0 },
+            tge.get_mgmt_env_as_admin(),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err(mgmt::MGMTError!(TOO_EARLY, launch_time + timelock))
+    );
+
+    tge.ensemble
+        .execute(
+            &mgmt::HandleMsg::CancelChange { id: 1 },
+            tge.get_mgmt_env_as_admin(),
+        )
+        .unwrap();
+
+    tge.ensemble.block().time = launch_time + timelock;
+
+    let err = tge
+        .ensemble
+        .execute(
+            &mgmt::HandleMsg::ExecuteChange { id: 1 },
+            tge.get_mgmt_env_as_admin(),
+        )
+        .unwrap_err();
+    assert_eq!(err, StdError::generic_err(mgmt::MGMTError!(NO_CHANGE, 1)));
+
+    tge.ensemble
+        .execute(
+            &mgmt::HandleMsg::ExecuteChange { id: 0 },
+            tge.get_mgmt_env_as_admin(),
+        )
+        .unwrap();
+
+    let schedule = tge.query_schedule();
+    assert_eq!(schedule.pools[0].accounts.len(), 1);
+    assert_eq!(schedule.pools[0].accounts[0].name, "alice");
+
+    let pending: mgmt::PendingChangesResponse = tge
+        .ensemble
+        .query(tge.mgmt.address.clone(), mgmt::QueryMsg::PendingChanges {})
+        .unwrap();
+    assert!(pending.changes.is_empty());
+}
+
+#[test]
+fn supply() {
+    let mut tge = TGE::new(false);
+
+    let claimant = "claimant";
+    let launch_time = 1000;
+
+    tge.add_account(
+        "TEST".into(),
+        Account::periodic("claimant", &claimant.into(), 20, 0, 0, 10, 20),
+    )
+    .unwrap();
+
+    let query = |time| mgmt::QueryMsg::Supply { time };
+
+    let result: Result<mgmt::SupplyResponse, StdError> = tge
+        .ensemble
+        .query(tge.mgmt.address.clone(), query(launch_time));
+    assert_eq!(
+        result.unwrap_err(),
+        StdError::generic_err(mgmt::MGMTError!(PRELAUNCH))
+    );
+
+    tge.ensemble.block().time = launch_time;
+    tge.ensemble
+        .execute(&mgmt::HandleMsg::Launch {}, tge.get_mgmt_env_as_admin())
+        .unwrap();
+
+    let supply: mgmt::SupplyResponse = tge
+        .ensemble
+        .query(tge.mgmt.address.clone(), query(launch_time))
+        .unwrap();
+
+    assert_eq!(supply.total, Uint128(25));
+    assert_eq!(supply.unlocked, Uint128(10));
+    assert_eq!(supply.claimed, Uint128::zero());
+    assert_eq!(supply.unclaimed, Uint128(10));
+    assert_eq!(supply.locked, Uint128(15));
+    assert_eq!(supply.pools.len(), 1);
+    assert_eq!(supply.pools[0].name, "TEST");
+    assert_eq!(supply.pools[0].unlocked, Uint128(10));
+    assert_eq!(supply.pools[0].locked, Uint128(15));
+
+    tge.ensemble
+        .execute(&mgmt::HandleMsg::Claim {}, tge.get_mgmt_env(claimant))
+        .unwrap();
+
+    let supply: mgmt::SupplyResponse = tge
+        .ensemble
+        .query(tge.mgmt.address.clone(), query(launch_time))
+        .unwrap();
+
+    assert_eq!(supply.claimed, Uint128(10));
+    assert_eq!(supply.unclaimed, Uint128::zero());
+
+    // the unallocated part of the pool stays locked
+    let supply: mgmt::SupplyResponse = tge
+        .ensemble
+        .query(tge.mgmt.address.clone(), query(launch_time + 100))
+        .unwrap();
+
+    assert_eq!(supply.elapsed, 100);
+    assert_eq!(supply.unlocked, Uint128(20));
+    assert_eq!(supply.unclaimed, Uint128(10));
+    assert_eq!(supply.locked, Uint128(5));
+}
+
+#[test]
+fn claimant_and_pool_history() {
+    let mut tge = TGE::new(false);
+
+    let launch_time = 1000;
+
+    tge.add_account(
+        "TEST".into(),
+        Account::periodic("alice", &"alice".into(), 10, 0, 0, 10, 20),
+    )
+    .unwrap();
+    tge.add_account(
+        "TEST".into(),
+        Account::immediate("bob", &"bob".into(), 10),
+    )
+    .unwrap();
+
+    tge.ensemble.block().time = launch_time;
+    tge.ensemble
+        .execute(&mgmt::HandleMsg::Launch {}, tge.get_mgmt_env_as_admin())
+        .unwrap();
+
+    for claimant in ["alice", "bob"] {
+        tge.ensemble
+            .execute(&mgmt::HandleMsg::Claim {}, tge.get_mgmt_env(claimant))
+            .unwrap();
+    }
+
+    tge.ensemble.block().time = launch_time + 10;
+    tge.ensemble
+        .execute(&mgmt::HandleMsg::Claim {}, tge.get_mgmt_env("alice"))
+        .unwrap();
+
+    let pagination = |start, limit| mgmt::Pagination { start, limit };
+
+    let history: mgmt::HistoryResponse = tge
+        .ensemble
+        .query(
+            tge.mgmt.address.clone(),
+            mgmt::QueryMsg::ClaimantHistory {
+                address: "alice".into(),
+                range: None,
+                pagination: pagination(0, 10),
+            },
+        )
+        .unwrap();
+
+    assert_eq!(history.total, 2);
+    assert!(history
+        .entries
+        .iter()
+        .all(|entry| entry.claimant == HumanAddr::from("alice") && entry.amount == Uint128(5)));
+
+    let history: mgmt::HistoryResponse = tge
+        .ensemble
+        .query(
+            tge.mgmt.address.clone(),
+            mgmt::QueryMsg::ClaimantHistory {
+                address: "alice".into(),
+                range: Some(mgmt::TimeRange {
+                    from: Some(launch_time + 5),
+                    to: None,
+                }),
+                pagination: pagination(0, 10),
+            },
+        )
+        .unwrap();
+
+    assert_eq!(history.total, 1);
+    assert_eq!(history.entries[0].timestamp, launch_time + 10);
+
+    let history: mgmt::HistoryResponse = tge
+        .ensemble
+        .query(
+            tge.mgmt.address.clone(),
+            mgmt::QueryMsg::PoolHistory {
+                pool: "TEST".into(),
+                range: None,
+                pagination: pagination(1, 1),
+            },
+        )
+        .unwrap();
+
+    assert_eq!(history.total, 3);
+    assert_eq!(history.entries.len(), 1);
+    assert_eq!(history.entries[0].claimant, HumanAddr::from("bob"));
+    assert_eq!(history.entries[0].amount, Uint128(10));
+
+    let history: mgmt::HistoryResponse = tge
+        .ensemble
+        .query(
+            tge.mgmt.address.clone(),
+            mgmt::QueryMsg::PoolHistory {
+                pool: "OTHER".into(),
+                range: None,
+                pagination: pagination(0, 10),
+            },
+        )
+        .unwrap();
+
+    assert_eq!(history.total, 0);
+    assert!(history.entries.is_empty());
+}
@@ -309,6 +309,9 @@ impl AccountFactory for Account<HumanAddr> {
             duration,
             interval,
             start_at,
+            revocable: false,
+            revoked_at: None,
+            curve: Default::default(),
         }
     }
 }
@@ -1,5 +1,5 @@
 #![allow(dead_code)]
-use fadroma::{ensemble::MockEnv, ContractLink, HumanAddr, Uint128};
+use fadroma::{ensemble::MockEnv, ContractLink, HumanAddr, StdError, Uint128};
 use sienna_mgmt::ConfigResponse;
 use sienna_rpt::LinearMap;
 use sienna_schedule::{Account, Pool, Schedule};
@@ -125,6 +125,9 @@ fn should_distribute() {
             start_at: 0,
             interval: 180,
             duration: 0,
+            revocable: false,
+            revoked_at: None,
+            curve: Default::default(),
         },
     )
     .unwrap();
@@ -155,9 +158,116 @@ fn should_distribute() {
         )
         .unwrap();
 
+    // vesting only credits the recipients
+    assert_eq!(tge.query_balance(ADMIN), initial_balance);
+
+    for (recipient, amount) in [(ADMIN, 20), ("STRANGER", 5)] {
+        let pending: Uint128 = tge
+            .ensemble
+            .query(
+                tge.rpt.address.clone(),
+                &sienna_rpt::QueryMsg::Pending {
+                    address: recipient.into(),
+                },
+            )
+            .unwrap();
+
+        assert_eq!(pending, Uint128(amount));
+    }
+
+    tge.ensemble
+        .execute(&sienna_rpt::HandleMsg::Claim {}, tge.get_rpt_env(ADMIN))
+        .unwrap();
+
     let updated_balance = tge.query_balance(ADMIN);
 
-    assert_ne!(initial_balance, updated_balance);
+    assert_eq!(updated_balance, initial_balance + Uint128(20));
+
+    let err = tge
+        .ensemble
+        .execute(&sienna_rpt::HandleMsg::Claim {}, tge.get_rpt_env(ADMIN))
+        .unwrap_err();
+
+    assert_eq!(
+        err,
+        StdError::generic_err(sienna_rpt::RPTError!(NOTHING))
+    );
+}
+
+#[test]
+fn should_carry_remainder_forward() {
+    let mut tge = TGE::default();
+    let launch_time = 1000;
+    let rpt = tge.rpt.address.clone();
+
+    // 25 vests in portions of 12, with the remainder of 1 at the end
+    tge.add_account(
+        "TEST".into(),
+        Account::periodic("RPT_SPLIT", &rpt, 25, 0, 0, 10, 20),
+    )
+    .unwrap();
+
+    tge.ensemble
+        .execute(
+            &sienna_rpt::HandleMsg::Configure {
+                distribution: LinearMap(vec![
+                    (HumanAddr::from(ADMIN), Uint128(8)),
+                    (HumanAddr::from("STRANGER"), Uint128(2)),
+                ]),
+                portion: Uint128(10),
+            },
+            tge.get_rpt_env(ADMIN),
+        )
+        .unwrap();
+
+    tge.ensemble.block().time = launch_time;
+    tge.ensemble
+        .execute(
+            &sienna_mgmt::HandleMsg::Launch {},
+            tge.get_mgmt_env_as_admin(),
+        )
+        .unwrap();
+
+    let pending = |tge: &TGE, recipient: &str| -> Uint128 {
+        tge.ensemble
+            .query(
+                tge.rpt.address.clone(),
+                &sienna_rpt::QueryMsg::Pending {
+                    address: recipient.into(),
+                },
+            )
+            .unwrap()
+    };
+
+    // 12 available: one portion is credited and 2 are carried forward
+    tge.ensemble
+        .execute(&sienna_rpt::HandleMsg::Vest {}, tge.get_rpt_env(ADMIN))
+        .unwrap();
+
+    assert_eq!(pending(&tge, ADMIN), Uint128(8));
+    assert_eq!(pending(&tge, "STRANGER"), Uint128(2));
+
+    // 12 more plus the 2 carried forward: one portion again, 4 carried forward
+    tge.ensemble.block().time = launch_time + 10;
+    tge.ensemble
+        .execute(&sienna_rpt::HandleMsg::Vest {}, tge.get_rpt_env(ADMIN))
+        .unwrap();
+
+    assert_eq!(pending(&tge, ADMIN), Uint128(16));
+    assert_eq!(pending(&tge, "STRANGER"), Uint128(4));
+
+    // the carried forward funds stay with RPT
+    assert_eq!(tge.query_balance(rpt.as_str()), Uint128(24));
+
+    // the final remainder of 1 makes 5, which is still less than a portion
+    tge.ensemble.block().time = launch_time + 20;
+    tge.ensemble
+        .execute(&sienna_rpt::HandleMsg::Vest {}, tge.get_rpt_env(ADMIN))
+        .unwrap();
+
+    assert_eq!(pending(&tge, ADMIN), Uint128(16));
+    assert_eq!(pending(&tge, "STRANGER"), Uint128(4));
+    assert_eq!(tge.query_balance(rpt.as_str()), Uint128(25));
 }
 
 #[test]
@@ -1,11 +1,53 @@
 use fadroma::{
     schemars,
-    cosmwasm_std::StdResult,
+    cosmwasm_std::{BlockInfo, StdResult, StdError},
     Uint256, Decimal256,
 };
 use serde::{Serialize, Deserialize};
 
 pub const BLOCKS_PER_YEAR: u64 = 5259600;
+pub const SECONDS_PER_YEAR: u64 = 31536000;
+
+/// Whether interest accrues per elapsed block or per elapsed second.
+#[derive(Serialize, Deserialize, schemars::JsonSchema, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum AccrualMode {
+    Block,
+    Time
+}
+
+impl Default for AccrualMode {
+    fn default() -> Self {
+        Self::Block
+    }
+}
+
+impl AccrualMode {
+    /// The number of accrual periods in a year. `blocks_year`
+    /// is only used when accruing per block.
+    pub fn periods_per_year(&self, blocks_year: Option<u64>) -> u64 {
+        match self {
+            Self::Block => blocks_year.unwrap_or(BLOCKS_PER_YEAR),
+            Self::Time => SECONDS_PER_YEAR
+        }
+    }
+
+    /// The block height or the block time, depending on the mode.
+    pub fn current(&self, block: &BlockInfo) -> u64 {
+        match self {
+            Self::Block => block.height,
+            Self::Time => block.time
+        }
+    }
+
+    /// Picks either the `block` or the `time`, depending on the mode.
+    pub fn select(&self, block: Option<u64>, time: Option<u64>) -> Option<u64> {
+        match self {
+            Self::Block => block,
+            Self::Time => time
+        }
+    }
+}
 
 #[derive(Serialize, Deserialize, schemars::JsonSchema, Debug)]
 pub struct JumpRateInterest {
@@ -16,7 +58,10 @@ pub struct JumpRateInterest {
     /// The base interest rate which is the y-intercept when utilization rate is 0. 
     pub base_rate_block: Decimal256,
     /// The utilization point at which the jump multiplier is applied.
-    pub jump_threshold: Decimal256
+    pub jump_threshold: Decimal256,
+    /// The premium over the variable borrow rate that is charged on stable rate borrows.
+    #[serde(default)]
+    pub stable_premium_block: Decimal256
 }
 
 impl JumpRateInterest {
@@ -34,7 +79,8 @@ impl JumpRateInterest {
             base_rate_block: (base_rate_year / blocks)?,
             multiplier_block: (multiplier_year / blocks)?,
             jump_multiplier_block: (jump_multiplier_year / blocks)?,
-            jump_threshold
+            jump_threshold,
+            stable_premium_block: Decimal256::zero()
         })
     }
 
@@ -52,10 +98,24 @@ impl JumpRateInterest {
             base_rate_block: (base_rate_year / blocks)?,
             multiplier_block: ((multiplier_year * Decimal256::one())? / (blocks * jump_threshold)?)?,
             jump_multiplier_block: (jump_multiplier_year / blocks)?,
-            jump_threshold
+            jump_threshold,
+            stable_premium_block: Decimal256::zero()
         })
     }
 
+    pub fn set_stable_premium(
+        &mut self,
+        stable_premium_year: Decimal256,
+        blocks_year: Option<u64>
+    ) -> StdResult<()> {
+        let blocks_year = blocks_year.unwrap_or(BLOCKS_PER_YEAR);
+        let blocks = Decimal256::from_uint256(Uint256::from(blocks_year))?;
+
+        self.stable_premium_block = (stable_premium_year / blocks)?;
+
+        Ok(())
+    }
+
     pub fn borrow_rate(
         &self,
         market_size: Decimal256,
@@ -74,6 +134,16 @@ impl JumpRateInterest {
         ((excess_rate * self.jump_multiplier_block)? / Decimal256::one())? + normal_rate
     }
 
+    /// The rate that a new stable rate borrow would be locked at.
+    pub fn stable_borrow_rate(
+        &self,
+        market_size: Decimal256,
+        num_borrows: Decimal256,
+        reserves: Decimal256
+    ) -> StdResult<Decimal256> {
+        self.borrow_rate(market_size, num_borrows, reserves)? + self.stable_premium_block
+    }
+
     pub fn supply_rate(
         &self,
         market_size: Decimal256,
@@ -89,6 +159,390 @@ impl JumpRateInterest {
     }
 }
 
+/// A point on the utilization curve of [`KinkedInterest`].
+#[derive(Serialize, Deserialize, schemars::JsonSchema, Clone, PartialEq, Debug)]
+pub struct Breakpoint {
+    /// The utilization rate at this point.
+    pub utilization: Decimal256,
+    /// The borrow rate at this point.
+    pub rate: Decimal256
+}
+
+/// Interest model with an arbitrary number of kinks. The borrow rate is
+/// linearly interpolated between the two breakpoints surrounding the
+/// current utilization rate.
+#[derive(Serialize, Deserialize, schemars::JsonSchema, Debug)]
+pub struct KinkedInterest {
+    /// Breakpoints sorted by utilization with rates per block.
+    pub breakpoints: Vec<Breakpoint>,
+    /// The premium over the variable borrow rate that is charged on stable rate borrows.
+    #[serde(default)]
+    pub stable_premium_block: Decimal256
+}
+
+impl KinkedInterest {
+    /// Creates the model from breakpoints with yearly rates. The first breakpoint
+    /// must be at 0% utilization and the last one at 100%.
+    pub fn new(
+        breakpoints_year: Vec<Breakpoint>,
+        blocks_year: Option<u64>
+    ) -> StdResult<Self> {
+        Self::validate(&breakpoints_year)?;
+
+        let blocks_year = blocks_year.unwrap_or(BLOCKS_PER_YEAR);
+        let blocks = Decimal256::from_uint256(Uint256::from(blocks_year))?;
+
+        let breakpoints = breakpoints_year
+            .into_iter()
+            .map(|point| Ok(Breakpoint {
+                utilization: point.utilization,
+                rate: (point.rate / blocks)?
+            }))
+            .collect::<StdResult<Vec<Breakpoint>>>()?;
+
+        Ok(Self {
+            breakpoints,
+            stable_premium_block: Decimal256::zero()
+        })
+    }
+
+    pub fn set_stable_premium(
+        &mut self,
+        stable_premium_year: Decimal256,
+        blocks_year: Option<u64>
+    ) -> StdResult<()> {
+        let blocks_year = blocks_year.unwrap_or(BLOCKS_PER_YEAR);
+        let blocks = Decimal256::from_uint256(Uint256::from(blocks_year))?;
+
+        self.stable_premium_block = (stable_premium_year / blocks)?;
+
+        Ok(())
+    }
+
+    pub fn borrow_rate(
+        &self,
+        market_size: Decimal256,
+        num_borrows: Decimal256,
+        reserves: Decimal256
+    ) -> StdResult<Decimal256> {
+        let util_rate = utilization_rate(market_size, num_borrows, reserves)?
+            .min(Decimal256::one());
+
+        // Validation guarantees that there are at least two breakpoints
+        // and that the last one is at 100% utilization.
+        let upper = self.breakpoints
+            .iter()
+            .position(|point| point.utilization >= util_rate)
+            .unwrap_or(self.breakpoints.len() - 1);
+
+        if upper == 0 {
+            return Ok(self.breakpoints[0].rate);
+        }
+
+        let lower = &self.breakpoints[upper - 1];
+        let upper = &self.breakpoints[upper];
+
+        let slope = ((upper.rate - lower.rate)? / (upper.utilization - lower.utilization)?)?;
+
+        ((util_rate - lower.utilization)? * slope)? + lower.rate
+    }
+
+    /// The rate that a new stable rate borrow would be locked at.
+    pub fn stable_borrow_rate(
+        &self,
+        market_size: Decimal256,
+        num_borrows: Decimal256,
+        reserves: Decimal256
+    ) -> StdResult<Decimal256> {
+        self.borrow_rate(market_size, num_borrows, reserves)? + self.stable_premium_block
+    }
+
+    pub fn supply_rate(
+        &self,
+        market_size: Decimal256,
+        num_borrows: Decimal256,
+        reserves: Decimal256,
+        reserve_factor: Decimal256
+    ) -> StdResult<Decimal256> {
+        let one_minus_reserve_factor = (Decimal256::one() - reserve_factor)?;
+        let borrow_rate = self.borrow_rate(market_size, num_borrows, reserves)?;
+        let rate_to_pool = (borrow_rate * one_minus_reserve_factor)?;
+
+        utilization_rate(market_size, num_borrows, reserves)? * rate_to_pool
+    }
+
+    fn validate(breakpoints: &[Breakpoint]) -> StdResult<()> {
+        if breakpoints.len() < 2 {
+            return Err(StdError::generic_err("At least two breakpoints are required."));
+        }
+
+        if !breakpoints[0].utilization.is_zero() {
+            return Err(StdError::generic_err("The first breakpoint must be at 0% utilization."));
+        }
+
+        if breakpoints[breakpoints.len() - 1].utilization != Decimal256::one() {
+            return Err(StdError::generic_err("The last breakpoint must be at 100% utilization."));
+        }
+
+        for pair in breakpoints.windows(2) {
+            if pair[1].utilization <= pair[0].utilization {
+                return Err(StdError::generic_err(
+                    "Breakpoints must be sorted by strictly increasing utilization."
+                ));
+            }
+
+            if pair[1].rate < pair[0].rate {
+                return Err(StdError::generic_err(
+                    "Breakpoint rates must not decrease as utilization increases."
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Interest model whose rate at the target utilization drifts over time.
+/// While utilization stays above the target, the rate at target keeps
+/// increasing and while it stays below, it keeps decreasing. The borrow
+/// rate is then derived from it using a curve centered at the target.
+/// Every market drifts separately, so the contract only stores the initial
+/// `rate_at_target` here and sets the market's own using `with_rate_at_target`.
+#[derive(Serialize, Deserialize, schemars::JsonSchema, Debug)]
+pub struct AdaptiveInterest {
+    /// The utilization rate that the model steers towards.
+    pub target_utilization: Decimal256,
+    /// The borrow rate per block at the target utilization.
+    /// Markets start from this rate before their first adjustment.
+    pub rate_at_target: Decimal256,
+    /// The lower bound of `rate_at_target`.
+    pub min_rate_at_target: Decimal256,
+    /// The upper bound of `rate_at_target`.
+    pub max_rate_at_target: Decimal256,
+    /// The relative change of `rate_at_target` per block (or second, in time mode)
+    /// when utilization is at 0% or 100%.
+    pub adjustment_speed_block: Decimal256,
+    /// The borrow rate at 100% utilization is `rate_at_target * curve_steepness`
+    /// and at 0% utilization it is `rate_at_target / curve_steepness`.
+    pub curve_steepness: Decimal256,
+    /// The premium over the variable borrow rate that is charged on stable rate borrows.
+    #[serde(default)]
+    pub stable_premium_block: Decimal256
+}
+
+impl AdaptiveInterest {
+    pub fn new(
+        target_utilization: Decimal256,
+        initial_rate_year: Decimal256,
+        min_rate_year: Decimal256,
+        max_rate_year: Decimal256,
+        adjustment_speed_year: Decimal256,
+        curve_steepness: Decimal256,
+        blocks_year: Option<u64>
+    ) -> StdResult<Self> {
+        if target_utilization.is_zero() || target_utilization >= Decimal256::one() {
+            return Err(StdError::generic_err("Target utilization must be between 0 and 1."));
+        }
+
+        if curve_steepness < Decimal256::one() {
+            return Err(StdError::generic_err("Curve steepness must be greater than or equal to 1."));
+        }
+
+        if min_rate_year > initial_rate_year || initial_rate_year > max_rate_year {
+            return Err(StdError::generic_err(
+                "Initial rate must be between the minimum and maximum rates."
+            ));
+        }
+
+        let blocks_year = blocks_year.unwrap_or(BLOCKS_PER_YEAR);
+        let blocks = Decimal256::from_uint256(Uint256::from(blocks_year))?;
+
+        Ok(Self {
+            target_utilization,
+            rate_at_target: (initial_rate_year / blocks)?,
+            min_rate_at_target: (min_rate_year / blocks)?,
+            max_rate_at_target: (max_rate_year / blocks)?,
+            adjustment_speed_block: (adjustment_speed_year / blocks)?,
+            curve_steepness,
+            stable_premium_block: Decimal256::zero()
+        })
+    }
+
+    pub fn set_stable_premium(
+        &mut self,
+        stable_premium_year: Decimal256,
+        blocks_year: Option<u64>
+    ) -> StdResult<()> {
+        let blocks_year = blocks_year.unwrap_or(BLOCKS_PER_YEAR);
+        let blocks = Decimal256::from_uint256(Uint256::from(blocks_year))?;
+
+        self.stable_premium_block = (stable_premium_year / blocks)?;
+
+        Ok(())
+    }
+
+    pub fn borrow_rate(
+        &self,
+        market_size: Decimal256,
+        num_borrows: Decimal256,
+        reserves: Decimal256
+    ) -> StdResult<Decimal256> {
+        let (error, is_above) = self.normalized_error(market_size, num_borrows, reserves)?;
+
+        if is_above {
+            let steepness = (self.curve_steepness - Decimal256::one())?;
+            let factor = ((error * steepness)? + Decimal256::one())?;
+
+            self.rate_at_target * factor
+        } else {
+            let steepness = (Decimal256::one() - (Decimal256::one() / self.curve_steepness)?)?;
+            let factor = (Decimal256::one() - (error * steepness)?)?;
+
+            self.rate_at_target * factor
+        }
+    }
+
+    /// The rate that a new stable rate borrow would be locked at.
+    pub fn stable_borrow_rate(
+        &self,
+        market_size: Decimal256,
+        num_borrows: Decimal256,
+        reserves: Decimal256
+    ) -> StdResult<Decimal256> {
+        self.borrow_rate(market_size, num_borrows, reserves)? + self.stable_premium_block
+    }
+
+    pub fn supply_rate(
+        &self,
+        market_size: Decimal256,
+        num_borrows: Decimal256,
+        reserves: Decimal256,
+        reserve_factor: Decimal256
+    ) -> StdResult<Decimal256> {
+        let one_minus_reserve_factor = (Decimal256::one() - reserve_factor)?;
+        let borrow_rate = self.borrow_rate(market_size, num_borrows, reserves)?;
+        let rate_to_pool = (borrow_rate * one_minus_reserve_factor)?;
+
+        utilization_rate(market_size, num_borrows, reserves)? * rate_to_pool
+    }
+
+    /// Replaces the `rate_at_target` with the given one, kept within the bounds
+    /// in case they were changed since the rate was last adjusted.
+    pub fn with_rate_at_target(&mut self, rate: Decimal256) {
+        self.rate_at_target = rate
+            .max(self.min_rate_at_target)
+            .min(self.max_rate_at_target);
+    }
+
+    /// Moves `rate_at_target` according to the utilization that was
+    /// in effect for the given number of `blocks`.
+    pub fn adjust(
+        &mut self,
+        market_size: Decimal256,
+        num_borrows: Decimal256,
+        reserves: Decimal256,
+        blocks: u64
+    ) -> StdResult<()> {
+        let (error, is_above) = self.normalized_error(market_size, num_borrows, reserves)?;
+
+        let blocks = Decimal256::from_uint256(Uint256::from(blocks))?;
+        let change = (((self.adjustment_speed_block * error)? * blocks)? * self.rate_at_target)?;
+
+        self.rate_at_target = if is_above {
+            (self.rate_at_target + change)?.min(self.max_rate_at_target)
+        } else {
+            // Saturate at the minimum since the change is linear
+            // and could otherwise exceed the current rate.
+            (self.rate_at_target - change)
+                .unwrap_or(self.min_rate_at_target)
+                .max(self.min_rate_at_target)
+        };
+
+        Ok(())
+    }
+
+    /// Returns the distance of the current utilization from the target, scaled
+    /// to the [0, 1] range and whether the utilization is above the target.
+    fn normalized_error(
+        &self,
+        market_size: Decimal256,
+        num_borrows: Decimal256,
+        reserves: Decimal256
+    ) -> StdResult<(Decimal256, bool)> {
+        let util_rate = utilization_rate(market_size, num_borrows, reserves)?
+            .min(Decimal256::one());
+
+        if util_rate > self.target_utilization {
+            let error = ((util_rate - self.target_utilization)? /
+                (Decimal256::one() - self.target_utilization)?)?;
+
+            Ok((error, true))
+        } else {
+            let error = ((self.target_utilization - util_rate)? / self.target_utilization)?;
+
+            Ok((error, false))
+        }
+    }
+}
+
+/// The interest model used by the interest model contract.
+// Untagged, so that the previously stored `JumpRateInterest` can still be read.
+#[derive(Serialize, Deserialize, schemars::JsonSchema, Debug)]
+#[serde(untagged)]
+pub enum InterestRateModel {
+    Adaptive(AdaptiveInterest),
+    Kinked(KinkedInterest),
+    JumpRate(JumpRateInterest)
+}
+
+impl InterestRateModel {
+    /// Whether the model needs to be notified by the markets when they accrue interest.
+    #[inline]
+    pub fn is_adaptive(&self) -> bool {
+        matches!(self, Self::Adaptive(_))
+    }
+
+    pub fn borrow_rate(
+        &self,
+        market_size: Decimal256,
+        num_borrows: Decimal256,
+        reserves: Decimal256
+    ) -> StdResult<Decimal256> {
+        match self {
+            Self::Adaptive(model) => model.borrow_rate(market_size, num_borrows, reserves),
+            Self::Kinked(model) => model.borrow_rate(market_size, num_borrows, reserves),
+            Self::JumpRate(model) => model.borrow_rate(market_size, num_borrows, reserves)
+        }
+    }
+
+    pub fn stable_borrow_rate(
+        &self,
+        market_size: Decimal256,
+        num_borrows: Decimal256,
+        reserves: Decimal256
+    ) -> StdResult<Decimal256> {
+        match self {
+            Self::Adaptive(model) => model.stable_borrow_rate(market_size, num_borrows, reserves),
+            Self::Kinked(model) => model.stable_borrow_rate(market_size, num_borrows, reserves),
+            Self::JumpRate(model) => model.stable_borrow_rate(market_size, num_borrows, reserves)
+        }
+    }
+
+    pub fn supply_rate(
+        &self,
+        market_size: Decimal256,
+        num_borrows: Decimal256,
+        reserves: Decimal256,
+        reserve_factor: Decimal256
+    ) -> StdResult<Decimal256> {
+        match self {
+            Self::Adaptive(model) => model.supply_rate(market_size, num_borrows, reserves, reserve_factor),
+            Self::Kinked(model) => model.supply_rate(market_size, num_borrows, reserves, reserve_factor),
+            Self::JumpRate(model) => model.supply_rate(market_size, num_borrows, reserves, reserve_factor)
+        }
+    }
+}
+
 pub fn utilization_rate(
     market_size: Decimal256,
     num_borrows: Decimal256,
@@ -226,6 +680,154 @@ mod tests {
         }
     }
 
+    #[test]
+    fn stable_borrow_rate() {
+        let blocks_year_raw = 2102400;
+        let blocks_year = Decimal256::from_uint256(Uint256::from(blocks_year_raw)).unwrap();
+
+        let mut model = JumpRateInterest::v1(
+            Decimal256::from_str("0.1").unwrap(),
+            Decimal256::from_str("0.2").unwrap(),
+            Decimal256::one(),
+            Decimal256::from_str("0.9").unwrap(),
+            Some(blocks_year_raw)
+        ).unwrap();
+
+        let vars = Utilization::new(Decimal256::from_str("0.1").unwrap());
+
+        let variable = model.borrow_rate(vars.market_size, vars.num_borrows, vars.reserves).unwrap();
+        let stable = model.stable_borrow_rate(vars.market_size, vars.num_borrows, vars.reserves).unwrap();
+        assert_eq!(variable, stable);
+
+        model.set_stable_premium(Decimal256::from_str("0.05").unwrap(), Some(blocks_year_raw)).unwrap();
+
+        let result = model.stable_borrow_rate(vars.market_size, vars.num_borrows, vars.reserves).unwrap();
+        let result = ((result / Decimal256::one()).unwrap() * blocks_year).unwrap();
+
+        assert_delta(result, Decimal256::from_str("0.17").unwrap());
+    }
+
+    #[test]
+    fn borrow_rate_kinked() {
+        let blocks_year_raw = 2102400;
+        let blocks_year = Decimal256::from_uint256(Uint256::from(blocks_year_raw)).unwrap();
+
+        let point = |utilization: &str, rate: &str| Breakpoint {
+            utilization: Decimal256::from_str(utilization).unwrap(),
+            rate: Decimal256::from_str(rate).unwrap()
+        };
+
+        let model = KinkedInterest::new(
+            vec![
+                point("0", "0.02"),
+                point("0.5", "0.05"),
+                point("0.9", "0.15"),
+                point("1", "1")
+            ],
+            Some(blocks_year_raw)
+        ).unwrap();
+
+        let tests = vec![
+            (Decimal256::zero(), Decimal256::from_str("0.02").unwrap()),
+            (Decimal256::from_str("0.25").unwrap(), Decimal256::from_str("0.035").unwrap()),
+            (Decimal256::from_str("0.5").unwrap(), Decimal256::from_str("0.05").unwrap()),
+            (Decimal256::from_str("0.7").unwrap(), Decimal256::from_str("0.1").unwrap()),
+            (Decimal256::from_str("0.9").unwrap(), Decimal256::from_str("0.15").unwrap()),
+            (Decimal256::from_str("0.95").unwrap(), Decimal256::from_str("0.575").unwrap()),
+            (Decimal256::one(), Decimal256::one())
+        ];
+
+        for (util, expected) in tests {
+            let vars = Utilization::new(util);
+
+            let result = model.borrow_rate(vars.market_size, vars.num_borrows, vars.reserves).unwrap();
+            let result = (result * blocks_year).unwrap();
+
+            assert_delta(result, expected);
+        }
+    }
+
+    #[test]
+    fn adaptive_rate_drifts() {
+        let blocks_year_raw = 2102400;
+        let blocks_year = Decimal256::from_uint256(Uint256::from(blocks_year_raw)).unwrap();
+
+        let mut model = AdaptiveInterest::new(
+            Decimal256::from_str("0.9").unwrap(),
+            Decimal256::from_str("0.04").unwrap(),
+            Decimal256::from_str("0.001").unwrap(),
+            Decimal256::from_str("2").unwrap(),
+            Decimal256::from_str("50").unwrap(),
+            Decimal256::from_str("4").unwrap(),
+            Some(blocks_year_raw)
+        ).unwrap();
+
+        let yearly = |model: &AdaptiveInterest, util: &str| {
+            let vars = Utilization::new(Decimal256::from_str(util).unwrap());
+            let rate = model.borrow_rate(vars.market_size, vars.num_borrows, vars.reserves).unwrap();
+
+            (rate * blocks_year).unwrap()
+        };
+
+        // The curve is centered at the target.
+        assert_delta(yearly(&model, "0.9"), Decimal256::from_str("0.04").unwrap());
+        assert_delta(yearly(&model, "1"), Decimal256::from_str("0.16").unwrap());
+        assert_delta(yearly(&model, "0"), Decimal256::from_str("0.01").unwrap());
+
+        // Utilization at target doesn't move the rate.
+        let vars = Utilization::new(Decimal256::from_str("0.9").unwrap());
+        model.adjust(vars.market_size, vars.num_borrows, vars.reserves, 1000).unwrap();
+        assert_delta(yearly(&model, "0.9"), Decimal256::from_str("0.04").unwrap());
+
+        // Sustained high utilization increases the rate.
+        let before = model.rate_at_target;
+        let vars = Utilization::new(Decimal256::from_str("0.95").unwrap());
+        model.adjust(vars.market_size, vars.num_borrows, vars.reserves, 10000).unwrap();
+        assert!(model.rate_at_target > before);
+
+        // Sustained low utilization decreases it, but never below the minimum.
+        let vars = Utilization::new(Decimal256::from_str("0.1").unwrap());
+        model.adjust(vars.market_size, vars.num_borrows, vars.reserves, 10000).unwrap();
+        assert!(model.rate_at_target < before);
+
+        model.adjust(vars.market_size, vars.num_borrows, vars.reserves, blocks_year_raw).unwrap();
+        assert_eq!(model.rate_at_target, model.min_rate_at_target);
+
+        // And sustained full utilization never goes above the maximum.
+        let vars = Utilization::new(Decimal256::one());
+        model.adjust(vars.market_size, vars.num_borrows, vars.reserves, blocks_year_raw).unwrap();
+        assert_eq!(model.rate_at_target, model.max_rate_at_target);
+    }
+
+    #[test]
+    fn kinked_validation() {
+        let point = |utilization: &str, rate: &str| Breakpoint {
+            utilization: Decimal256::from_str(utilization).unwrap(),
+            rate: Decimal256::from_str(rate).unwrap()
+        };
+
+        let err = KinkedInterest::new(vec![point("0", "0.02")], None).unwrap_err();
+        assert_eq!(err, StdError::generic_err("At least two breakpoints are required."));
+
+        let err = KinkedInterest::new(vec![point("0.1", "0.02"), point("1", "0.5")], None).unwrap_err();
+        assert_eq!(err, StdError::generic_err("The first breakpoint must be at 0% utilization."));
+
+        let err = KinkedInterest::new(vec![point("0", "0.02"), point("0.9", "0.5")], None).unwrap_err();
+        assert_eq!(err, StdError::generic_err("The last breakpoint must be at 100% utilization."));
+
+        let err = KinkedInterest::new(
+            vec![point("0", "0.02"), point("0.5", "0.1"), point("0.5", "0.2"), point("1", "0.5")],
+            None
+        ).unwrap_err();
+        assert_eq!(err, StdError::generic_err("Breakpoints must be sorted by strictly increasing utilization."));
+
+        let err = KinkedInterest::new(
+            vec![point("0", "0.02"), point("0.5", "0.1"), point("1", "0.05")],
+            None
+        ).unwrap_err();
+        assert_eq!(err, StdError::generic_err("Breakpoint rates must not decrease as utilization increases."));
+    }
+
     fn assert_delta(lhs: Decimal256, rhs: Decimal256) {
         let max = lhs.max(rhs);
         let min = lhs.min(rhs);
@@ -1,13 +1,16 @@
 use fadroma::{
     admin, cosmwasm_std,
     cosmwasm_std::{
-        to_binary, HandleResponse, HumanAddr, InitResponse, Querier, StdResult, WasmQuery,
+        to_binary, CosmosMsg, HandleResponse, HumanAddr, InitResponse, Querier, StdResult,
+        WasmMsg, WasmQuery,
     },
     derive_contract::*,
     schemars, ContractLink, Decimal256, QueryRequest,
 };
 use serde::{Deserialize, Serialize};
 
+use crate::core::{AccrualMode, Breakpoint};
+
 #[interface(component(path = "admin"))]
 pub trait InterestModel {
     #[init]
@@ -18,8 +21,13 @@ pub trait InterestModel {
         jump_multiplier_year: Decimal256,
         jump_threshold: Decimal256,
         blocks_year: Option<u64>,
+        stable_premium_year: Option<Decimal256>,
+        accrual_mode: Option<AccrualMode>,
     ) -> StdResult<InitResponse>;
 
+    /// Rates are yearly. They are converted to rates per second instead
+    /// of per block if the market using this model accrues by time.
+    /// If `accrual_mode` is not given, the current mode is kept.
     #[handle]
     fn update_config(
         base_rate_year: Decimal256,
@@ -27,38 +35,186 @@ pub trait InterestModel {
         jump_multiplier_year: Decimal256,
         jump_threshold: Decimal256,
         blocks_year: Option<u64>,
+        stable_premium_year: Option<Decimal256>,
+        accrual_mode: Option<AccrualMode>,
+    ) -> StdResult<HandleResponse>;
+
+    /// Switch to a model that interpolates between the given breakpoints.
+    /// Rates are yearly and converted the same way as in `update_config`.
+    #[handle]
+    fn update_kinked_config(
+        breakpoints: Vec<Breakpoint>,
+        blocks_year: Option<u64>,
+        stable_premium_year: Option<Decimal256>,
+        accrual_mode: Option<AccrualMode>,
+    ) -> StdResult<HandleResponse>;
+
+    /// Switch to a model whose rate at the target utilization drifts over time.
+    /// Every market that notifies the model drifts separately, starting at
+    /// the `initial_rate_year`. Rates and the adjustment speed are yearly and
+    /// converted the same way as in `update_config`.
+    #[handle]
+    fn update_adaptive_config(
+        target_utilization: Decimal256,
+        initial_rate_year: Decimal256,
+        min_rate_year: Decimal256,
+        max_rate_year: Decimal256,
+        adjustment_speed_year: Decimal256,
+        curve_steepness: Decimal256,
+        blocks_year: Option<u64>,
+        stable_premium_year: Option<Decimal256>,
+        accrual_mode: Option<AccrualMode>,
+    ) -> StdResult<HandleResponse>;
+
+    /// Called by the market whenever it accrues interest, if the model is adaptive.
+    /// The parameters are the values that were in effect for the elapsed number
+    /// of `blocks` (or seconds, in time mode). Only the rate of the calling market is adjusted.
+    #[handle]
+    fn on_accrue(
+        market_size: Decimal256,
+        num_borrows: Decimal256,
+        reserves: Decimal256,
+        blocks: u64,
     ) -> StdResult<HandleResponse>;
 
     #[query]
     fn config() -> StdResult<ConfigResponse>;
 
+    /// Whether the rates of the model are per block or per second.
+    /// Markets must accrue interest in the same mode.
+    #[query]
+    fn accrual_mode() -> StdResult<AccrualMode>;
+
+    /// The rate at target of the adaptive model for the given market.
+    #[query]
+    fn rate_at_target(market: HumanAddr) -> StdResult<Decimal256>;
+
+    /// The borrow rate that the `market` accrues interest at and
+    /// whether it needs to notify the model about it.
+    #[query]
+    fn accrual_rate(
+        market: HumanAddr,
+        market_size: Decimal256,
+        num_borrows: Decimal256,
+        reserves: Decimal256,
+    ) -> StdResult<AccrualRateResponse>;
+
+    /// The `market` is only used by the adaptive model.
     #[query]
     fn borrow_rate(
         market_size: Decimal256,
         num_borrows: Decimal256,
         reserves: Decimal256,
+        market: Option<HumanAddr>,
     ) -> StdResult<Decimal256>;
 
+    /// The `market` is only used by the adaptive model.
+    #[query]
+    fn stable_borrow_rate(
+        market_size: Decimal256,
+        num_borrows: Decimal256,
+        reserves: Decimal256,
+        market: Option<HumanAddr>,
+    ) -> StdResult<Decimal256>;
+
+    /// The `market` is only used by the adaptive model.
     #[query]
     fn supply_rate(
         market_size: Decimal256,
         num_borrows: Decimal256,
         reserves: Decimal256,
         reserve_factor: Decimal256,
+        market: Option<HumanAddr>,
     ) -> StdResult<Decimal256>;
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, schemars::JsonSchema)]
-pub struct ConfigResponse {
-    pub multiplier_block: Decimal256,
-    pub jump_multiplier_block: Decimal256,
-    pub base_rate_block: Decimal256,
-    pub jump_threshold: Decimal256,
+pub struct AccrualRateResponse {
+    pub borrow_rate: Decimal256,
+    /// Whether the model needs to be sent an `on_accrue` notification.
+    pub notify: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigResponse {
+    JumpRate {
+        multiplier_block: Decimal256,
+        jump_multiplier_block: Decimal256,
+        base_rate_block: Decimal256,
+        jump_threshold: Decimal256,
+        stable_premium_block: Decimal256,
+    },
+    Kinked {
+        breakpoints: Vec<Breakpoint>,
+        stable_premium_block: Decimal256,
+    },
+    Adaptive {
+        target_utilization: Decimal256,
+        initial_rate_at_target: Decimal256,
+        min_rate_at_target: Decimal256,
+        max_rate_at_target: Decimal256,
+        adjustment_speed_block: Decimal256,
+        curve_steepness: Decimal256,
+        stable_premium_block: Decimal256,
+    },
+}
+
+pub fn on_accrue_msg(
+    interest_model: ContractLink<HumanAddr>,
+    market_size: Decimal256,
+    num_borrows: Decimal256,
+    reserves: Decimal256,
+    blocks: u64,
+) -> StdResult<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: interest_model.address,
+        callback_code_hash: interest_model.code_hash,
+        msg: to_binary(&HandleMsg::OnAccrue {
+            market_size,
+            num_borrows,
+            reserves,
+            blocks,
+        })?,
+        send: vec![],
+    }))
+}
+
+pub fn query_accrual_mode(
+    querier: &impl Querier,
+    interest_model: ContractLink<HumanAddr>,
+) -> StdResult<AccrualMode> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: interest_model.address,
+        callback_code_hash: interest_model.code_hash,
+        msg: to_binary(&QueryMsg::AccrualMode {})?,
+    }))
+}
+
+pub fn query_accrual_rate(
+    querier: &impl Querier,
+    interest_model: ContractLink<HumanAddr>,
+    market: HumanAddr,
+    market_size: Decimal256,
+    num_borrows: Decimal256,
+    reserves: Decimal256,
+) -> StdResult<AccrualRateResponse> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: interest_model.address,
+        callback_code_hash: interest_model.code_hash,
+        msg: to_binary(&QueryMsg::AccrualRate {
+            market,
+            market_size,
+            num_borrows,
+            reserves,
+        })?,
+    }))
 }
 
 pub fn query_borrow_rate(
     querier: &impl Querier,
     interest_model: ContractLink<HumanAddr>,
+    market: HumanAddr,
     market_size: Decimal256,
     num_borrows: Decimal256,
     reserves: Decimal256,
@@ -70,6 +226,27 @@ pub fn query_borrow_rate(
             market_size,
             num_borrows,
             reserves,
+            market: Some(market),
+        })?,
+    }))
+}
+
+pub fn query_stable_borrow_rate(
+    querier: &impl Querier,
+    interest_model: ContractLink<HumanAddr>,
+    market: HumanAddr,
+    market_size: Decimal256,
+    num_borrows: Decimal256,
+    reserves: Decimal256,
+) -> StdResult<Decimal256> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: interest_model.address,
+        callback_code_hash: interest_model.code_hash,
+        msg: to_binary(&QueryMsg::StableBorrowRate {
+            market_size,
+            num_borrows,
+            reserves,
+            market: Some(market),
         })?,
     }))
 }
@@ -77,6 +254,7 @@ pub fn query_borrow_rate(
 pub fn query_supply_rate(
     querier: &impl Querier,
     interest_model: ContractLink<HumanAddr>,
+    market: HumanAddr,
     market_size: Decimal256,
     num_borrows: Decimal256,
     reserves: Decimal256,
@@ -90,6 +268,7 @@ pub fn query_supply_rate(
             num_borrows,
             reserves,
             reserve_factor,
+            market: Some(market),
         })?,
     }))
 }
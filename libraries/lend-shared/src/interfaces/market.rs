@@ -1,8 +1,8 @@
 use fadroma::{
     admin, cosmwasm_std, derive_contract::*, killswitch, schemars, schemars::JsonSchema,
-    snip20_impl::msg::QueryAnswer as Snip20Response, to_binary, Binary, Callback, ContractLink,
-    Decimal256, HandleResponse, HumanAddr, InitResponse, Querier, QueryRequest, StdError,
-    StdResult, Uint128, Uint256, WasmQuery,
+    snip20_impl::msg::QueryAnswer as Snip20Response, to_binary, Api, Binary, Callback,
+    CanonicalAddr, Canonize, ContractLink, Decimal256, HandleResponse, HumanAddr, Humanize,
+    InitResponse, Querier, QueryRequest, StdError, StdResult, Uint128, Uint256, WasmQuery,
 };
 
 use serde::{Deserialize, Serialize};
@@ -11,7 +11,8 @@ use crate::interfaces::overseer::{
     AccountLiquidity,
     Market as EnteredMarket
 };
-use crate::core::{MasterKey, AuthMethod, Pagination};
+use crate::interfaces::swap::Hop;
+use crate::core::{MasterKey, AuthMethod, AccrualMode, Pagination};
 
 #[interface(
     component(path = "admin"),
@@ -50,6 +51,19 @@ pub trait Market {
     #[handle]
     fn borrow(amount: Uint256) -> StdResult<HandleResponse>;
 
+    /// Borrow at a stable rate which is locked at the time of borrowing.
+    /// Only available if the market has a `stable_rate_threshold` set.
+    #[handle]
+    fn borrow_stable(amount: Uint256) -> StdResult<HandleResponse>;
+
+    /// Reset the stable rate of the given borrower to the current one.
+    /// Can be called by anyone, but only succeeds if the current stable rate
+    /// is below the borrower's rate by at least the `stable_rate_rebalance_delta`
+    /// or if utilization has reached the `stable_rate_threshold` and the current
+    /// rate is above theirs.
+    #[handle]
+    fn rebalance_stable_rate(borrower: Binary) -> StdResult<HandleResponse>;
+
     #[handle]
     fn transfer(recipient: HumanAddr, amount: Uint256) -> StdResult<HandleResponse>;
 
@@ -84,11 +98,82 @@ pub trait Market {
         interest_model: Option<ContractLink<HumanAddr>>,
         reserve_factor: Option<Decimal256>,
         borrow_cap: Option<Uint256>,
+        flash_loan_fee: Option<Decimal256>,
     ) -> StdResult<HandleResponse>;
 
     #[handle]
     fn reduce_reserves(amount: Uint128, to: Option<HumanAddr>) -> StdResult<HandleResponse>;
 
+    /// Set how reserves are distributed by `sweep_reserves`. Sweeping is disabled if `None`.
+    #[handle]
+    fn set_reserve_policy(policy: Option<ReservePolicy<HumanAddr>>) -> StdResult<HandleResponse>;
+
+    /// Distribute the reserves above the `min_reserves` of the reserve policy. Anyone can
+    /// send the treasury share to the treasury. If `min_buyback_output` is given, the buyback
+    /// share is also swapped into SIENNA, using the swap router of the overseer for routes
+    /// longer than one hop, and sent to the rewards pool. The swap fails if it returns less
+    /// than `min_buyback_output`, which only the admin or the `keeper` of the reserve policy
+    /// can set. Otherwise the buyback share stays in the reserves until they sweep.
+    /// Responds with a `SweepReservesResult` as data.
+    #[handle]
+    fn sweep_reserves(min_buyback_output: Option<Uint256>) -> StdResult<HandleResponse>;
+
+    /// Write off the debt of a borrower that has no collateral left in any of the
+    /// markets that they have entered. The debt is covered by the reserves first and
+    /// any remainder is socialized across suppliers by lowering the exchange rate.
+    /// Can be called by anyone.
+    #[handle]
+    fn realize_bad_debt(borrower: Binary) -> StdResult<HandleResponse>;
+
+    /// Send `amount` of the underlying to the `callback` contract and execute it.
+    /// By the end of the callback, the amount plus the `flash_loan_fee` must have been
+    /// transferred back to the market. While the loan is in progress the market's balance
+    /// doesn't reflect its actual liquidity, so every handle and query that depends on it
+    /// (including deposits, repays, liquidations and the exchange rate) is rejected.
+    /// The funds must be returned using a plain transfer.
+    #[handle]
+    fn flash_loan(amount: Uint256, callback: Callback<HumanAddr>) -> StdResult<HandleResponse>;
+
+    /// Executed by the market itself after the flash loan callback in order to
+    /// verify that the loan has been paid back.
+    #[handle]
+    fn finish_flash_loan() -> StdResult<HandleResponse>;
+
+    /// Redeem slTokens of the `owner` and send the underlying to `recipient` along with `msg`.
+    /// Called by the overseer as the first step of swapping collateral, which performs the
+    /// liquidity check once the whole operation has completed.
+    #[handle]
+    fn redeem_to(
+        owner: HumanAddr,
+        burn_amount: Uint256,
+        recipient: ContractLink<HumanAddr>,
+        msg: Binary
+    ) -> StdResult<HandleResponse>;
+
+    /// Called by the overseer after collateral was swapped into the underlying of this market.
+    /// Everything received since the market had `balance_before` is used for the `action`.
+    #[handle]
+    fn apply_swap_output(
+        balance_before: Uint256,
+        min_amount: Uint256,
+        action: SwapOutputAction
+    ) -> StdResult<HandleResponse>;
+
+    /// Pause the given actions in addition to the ones that are already paused.
+    /// Can be called by the admin or by the overseer on behalf of the guardian.
+    #[handle]
+    fn pause(actions: PausedActions) -> StdResult<HandleResponse>;
+
+    /// Set exactly which actions are paused. Can only be called by the admin.
+    #[handle]
+    fn set_paused(actions: PausedActions) -> StdResult<HandleResponse>;
+
+    /// Start reporting balance changes to the overseer so that it can distribute rewards.
+    /// Called by the overseer once reward speeds are set for the market. Can't be undone,
+    /// since the overseer would otherwise keep distributing based on stale balances.
+    #[handle]
+    fn enable_rewards() -> StdResult<HandleResponse>;
+
     #[handle]
     fn create_viewing_key(entropy: String, padding: Option<String>) -> StdResult<HandleResponse>;
 
@@ -98,6 +183,7 @@ pub trait Market {
     #[query]
     fn simulate_liquidation(
         block: u64,
+        time: Option<u64>,
         borrower: Binary,
         collateral: HumanAddr,
         amount: Uint256
@@ -110,17 +196,34 @@ pub trait Market {
         amount: Uint256
     ) -> StdResult<Uint256>;
 
+    #[query]
+    fn paused() -> StdResult<PausedActions>;
+
+    /// The cumulative amount of debt that has been written off in this market.
+    #[query]
+    fn bad_debt() -> StdResult<BadDebt>;
+
+    #[query]
+    fn reserve_policy() -> StdResult<Option<ReservePolicy<HumanAddr>>>;
+
     #[query]
     fn token_info() -> StdResult<Snip20Response>;
 
     #[query]
     fn balance(address: HumanAddr, key: String) -> StdResult<Uint128>;
 
+    /// Queries that accept both a `block` and a `time` accrue interest up to the one
+    /// matching the `accrual_mode` of the market. If it isn't set, the values as of
+    /// the last accrual are returned.
     #[query]
-    fn balance_underlying(method: MarketAuth, block: Option<u64>) -> StdResult<Uint128>;
+    fn balance_underlying(
+        method: MarketAuth,
+        block: Option<u64>,
+        time: Option<u64>
+    ) -> StdResult<Uint128>;
 
     #[query]
-    fn state(block: Option<u64>) -> StdResult<State>;
+    fn state(block: Option<u64>, time: Option<u64>) -> StdResult<State>;
 
     #[query]
     fn underlying_asset() -> StdResult<ContractLink<HumanAddr>>;
@@ -129,16 +232,23 @@ pub trait Market {
     fn interest_model() -> StdResult<ContractLink<HumanAddr>>;
 
     #[query]
-    fn borrow_rate(block: Option<u64>) -> StdResult<Decimal256>;
+    fn borrow_rate(block: Option<u64>, time: Option<u64>) -> StdResult<Decimal256>;
+
+    #[query]
+    fn stable_borrow_rate(block: Option<u64>, time: Option<u64>) -> StdResult<Decimal256>;
 
     #[query]
-    fn supply_rate(block: Option<u64>) -> StdResult<Decimal256>;
+    fn supply_rate(block: Option<u64>, time: Option<u64>) -> StdResult<Decimal256>;
 
     #[query]
-    fn exchange_rate(block: Option<u64>) -> StdResult<Decimal256>;
+    fn exchange_rate(block: Option<u64>, time: Option<u64>) -> StdResult<Decimal256>;
 
     #[query]
-    fn account(method: MarketAuth, block: Option<u64>) -> StdResult<AccountInfo>;
+    fn account(
+        method: MarketAuth,
+        block: Option<u64>,
+        time: Option<u64>
+    ) -> StdResult<AccountInfo>;
 
     #[query]
     fn id(method: MarketAuth) -> StdResult<Binary>;
@@ -146,8 +256,20 @@ pub trait Market {
     #[query]
     fn borrowers(
         block: u64,
+        time: Option<u64>,
         pagination: Pagination
     ) -> StdResult<BorrowersResponse>;
+
+    /// Used by the overseer to enumerate the addresses of all borrowers.
+    #[query]
+    fn borrower_addresses(
+        key: MasterKey,
+        pagination: Pagination
+    ) -> StdResult<BorrowerAddressesResponse>;
+
+    /// Borrows, repays, liquidations and seizures that affected the account, oldest first.
+    #[query]
+    fn history(method: MarketAuth, pagination: Pagination) -> StdResult<HistoryResponse>;
 }
 
 pub type MarketAuth = AuthMethod<MarketPermissions>;
@@ -163,12 +285,16 @@ pub enum MarketPermissions {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct State {
-    /// Block number that the interest was last accrued at
+    /// Block number or time, depending on the `accrual_mode`, that the interest was last accrued at
     pub accrual_block: u64,
     /// Accumulator of the total earned interest rate since the opening of the market
     pub borrow_index: Decimal256,
-    /// Total amount of outstanding borrows of the underlying in this market
+    /// Total amount of outstanding variable rate borrows of the underlying in this market
     pub total_borrows: Uint256,
+    /// Total amount of outstanding stable rate borrows of the underlying in this market
+    pub total_stable_borrows: Uint256,
+    /// Average rate per block (or second) paid by all stable rate borrows
+    pub average_stable_rate: Decimal256,
     /// Total amount of reserves of the underlying held in this market
     pub total_reserves: Uint256,
     /// Total number of tokens in circulation
@@ -188,6 +314,22 @@ pub struct Config {
     pub reserve_factor: Decimal256,
     /// Share of seized collateral that is added to reserves
     pub seize_factor: Decimal256,
+    /// Utilization rate above which stable rate borrows can be rebalanced
+    /// up to the current stable rate. Stable rate borrowing is disabled if not set.
+    #[serde(default)]
+    pub stable_rate_threshold: Option<Decimal256>,
+    /// Fraction by which the current stable rate must be lower than a borrower's
+    /// rate before it can be rebalanced down. Rebalancing down is disabled if not set.
+    #[serde(default)]
+    pub stable_rate_rebalance_delta: Option<Decimal256>,
+    /// Fee charged on flash loans as a fraction of the borrowed amount.
+    /// Flash loans are disabled if not set.
+    #[serde(default)]
+    pub flash_loan_fee: Option<Decimal256>,
+    /// Whether interest accrues per block or per second. Can only be set when
+    /// instantiating the market and the interest model must use the same mode.
+    #[serde(default)]
+    pub accrual_mode: AccrualMode,
 }
 
 impl Config {
@@ -207,6 +349,14 @@ impl Config {
         }
     }
 
+    pub fn set_flash_loan_fee(&mut self, new: Decimal256) -> StdResult<()> {
+        Self::validate_flash_loan_fee(&Some(new))?;
+
+        self.flash_loan_fee = Some(new);
+
+        Ok(())
+    }
+
     fn validate_initial_exchange_rate(rate: &Decimal256) -> StdResult<()> {
         if *rate == Decimal256::zero() {
             return Err(StdError::generic_err("Initial exchange rate must be greater than 0"));
@@ -215,14 +365,160 @@ impl Config {
         }
     }
 
+    fn validate_stable_rate_threshold(threshold: &Option<Decimal256>) -> StdResult<()> {
+        match threshold {
+            Some(threshold) if *threshold > Decimal256::one() => Err(StdError::generic_err(
+                "Stable rate threshold must be lower than or equal to 1",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn validate_stable_rate_rebalance_delta(delta: &Option<Decimal256>) -> StdResult<()> {
+        match delta {
+            Some(delta) if *delta > Decimal256::one() => Err(StdError::generic_err(
+                "Stable rate rebalance delta must be lower than or equal to 1",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn validate_flash_loan_fee(fee: &Option<Decimal256>) -> StdResult<()> {
+        match fee {
+            Some(fee) if *fee > Decimal256::one() => Err(StdError::generic_err(
+                "Flash loan fee must be lower than or equal to 1",
+            )),
+            _ => Ok(()),
+        }
+    }
+
     pub fn validate(&self) -> StdResult<()> {
         Self::validate_initial_exchange_rate(&self.initial_exchange_rate)?;
         Self::validate_reserve_factor(&self.reserve_factor)?;
+        Self::validate_stable_rate_threshold(&self.stable_rate_threshold)?;
+        Self::validate_stable_rate_rebalance_delta(&self.stable_rate_rebalance_delta)?;
+        Self::validate_flash_loan_fee(&self.flash_loan_fee)?;
+
+        Ok(())
+    }
+}
+
+/// Determines how reserves above the `min_reserves` buffer are distributed when swept.
+/// Whatever isn't covered by the two shares stays in the reserves.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub struct ReservePolicy<A> {
+    pub treasury: A,
+    /// Fraction of the swept reserves that is sent to the `treasury`.
+    pub treasury_share: Decimal256,
+    /// The staking rewards pool that receives the bought back SIENNA.
+    pub rewards_pool: A,
+    /// Fraction of the swept reserves that is swapped into SIENNA.
+    pub buyback_share: Decimal256,
+    /// Route from the underlying asset of the market to SIENNA.
+    pub buyback_route: Vec<Hop>,
+    /// Amount of reserves that is never swept.
+    pub min_reserves: Uint256,
+    /// Account that can buy back SIENNA with the reserves besides the admin.
+    /// Since it sets the minimum output of the buyback, it must be trusted.
+    #[serde(default)]
+    pub keeper: Option<A>,
+}
+
+impl<A> ReservePolicy<A> {
+    pub fn validate(&self) -> StdResult<()> {
+        if (self.treasury_share + self.buyback_share)? > Decimal256::one() {
+            return Err(StdError::generic_err(
+                "Treasury and buyback shares cannot add up to more than 1."
+            ));
+        }
+
+        if !self.buyback_share.is_zero() && self.buyback_route.is_empty() {
+            return Err(StdError::generic_err("Buyback route cannot be empty."));
+        }
 
         Ok(())
     }
 }
 
+impl Canonize for ReservePolicy<HumanAddr> {
+    type Output = ReservePolicy<CanonicalAddr>;
+
+    fn canonize(self, api: &impl Api) -> StdResult<Self::Output> {
+        Ok(ReservePolicy {
+            treasury: self.treasury.canonize(api)?,
+            treasury_share: self.treasury_share,
+            rewards_pool: self.rewards_pool.canonize(api)?,
+            buyback_share: self.buyback_share,
+            buyback_route: self.buyback_route,
+            min_reserves: self.min_reserves,
+            keeper: self.keeper.map(|keeper| keeper.canonize(api)).transpose()?,
+        })
+    }
+}
+
+impl Humanize for ReservePolicy<CanonicalAddr> {
+    type Output = ReservePolicy<HumanAddr>;
+
+    fn humanize(self, api: &impl Api) -> StdResult<Self::Output> {
+        Ok(ReservePolicy {
+            treasury: self.treasury.humanize(api)?,
+            treasury_share: self.treasury_share,
+            rewards_pool: self.rewards_pool.humanize(api)?,
+            buyback_share: self.buyback_share,
+            buyback_route: self.buyback_route,
+            min_reserves: self.min_reserves,
+            keeper: self.keeper.map(|keeper| keeper.humanize(api)).transpose()?,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub struct SweepReservesResult {
+    /// Amount of the underlying sent to the treasury.
+    pub to_treasury: Uint256,
+    /// Amount of the underlying swapped into SIENNA.
+    pub bought_back: Uint256,
+    /// Reserves left in the market after the sweep.
+    pub remaining_reserves: Uint256,
+}
+
+/// Actions that are set to `true` cannot be performed in the market.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub struct PausedActions {
+    #[serde(default)]
+    pub deposit: bool,
+    #[serde(default)]
+    pub redeem: bool,
+    /// Applies to both variable and stable rate borrows.
+    #[serde(default)]
+    pub borrow: bool,
+    #[serde(default)]
+    pub repay: bool,
+    /// Applies to both liquidating borrowers and seizing collateral.
+    #[serde(default)]
+    pub liquidate: bool,
+    #[serde(default)]
+    pub transfer: bool,
+}
+
+impl PausedActions {
+    /// Pause any actions that are paused in `other` as well.
+    pub fn merge(&mut self, other: &Self) {
+        self.deposit |= other.deposit;
+        self.redeem |= other.redeem;
+        self.borrow |= other.borrow;
+        self.repay |= other.repay;
+        self.liquidate |= other.liquidate;
+        self.transfer |= other.transfer;
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct BorrowersResponse {
@@ -231,16 +527,104 @@ pub struct BorrowersResponse {
     pub total: u64
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct BorrowerAddressesResponse {
+    pub entries: Vec<HumanAddr>,
+    /// The total number of entries stored by the contract.
+    pub total: u64
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Borrower {
     pub id: Binary,
     pub principal_balance: Uint256,
     pub actual_balance: Uint256,
+    pub stable_balance: Uint256,
     pub liquidity: AccountLiquidity,
     pub markets: Vec<EnteredMarket<HumanAddr>>,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct HistoryResponse {
+    pub entries: Vec<HistoryEntry>,
+    /// The total number of entries stored for the account.
+    pub total: u64
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub struct HistoryEntry {
+    /// Block number at which the event occurred.
+    pub block: u64,
+    /// Block time at which the event occurred.
+    pub time: u64,
+    pub event: HistoryEvent,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub enum HistoryEvent {
+    Borrow {
+        amount: Uint256,
+        stable: bool,
+    },
+    Repay {
+        amount: Uint256,
+        stable: bool,
+    },
+    RebalanceStableRate {
+        /// The new rate per block.
+        rate: Decimal256,
+    },
+    /// Debt of the account was repaid by a liquidator.
+    Liquidated {
+        amount: Uint256,
+        /// The market from which collateral was seized.
+        collateral: HumanAddr,
+    },
+    /// slTokens of the account were seized as part of a liquidation.
+    Seized {
+        amount: Uint256,
+    },
+    /// The remaining debt of the account was written off because it had no collateral left.
+    BadDebt {
+        amount: Uint256,
+        /// The part of the `amount` that was covered by the reserves.
+        covered_by_reserves: Uint256,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub enum SwapOutputAction {
+    /// Repay the variable rate debt of the borrower. Any excess is sent back to them.
+    Repay {
+        borrower: HumanAddr,
+    },
+    /// Deposit on behalf of the owner.
+    Deposit {
+        owner: HumanAddr,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub struct BadDebt {
+    /// Total amount of debt written off.
+    pub total: Uint256,
+    /// The part of the `total` that was covered by the reserves.
+    pub covered_by_reserves: Uint256,
+    /// The part of the `total` that was absorbed by suppliers.
+    pub socialized: Uint256,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct BorrowerInfo {
@@ -250,6 +634,17 @@ pub struct BorrowerInfo {
     pub interest_index: Decimal256,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct StableBorrowInfo {
+    /// Total balance (with accrued interest), after applying the most recent balance-changing action
+    pub principal: Uint256,
+    /// The rate per block that was locked in at the most recent balance-changing action
+    pub rate: Decimal256,
+    /// Block number or time of the most recent balance-changing action
+    pub accrual_block: u64,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct SimulateLiquidationResult {
@@ -265,6 +660,7 @@ pub struct SimulateLiquidationResult {
 pub struct AccountInfo {
     pub sl_token_balance: Uint256,
     pub borrow_balance: Uint256,
+    pub stable_borrow_balance: Uint256,
     pub exchange_rate: Decimal256,
 }
 
@@ -278,6 +674,10 @@ pub enum ReceiverCallbackMsg {
         /// Repay someone else's debt.
         borrower: Option<Binary>,
     },
+    RepayStable {
+        /// Repay someone else's debt.
+        borrower: Option<Binary>,
+    },
     Liquidate {
         borrower: Binary,
         collateral: HumanAddr,
@@ -288,11 +688,72 @@ pub fn query_exchange_rate(
     querier: &impl Querier,
     market: ContractLink<HumanAddr>,
     block: Option<u64>,
+    time: Option<u64>,
 ) -> StdResult<Decimal256> {
     querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
         contract_addr: market.address,
         callback_code_hash: market.code_hash,
-        msg: to_binary(&QueryMsg::ExchangeRate { block })?,
+        msg: to_binary(&QueryMsg::ExchangeRate { block, time })?,
+    }))
+}
+
+pub fn query_state(
+    querier: &impl Querier,
+    market: ContractLink<HumanAddr>,
+    block: Option<u64>,
+    time: Option<u64>,
+) -> StdResult<State> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: market.address,
+        callback_code_hash: market.code_hash,
+        msg: to_binary(&QueryMsg::State { block, time })?,
+    }))
+}
+
+pub fn query_id(
+    querier: &impl Querier,
+    market: ContractLink<HumanAddr>,
+    method: MarketAuth,
+) -> StdResult<Binary> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: market.address,
+        callback_code_hash: market.code_hash,
+        msg: to_binary(&QueryMsg::Id { method })?,
+    }))
+}
+
+pub fn query_borrower_addresses(
+    querier: &impl Querier,
+    market: ContractLink<HumanAddr>,
+    key: MasterKey,
+    pagination: Pagination,
+) -> StdResult<BorrowerAddressesResponse> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: market.address,
+        callback_code_hash: market.code_hash,
+        msg: to_binary(&QueryMsg::BorrowerAddresses { key, pagination })?,
+    }))
+}
+
+pub fn query_simulate_liquidation(
+    querier: &impl Querier,
+    market: ContractLink<HumanAddr>,
+    block: u64,
+    time: Option<u64>,
+    borrower: Binary,
+    collateral: HumanAddr,
+    amount: Uint256,
+) -> StdResult<SimulateLiquidationResult> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: market.address,
+        callback_code_hash: market.code_hash,
+        msg: to_binary(&QueryMsg::SimulateLiquidation {
+            block,
+            time,
+            borrower,
+            collateral,
+            amount,
+        })?,
     }))
 }
 
@@ -301,11 +762,12 @@ pub fn query_account(
     market: ContractLink<HumanAddr>,
     method: MarketAuth,
     block: Option<u64>,
+    time: Option<u64>,
 ) -> StdResult<AccountInfo> {
     querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
         contract_addr: market.address,
         callback_code_hash: market.code_hash,
-        msg: to_binary(&QueryMsg::Account { method, block })?,
+        msg: to_binary(&QueryMsg::Account { method, block, time })?,
     }))
 }
 
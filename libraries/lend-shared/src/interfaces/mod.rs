@@ -2,3 +2,4 @@ pub mod interest_model;
 pub mod market;
 pub mod oracle;
 pub mod overseer;
+pub mod swap;
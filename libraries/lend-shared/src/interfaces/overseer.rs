@@ -1,8 +1,8 @@
 use fadroma::{
     admin, auth, cosmwasm_std,
     cosmwasm_std::{
-        to_binary, Api, Binary, CanonicalAddr, HandleResponse, HumanAddr, InitResponse, Querier,
-        QueryRequest, StdError, StdResult, WasmQuery,
+        to_binary, Api, Binary, CanonicalAddr, CosmosMsg, HandleResponse, HumanAddr, InitResponse,
+        Querier, QueryRequest, StdError, StdResult, WasmMsg, WasmQuery,
     },
     derive_contract::*,
     schemars, Canonize, ContractInstantiationInfo, ContractLink, Decimal256, Humanize, Uint256,
@@ -11,7 +11,10 @@ use fadroma::{
 use serde::{Deserialize, Serialize};
 
 use crate::core::{AuthMethod, MasterKey, Pagination};
-use crate::interfaces::market::Config as MarketConfig;
+use crate::interfaces::market::{
+    Config as MarketConfig, PausedActions, SimulateLiquidationResult,
+};
+use crate::interfaces::swap::Hop;
 
 #[interface(
     component(path = "admin"),
@@ -52,9 +55,131 @@ pub trait Overseer {
     fn change_market(
         market: HumanAddr,
         ltv_ratio:  Option<Decimal256>,
-        symbol: Option<String>
+        symbol: Option<String>,
+        /// Only applicable to markets listed in isolation mode.
+        debt_ceiling: Option<Uint256>,
+        borrowable_in_isolation: Option<bool>
+    ) -> StdResult<HandleResponse>;
+
+    /// Stop counting the market as collateral for new borrows and disallow
+    /// deposits and borrows in it. Repaying, redeeming and liquidating is still possible.
+    #[handle]
+    fn deprecate_market(market: HumanAddr) -> StdResult<HandleResponse>;
+
+    /// Remove a deprecated market that has no supply and no outstanding borrows.
+    #[handle]
+    fn delist_market(market: HumanAddr) -> StdResult<HandleResponse>;
+
+    /// Set the account that is allowed to pause actions in markets.
+    #[handle]
+    fn set_guardian(guardian: Option<HumanAddr>) -> StdResult<HandleResponse>;
+
+    /// Pause the given actions in the market. Can be called by the
+    /// guardian or the admin. Only the market admin can unpause.
+    #[handle]
+    fn pause_market(market: HumanAddr, actions: PausedActions) -> StdResult<HandleResponse>;
+
+    /// Set the SiennaSwap router that is used for swaps that go through more than one pair.
+    #[handle]
+    fn set_swap_router(router: ContractLink<HumanAddr>) -> StdResult<HandleResponse>;
+
+    /// Allow or disallow swapping through the given pairs in collateral operations.
+    #[handle]
+    fn set_swap_pairs(pairs: Vec<HumanAddr>, allowed: bool) -> StdResult<HandleResponse>;
+
+    /// Redeem `amount` of slTokens from the `collateral` market, swap the underlying along
+    /// `hops` and use the output to repay the variable rate debt in the `borrowed` market.
+    /// Every hop must go through a pair allowed with `set_swap_pairs`.
+    /// Fails if the swap returns less than `min_output` or if it would leave
+    /// the account below its LTV.
+    #[handle]
+    fn repay_with_collateral(
+        collateral: HumanAddr,
+        amount: Uint256,
+        borrowed: HumanAddr,
+        hops: Vec<Hop>,
+        min_output: Uint256
+    ) -> StdResult<HandleResponse>;
+
+    /// Redeem `amount` of slTokens from the `from` market, swap the underlying along
+    /// `hops` and deposit the output into the `to` market.
+    /// Every hop must go through a pair allowed with `set_swap_pairs`.
+    /// Fails if the swap returns less than `min_output` or if it would leave
+    /// the account below its LTV.
+    #[handle]
+    fn swap_collateral(
+        from: HumanAddr,
+        amount: Uint256,
+        to: HumanAddr,
+        hops: Vec<Hop>,
+        min_output: Uint256
+    ) -> StdResult<HandleResponse>;
+
+    /// Executed by the overseer itself as the last step of a collateral operation in order to
+    /// check that it didn't leave the account with less collateral than it requires.
+    #[handle]
+    fn finish_collateral_operation() -> StdResult<HandleResponse>;
+
+    /// Called by markets whenever a borrow or a repay occurs in order
+    /// to track the debt taken against isolated collateral.
+    #[handle]
+    fn update_isolated_debt(
+        borrower: HumanAddr,
+        amount: Uint256,
+        repay: bool
+    ) -> StdResult<HandleResponse>;
+
+    /// Create a new e-mode category. The id of the category is returned in the `data` field.
+    #[handle]
+    fn add_e_mode_category(
+        label: String,
+        ltv_ratio: Decimal256,
+        liquidation_threshold: Decimal256
+    ) -> StdResult<HandleResponse>;
+
+    #[handle]
+    fn update_e_mode_category(
+        id: u64,
+        ltv_ratio: Option<Decimal256>,
+        liquidation_threshold: Option<Decimal256>
+    ) -> StdResult<HandleResponse>;
+
+    /// Add the market to the given e-mode category or remove it from its current one.
+    #[handle]
+    fn set_market_e_mode_category(
+        market: HumanAddr,
+        category: Option<u64>
+    ) -> StdResult<HandleResponse>;
+
+    /// Opt into the given e-mode category or opt out by passing `None`.
+    #[handle]
+    fn set_e_mode(category: Option<u64>) -> StdResult<HandleResponse>;
+
+    /// Set the token that market rewards are paid out in.
+    /// The overseer must hold enough of it to cover the claims.
+    #[handle]
+    fn set_rewards_token(token: ContractLink<HumanAddr>) -> StdResult<HandleResponse>;
+
+    /// Set the amount of rewards distributed per block to the suppliers and to the
+    /// borrowers of the market. Positions that existed before the speeds were first set
+    /// earn from then on, based on the balances that the market holds for them.
+    #[handle]
+    fn set_reward_speeds(market: HumanAddr, speeds: RewardSpeeds) -> StdResult<HandleResponse>;
+
+    /// Called by markets whenever the slToken or borrow balance of an account changes in
+    /// order to distribute the market rewards. The `previous` balances were in effect
+    /// since the last update and the `current` ones apply from now on.
+    #[handle]
+    fn update_rewards(
+        account: HumanAddr,
+        previous: RewardBalances,
+        current: RewardBalances
     ) -> StdResult<HandleResponse>;
 
+    /// Claim the rewards accrued by supplying to and borrowing from the given markets.
+    #[handle]
+    fn claim_rewards(markets: Vec<HumanAddr>) -> StdResult<HandleResponse>;
+
     #[handle]
     fn change_config(
         premium_rate: Option<Decimal256>,
@@ -68,17 +193,54 @@ pub trait Overseer {
     #[query]
     fn market(address: HumanAddr) -> StdResult<Market<HumanAddr>>;
 
+    /// The total debt (in USD) taken against the given isolated market.
+    #[query]
+    fn isolated_debt(market: HumanAddr) -> StdResult<Uint256>;
+
+    #[query]
+    fn e_mode_categories(pagination: Pagination) -> StdResult<EModeCategoriesResponse>;
+
+    /// The e-mode category that the account has opted into, if any.
+    #[query]
+    fn e_mode(method: OverseerAuth) -> StdResult<Option<u64>>;
+
     #[query]
     fn entered_markets(method: OverseerAuth) -> StdResult<Vec<Market<HumanAddr>>>;
 
+    #[query]
+    fn guardian() -> StdResult<Option<HumanAddr>>;
+
+    #[query]
+    fn swap_router() -> StdResult<Option<ContractLink<HumanAddr>>>;
+
+    #[query]
+    fn swap_pair_allowed(pair: HumanAddr) -> StdResult<bool>;
+
     #[query]
     fn oracle_contract() -> StdResult<ContractLink<HumanAddr>>;
 
+    #[query]
+    fn rewards_token() -> StdResult<Option<ContractLink<HumanAddr>>>;
+
+    #[query]
+    fn reward_speeds(market: HumanAddr) -> StdResult<RewardSpeeds>;
+
+    /// The rewards that the account can claim from the given markets at the given block.
+    #[query]
+    fn unclaimed_rewards(
+        method: OverseerAuth,
+        markets: Vec<HumanAddr>,
+        block: u64
+    ) -> StdResult<Uint256>;
+
+    /// If both amounts are zero, the shortfall is whether the account can be liquidated.
+    /// Otherwise, it is whether the account can redeem or borrow the given amounts.
     #[query]
     fn account_liquidity(
         method: OverseerAuth,
         market: Option<HumanAddr>,
         block: Option<u64>,
+        time: Option<u64>,
         redeem_amount: Uint256,
         borrow_amount: Uint256,
     ) -> StdResult<AccountLiquidity>;
@@ -89,9 +251,22 @@ pub trait Overseer {
         address: HumanAddr,
         market: HumanAddr,
         block: u64,
+        time: Option<u64>,
         amount: Uint256,
     ) -> StdResult<bool>;
 
+    /// Scan the borrowers of all markets, starting from the given offset into their
+    /// combined list, and return those accounts that have a shortfall at the given block
+    /// (or time, for markets that accrue interest by time).
+    /// At most `pagination.limit` borrowers are scanned per call, continue from `next`.
+    /// Fails if `pagination.limit` is 0.
+    #[query]
+    fn liquidatable_accounts(
+        block: u64,
+        time: Option<u64>,
+        pagination: Pagination
+    ) -> StdResult<LiquidatableAccountsResponse>;
+
     #[query]
     fn seize_amount(
         borrowed: HumanAddr,
@@ -122,6 +297,43 @@ pub struct AccountLiquidity {
     pub shortfall: Uint256,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct LiquidatableAccountsResponse {
+    pub entries: Vec<LiquidatableAccount>,
+    /// The offset to continue scanning from. `None` if all borrowers were scanned.
+    pub next: Option<u64>
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub struct LiquidatableAccount {
+    /// The id of the account in the market of its largest debt position.
+    /// Used as the `borrower` when liquidating in that market.
+    pub id: Binary,
+    pub shortfall: Uint256,
+    pub largest_debt: Position,
+    /// `None` if the account has no collateral left.
+    pub largest_collateral: Option<Position>,
+    /// The maximum amount of the largest debt that can be repaid in a single liquidation.
+    pub max_repay: Uint256,
+    /// The outcome of repaying `max_repay` against the largest collateral.
+    /// `None` if there is no collateral or the simulation failed.
+    pub simulation: Option<SimulateLiquidationResult>
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub struct Position {
+    pub market: HumanAddr,
+    /// The borrow balance in the underlying asset or the slToken balance for collateral.
+    pub balance: Uint256,
+    /// The USD value of the position.
+    pub value: Uint256
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
 #[serde(deny_unknown_fields)]
@@ -134,6 +346,32 @@ pub struct Config {
     close_factor: Decimal256
 }
 
+/// Rewards distributed per block in a market. Each side is split
+/// between accounts in proportion to their balances.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, schemars::JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub struct RewardSpeeds {
+    /// Distributed to holders of slTokens.
+    pub supply: Uint256,
+    /// Distributed to borrowers, including stable rate ones.
+    pub borrow: Uint256,
+}
+
+/// The balances that the rewards of a market are distributed on.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, schemars::JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub struct RewardBalances {
+    /// The slToken balance of the account.
+    pub sl_token_balance: Uint256,
+    /// The borrow balance of the account, including stable rate borrows.
+    pub borrow_balance: Uint256,
+    pub total_supply: Uint256,
+    /// Including stable rate borrows.
+    pub total_borrows: Uint256,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct MarketsResponse {
@@ -152,6 +390,59 @@ pub struct Market<A> {
     pub decimals: u8,
     /// The percentage rate at which tokens can be borrowed given the size of the collateral.
     pub ltv_ratio: Decimal256,
+    /// If set, accounts using this market as collateral can only borrow from
+    /// markets that are borrowable in isolation and up to the debt ceiling.
+    #[serde(default)]
+    pub isolated: Option<Isolation>,
+    /// Whether accounts using isolated collateral can borrow from this market.
+    #[serde(default)]
+    pub borrowable_in_isolation: bool,
+    /// The e-mode category that the market belongs to, if any.
+    #[serde(default)]
+    pub e_mode_category: Option<u64>,
+    #[serde(default)]
+    pub status: MarketStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, schemars::JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum MarketStatus {
+    Active,
+    /// Only repaying, redeeming and liquidating is allowed.
+    Deprecated,
+    /// Removed from the list of markets.
+    Delisted,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, schemars::JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub struct Isolation {
+    /// The maximum total debt (in USD) that can be taken against the isolated collateral.
+    pub debt_ceiling: Uint256,
+}
+
+/// A category of correlated assets. If all of the account's collateral and
+/// debt is in the category that it has opted into, the category parameters
+/// are used instead of those of the individual markets.
+#[derive(Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub struct EModeCategory {
+    pub id: u64,
+    pub label: String,
+    /// The percentage rate at which tokens can be borrowed given the size of the collateral.
+    pub ltv_ratio: Decimal256,
+    /// The percentage of the collateral value at which the account becomes liquidatable.
+    pub liquidation_threshold: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct EModeCategoriesResponse {
+    pub entries: Vec<EModeCategory>,
+    /// The total number of entries stored by the contract.
+    pub total: u64
 }
 
 #[derive(Serialize, Deserialize, schemars::JsonSchema, Debug)]
@@ -169,7 +460,13 @@ pub struct MarketInitConfig {
     /// Symbol of the underlying asset. Must be the same as what the oracle expects.
     pub token_symbol: String,
     pub prng_seed: Binary,
-    pub entropy: Binary
+    pub entropy: Binary,
+    /// List the market in isolation mode. Used for riskier collateral.
+    #[serde(default)]
+    pub isolated: Option<Isolation>,
+    /// Whether accounts using isolated collateral can borrow from this market.
+    #[serde(default)]
+    pub borrowable_in_isolation: bool
 }
 
 impl Config {
@@ -237,6 +534,28 @@ impl<T> Market<T> {
     }
 }
 
+impl Default for MarketStatus {
+    fn default() -> Self {
+        Self::Active
+    }
+}
+
+impl EModeCategory {
+    pub fn validate(&self) -> StdResult<()> {
+        if self.ltv_ratio > self.liquidation_threshold {
+            return Err(StdError::generic_err(
+                "LTV ratio cannot be greater than the liquidation threshold."
+            ));
+        }
+
+        if self.liquidation_threshold > Decimal256::one() {
+            return Err(StdError::generic_err("Liquidation threshold must be between 0 and 1."));
+        }
+
+        Ok(())
+    }
+}
+
 impl Canonize for Market<HumanAddr> {
     type Output = Market<CanonicalAddr>;
 
@@ -246,6 +565,10 @@ impl Canonize for Market<HumanAddr> {
             contract: self.contract.canonize(api)?,
             ltv_ratio: self.ltv_ratio,
             decimals: self.decimals,
+            isolated: self.isolated,
+            borrowable_in_isolation: self.borrowable_in_isolation,
+            e_mode_category: self.e_mode_category,
+            status: self.status,
         })
     }
 }
@@ -259,6 +582,10 @@ impl Humanize for Market<CanonicalAddr> {
             contract: self.contract.humanize(api)?,
             ltv_ratio: self.ltv_ratio,
             decimals: self.decimals,
+            isolated: self.isolated,
+            borrowable_in_isolation: self.borrowable_in_isolation,
+            e_mode_category: self.e_mode_category,
+            status: self.status,
         })
     }
 }
@@ -270,6 +597,7 @@ pub fn query_account_liquidity(
     address: HumanAddr,
     market: Option<HumanAddr>,
     block: Option<u64>,
+    time: Option<u64>,
     redeem_amount: Uint256,
     borrow_amount: Uint256,
 ) -> StdResult<AccountLiquidity> {
@@ -280,12 +608,49 @@ pub fn query_account_liquidity(
             method: OverseerAuth::Internal { key, address },
             market,
             block,
+            time,
             redeem_amount,
             borrow_amount,
         })?,
     }))
 }
 
+pub fn update_isolated_debt_msg(
+    overseer: ContractLink<HumanAddr>,
+    borrower: HumanAddr,
+    amount: Uint256,
+    repay: bool,
+) -> StdResult<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: overseer.address,
+        callback_code_hash: overseer.code_hash,
+        msg: to_binary(&HandleMsg::UpdateIsolatedDebt {
+            borrower,
+            amount,
+            repay,
+        })?,
+        send: vec![],
+    }))
+}
+
+pub fn update_rewards_msg(
+    overseer: ContractLink<HumanAddr>,
+    account: HumanAddr,
+    previous: RewardBalances,
+    current: RewardBalances,
+) -> StdResult<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: overseer.address,
+        callback_code_hash: overseer.code_hash,
+        msg: to_binary(&HandleMsg::UpdateRewards {
+            account,
+            previous,
+            current,
+        })?,
+        send: vec![],
+    }))
+}
+
 pub fn query_can_transfer(
     querier: &impl Querier,
     overseer: ContractLink<HumanAddr>,
@@ -293,6 +658,7 @@ pub fn query_can_transfer(
     address: HumanAddr,
     market: HumanAddr,
     block: u64,
+    time: Option<u64>,
     amount: Uint256,
 ) -> StdResult<bool> {
     querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
@@ -303,6 +669,7 @@ pub fn query_can_transfer(
             address,
             market,
             block,
+            time,
             amount,
         })?,
     }))
@@ -320,6 +687,17 @@ pub fn query_market(
     }))
 }
 
+pub fn query_swap_router(
+    querier: &impl Querier,
+    overseer: ContractLink<HumanAddr>,
+) -> StdResult<Option<ContractLink<HumanAddr>>> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: overseer.address,
+        callback_code_hash: overseer.code_hash,
+        msg: to_binary(&QueryMsg::SwapRouter {})?,
+    }))
+}
+
 pub fn query_config(
     querier: &impl Querier,
     overseer: ContractLink<HumanAddr>,
@@ -0,0 +1,91 @@
+//! The subset of the SiennaSwap router and pair messages
+//! that is used by the overseer to swap collateral.
+
+use std::collections::VecDeque;
+
+use fadroma::{
+    schemars, schemars::JsonSchema, to_binary, Binary, ContractLink,
+    HumanAddr, StdError, StdResult, Uint128,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenType {
+    CustomToken {
+        contract_addr: HumanAddr,
+        token_code_hash: String,
+    },
+    NativeToken {
+        denom: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Hop {
+    pub from_token: TokenType,
+    pub pair_address: HumanAddr,
+    pub pair_code_hash: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Route {
+    pub hops: VecDeque<Hop>,
+    pub expected_return: Option<Uint128>,
+    pub to: HumanAddr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub enum Snip20Swap {
+    Swap {
+        expected_return: Option<Uint128>,
+        to: Option<HumanAddr>,
+    },
+}
+
+/// Returns the contract that the input token should be sent to and the
+/// message to send along with it in order to swap along the given `hops`
+/// with the output going to `to`. A single hop is swapped directly in the
+/// pair while longer routes go through the `router`. The swap fails if
+/// it returns less than the `expected_return`.
+pub fn swap_destination(
+    router: Option<ContractLink<HumanAddr>>,
+    mut hops: Vec<Hop>,
+    to: HumanAddr,
+    expected_return: Option<Uint128>,
+) -> StdResult<(ContractLink<HumanAddr>, Binary)> {
+    match hops.len() {
+        0 => Err(StdError::generic_err("Swap route cannot be empty.")),
+        1 => {
+            let hop = hops.pop().unwrap();
+
+            Ok((
+                ContractLink {
+                    address: hop.pair_address,
+                    code_hash: hop.pair_code_hash,
+                },
+                to_binary(&Snip20Swap::Swap {
+                    expected_return,
+                    to: Some(to),
+                })?,
+            ))
+        }
+        _ => {
+            let router = router
+                .ok_or_else(|| StdError::generic_err("Swap router is not configured."))?;
+
+            Ok((
+                router,
+                to_binary(&Route {
+                    hops: hops.into(),
+                    expected_return,
+                    to,
+                })?,
+            ))
+        }
+    }
+}
@@ -1,63 +1,216 @@
-//use serde::Deserialize;
-use sienna_schedule::{*, vesting::*, validate::*};
+use sienna_schedule::{*, validate::*};
+use fadroma::{HumanAddr, Uint128};
 
-use std::{path::Path, fs::read_to_string};
-use serde_json_wasm::from_str;
+use std::{path::Path, fs::read_to_string, collections::BTreeMap};
+use serde::Serialize;
+use serde_json_wasm::{from_str, to_string};
+use chrono::NaiveDate;
 
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches};
+
+const DEFAULT_PATH: &str = "../../settings/schedule.json";
+
+const DAY: Seconds = 86400;
 
 fn main () -> Result<(), std::io::Error> {
-    let mut app = App::new("sienna_schedule")
-        .version("1.0")
+    let path = || Arg::new("PATH").default_value(DEFAULT_PATH);
+    let at = || Arg::new("at").long("at").takes_value(true).required(true)
+        .about("YYYY-MM-DD (requires --launch), or seconds since launch");
+    let launch = || Arg::new("launch").long("launch").takes_value(true)
+        .about("YYYY-MM-DD of the launch");
+    let app = App::new("sienna_schedule")
+        .version("1.1")
         .author("Adam A. <adam@hack.bg>")
         .about("Converts a schedule from JSON to Markdown, materializing all portions")
-        .arg(Arg::new("PATH")
-            .default_value("../../settings/schedule.json"));
-
-    let matches = &app.clone().get_matches();
-    match matches.value_of("PATH") {
-        Some(path) => {
-            println!("\n# Schedule");
-            println!("\n(Generated from schedule.json)\n");
-            let schedule = get_schedule(path).unwrap();
-            println!("Internal representation:\n```\n{:#?}\n```", &schedule);
-            schedule.validate().unwrap();
-            for pool in schedule.pools.iter() {
-                println!("\n## Pool: *{}*", &pool.name);
-                for account in pool.accounts.iter() {
-                    println!("\n### Account: *{}*\n", &account.name);
-                    println!("* Amount: **{} attoSIENNA**", &account.amount);
-                    println!("* Cliff: **{} attoSIENNA**", &account.cliff);
-                    println!("* Portion size: **{} attoSIENNA**", &account.portion_size());
-                    println!("* Portion count: **{}**\n", &account.portion_count());
-                    let mut portion = if account.cliff > fadroma::Uint128::zero() { -1 } else { 0 };
-                    let mut balance = 0u128;
-                    println!("|portion #|day|unlocked amount (attoSIENNA)|");
-                    println!("|:-:|:-:|--:|");
-                    for t in account.start_at..account.end()+1 {
-                        let new_balance = account.unlocked(t, &account.address);
-                        if new_balance != balance {
-                            portion += 1;
-                            balance = new_balance;
-                            println!("|{:>7}|{:>7}|{:>26}|",
-                                if portion == 0 { "cliff".to_string() } else { portion.to_string() },
-                                t / 86400,
-                                balance
-                            );
-                        }
-                    }
-                }
+        .arg(path())
+        .subcommand(App::new("markdown")
+            .about("Converts a schedule from JSON to Markdown (same as without a subcommand)")
+            .arg(path()))
+        .subcommand(App::new("simulate")
+            .about("Shows how much each account has unlocked at a point in time")
+            .arg(at())
+            .arg(launch())
+            .arg(path()))
+        .subcommand(App::new("diff")
+            .about("Shows how a schedule change affects each account at a point in time")
+            .arg(at())
+            .arg(launch())
+            .arg(Arg::new("OLD").required(true))
+            .arg(Arg::new("NEW").required(true)))
+        .subcommand(App::new("export")
+            .about("Exports the full unlock timeline")
+            .arg(Arg::new("format").long("format").takes_value(true)
+                .possible_values(&["csv", "json"]).default_value("csv"))
+            .arg(path()));
+
+    let matches = &app.get_matches();
+    match matches.subcommand() {
+        Some(("markdown", args)) => markdown(&load(args, "PATH")),
+        Some(("simulate", args)) => simulate(&load(args, "PATH"), elapsed(args)),
+        Some(("diff",     args)) => diff(&load(args, "OLD"), &load(args, "NEW"), elapsed(args)),
+        Some(("export",   args)) => export(&load(args, "PATH"), args.value_of("format").unwrap()),
+        _ => markdown(&load(matches, "PATH"))
+    };
+
+    Ok(())
+}
+
+fn markdown (schedule: &Schedule<HumanAddr>) {
+    println!("\n# Schedule");
+    println!("\n(Generated from schedule.json)\n");
+    println!("Internal representation:\n```\n{:#?}\n```", &schedule);
+    for pool in schedule.pools.iter() {
+        println!("\n## Pool: *{}*", &pool.name);
+        for account in pool.accounts.iter() {
+            println!("\n### Account: *{}*\n", &account.name);
+            println!("* Amount: **{} attoSIENNA**", &account.amount);
+            println!("* Cliff: **{} attoSIENNA**", &account.cliff);
+            println!("* Portion size: **{} attoSIENNA**", &account.portion_size());
+            println!("* Portion count: **{}**\n", &account.portion_count());
+            println!("|portion #|day|unlocked amount (attoSIENNA)|");
+            println!("|:-:|:-:|--:|");
+            let offset = if account.cliff > Uint128::zero() { 0 } else { 1 };
+            for (i, (t, unlocked)) in account.timeline().iter().enumerate() {
+                println!("|{:>7}|{:>7}|{:>26}|",
+                    if i + offset == 0 { "cliff".to_string() } else { (i + offset).to_string() },
+                    t / DAY,
+                    unlocked
+                );
             }
-        },
-        None => {
-            app.print_long_help()?;
         }
+    }
+}
+
+fn simulate (schedule: &Schedule<HumanAddr>, elapsed: Seconds) {
+    println!("|pool|account|address|unlocked (attoSIENNA)|of (attoSIENNA)|");
+    println!("|:--|:--|:--|--:|--:|");
+    let mut total = 0u128;
+    for pool in schedule.pools.iter() {
+        for account in pool.accounts.iter() {
+            let unlocked = account.vested(elapsed);
+            total += unlocked;
+            println!("|{}|{}|{}|{}|{}|",
+                &pool.name, &account.name, &account.address, unlocked, account.allocated());
+        }
+    }
+    println!("|**total**|||**{}**|**{}**|", total, &schedule.total);
+}
+
+fn diff (old: &Schedule<HumanAddr>, new: &Schedule<HumanAddr>, elapsed: Seconds) {
+    let index = |schedule: &Schedule<HumanAddr>| {
+        let mut accounts = BTreeMap::new();
+        for pool in schedule.pools.iter() {
+            for account in pool.accounts.iter() {
+                accounts.insert((pool.name.clone(), account.name.clone()), account.clone());
+            }
+        }
+        accounts
     };
+    let (old_accounts, new_accounts) = (index(old), index(new));
+    let vested = |account: Option<&Account<HumanAddr>>|
+        account.map(|account| account.vested(elapsed).to_string()).unwrap_or_default();
+    let end = |account: Option<&Account<HumanAddr>>|
+        account.map(|account| account.end().to_string()).unwrap_or_default();
+    println!("|pool|account|field|old|new|");
+    println!("|:--|:--|:--|--:|--:|");
+    if old.total != new.total {
+        println!("|||total|{}|{}|", &old.total, &new.total);
+    }
+    let mut keys: Vec<_> = old_accounts.keys().chain(new_accounts.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    for key in keys {
+        let (pool, name) = key;
+        match (old_accounts.get(key), new_accounts.get(key)) {
+            (Some(a), None) => println!("|{}|{}|removed|{}||", pool, name, &a.amount),
+            (None, Some(b)) => println!("|{}|{}|added||{}|", pool, name, &b.amount),
+            (Some(a), Some(b)) => for (field, before, after) in [
+                ("address",  a.address.to_string(),        b.address.to_string()),
+                ("amount",   a.amount.to_string(),         b.amount.to_string()),
+                ("cliff",    a.cliff.to_string(),          b.cliff.to_string()),
+                ("start_at", a.start_at.to_string(),       b.start_at.to_string()),
+                ("interval", a.interval.to_string(),       b.interval.to_string()),
+                ("duration", a.duration.to_string(),       b.duration.to_string()),
+                ("curve",    format!("{:?}", a.curve),     format!("{:?}", b.curve)),
+                ("revoked",  format!("{:?}", a.revoked_at), format!("{:?}", b.revoked_at)),
+            ].iter() {
+                if before != after {
+                    println!("|{}|{}|{}|{}|{}|", pool, name, field, before, after);
+                }
+            },
+            (None, None) => unreachable!()
+        }
+        // Shown even if unchanged, so every account can be checked under both schedules
+        let (a, b) = (old_accounts.get(key), new_accounts.get(key));
+        println!("|{}|{}|vested|{}|{}|", pool, name, vested(a), vested(b));
+        println!("|{}|{}|end|{}|{}|", pool, name, end(a), end(b));
+    }
+}
 
-    Ok(())
+#[derive(Serialize)]
+struct Unlock<'a> {
+    pool:     &'a str,
+    account:  &'a str,
+    address:  &'a HumanAddr,
+    /// Seconds since launch
+    time:     Seconds,
+    day:      Seconds,
+    /// Released at this moment
+    amount:   Uint128,
+    /// Released by this account up to and including this moment
+    unlocked: Uint128,
+}
+
+fn export (schedule: &Schedule<HumanAddr>, format: &str) {
+    let mut unlocks = vec![];
+    for pool in schedule.pools.iter() {
+        for account in pool.accounts.iter() {
+            let mut last = 0;
+            for (time, unlocked) in account.timeline() {
+                unlocks.push(Unlock {
+                    pool: &pool.name, account: &account.name, address: &account.address,
+                    time, day: time / DAY,
+                    amount: Uint128(unlocked - last), unlocked: Uint128(unlocked),
+                });
+                last = unlocked;
+            }
+        }
+    }
+    unlocks.sort_by_key(|unlock| unlock.time);
+    match format {
+        "json" => println!("{}", to_string(&unlocks).unwrap()),
+        _ => {
+            println!("pool,account,address,time,day,amount,unlocked");
+            for u in unlocks.iter() {
+                println!("{},{},{},{},{},{},{}",
+                    u.pool, u.account, u.address, u.time, u.day, u.amount, u.unlocked);
+            }
+        }
+    }
+}
+
+/// Seconds between the launch and `--at`
+fn elapsed (args: &ArgMatches) -> Seconds {
+    let at = args.value_of("at").unwrap();
+    if let Ok(seconds) = at.parse::<Seconds>() {
+        return seconds
+    }
+    let date = |value: &str| NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .unwrap_or_else(|e| panic!("invalid date {}: {}", value, e))
+        .and_hms(0, 0, 0)
+        .timestamp();
+    let launch = date(args.value_of("launch").expect("--launch is required when --at is a date"));
+    let at = date(at);
+    if at > launch { (at - launch) as Seconds } else { 0 }
+}
 
+fn load (args: &ArgMatches, name: &str) -> Schedule<HumanAddr> {
+    let path = args.value_of(name).unwrap();
+    let schedule = get_schedule(path).unwrap();
+    schedule.validate().unwrap();
+    schedule
 }
 
-fn get_schedule (path: &str) -> Result<Schedule<fadroma::HumanAddr>, serde_json_wasm::de::Error> {
+fn get_schedule (path: &str) -> Result<Schedule<HumanAddr>, serde_json_wasm::de::Error> {
     from_str(&read_to_string(Path::new(path)).unwrap())
 }
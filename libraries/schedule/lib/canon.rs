@@ -38,6 +38,9 @@ impl Humanize for Account<CanonicalAddr> {
             start_at: self.start_at,
             interval: self.interval,
             duration: self.duration,
+            revocable: self.revocable,
+            revoked_at: self.revoked_at,
+            curve: self.curve,
             address: self.address.humanize(api)?
         })
     }
@@ -78,6 +81,9 @@ impl Canonize for Account<HumanAddr> {
             start_at: self.start_at,
             interval: self.interval,
             duration: self.duration,
+            revocable: self.revocable,
+            revoked_at: self.revoked_at,
+            curve: self.curve,
             address: self.address.canonize(api)?
         })
     }
@@ -55,6 +55,10 @@ define_errors!(
             account.amount.u128(),
             self.unallocated()
         }
+        err_account_not_found (&self, name: &str) {
+            "pool {}: account {} not found",
+            &self.name, &name
+        }
     }
     Account<A:Clone> {
         err_empty (&self,) {
@@ -71,5 +75,21 @@ define_errors!(
             "account {}: cliff + portions + remainder don't add up to amount",
             &self.name
         }
+        err_invalid_exponential (&self,) {
+            "account {}: exponential curve needs exponent > 0 and interval > 0",
+            &self.name
+        }
+        err_invalid_milestones (&self,) {
+            "account {}: milestones must be ordered, start at or above the cliff, and end with the full amount at the end of the duration",
+            &self.name
+        }
+        err_not_revocable (&self,) {
+            "account {}: can't be revoked",
+            &self.name
+        }
+        err_already_revoked (&self,) {
+            "account {}: already revoked",
+            &self.name
+        }
     }
 );
@@ -9,6 +9,10 @@
 ///   * If `partial` is true, `Account`s can be at runtime, up to the total.
 ///   * Otherwise, requires `Account`s to add up to exactly the total in order to pass validation.
 /// * `Account`: subdivision of a `Pool` (corresponds to `Channel`+`Periodic` from v1)
+///   * If `revocable` is true, vesting can be stopped at runtime. The unvested
+///     remainder is then no longer counted towards the pool's allocated funds.
+///   * `curve` determines how the amount after the cliff is released:
+///     in equal portions (default), continuously, back-loaded, or at explicit milestones.
 use fadroma::{
     schemars,
     schemars::JsonSchema,
@@ -78,7 +82,7 @@ impl<A:Clone> Pool<A> {
     }
     /// Sum of all contained accounts - expected to equal total
     pub fn subtotal (&self) -> u128 {
-        self.accounts.iter().fold(0, |total, acc| total + acc.amount.u128())
+        self.accounts.iter().fold(0, |total, acc| total + acc.allocated())
     }
     /// Remaining unallocated funds
     pub fn unallocated (&self) -> u128 {
@@ -108,6 +112,15 @@ pub struct Account<A> {
     pub interval: Seconds,
     /// If `> 0`, vesting stops after this much seconds regardless of how much is left of `total`.
     pub duration: Seconds,
+    /// If `true`, vesting can be stopped by revoking the account
+    #[serde(default)]
+    pub revocable: bool,
+    /// How many seconds after contract launch the account was revoked, freezing the unlocked amount
+    #[serde(default)]
+    pub revoked_at: Option<Seconds>,
+    /// How the amount after the cliff is released between `start_at` and `end()`
+    #[serde(default)]
+    pub curve:    Curve,
 }
 impl<A:Clone> Account<A> {
     pub fn immediate (name: &str, address: &A, amount: u128) -> Self {
@@ -118,7 +131,10 @@ impl<A:Clone> Account<A> {
             cliff:    0u128.into(),
             start_at: 0,
             interval: 0,
-            duration: 0
+            duration: 0,
+            revocable: false,
+            revoked_at: None,
+            curve:    Curve::Periodic
         }
     }
     pub fn periodic (
@@ -132,7 +148,51 @@ impl<A:Clone> Account<A> {
             cliff:   cliff.into(),
             start_at,
             interval,
-            duration
+            duration,
+            revocable: false,
+            revoked_at: None,
+            curve:    Curve::Periodic
         }
     }
+    pub fn revocable (mut self) -> Self {
+        self.revocable = true;
+        self
+    }
+    pub fn with_curve (mut self, curve: Curve) -> Self {
+        self.curve = curve;
+        self
+    }
+}
+
+/// Shape of the vesting after the cliff.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Curve {
+    /// Equal portions every `interval` until `duration`, plus the remainder at the end.
+    Periodic,
+    /// Continuous vesting: a proportional part of the amount unlocks every second.
+    Linear,
+    /// Back-loaded vesting every `interval`: after `n` of `N` portions,
+    /// `(n/N)^exponent` of the amount after the cliff is unlocked.
+    Exponential { exponent: u8 },
+    /// Explicit unlocks at given times. Amounts are cumulative and include the cliff.
+    Milestones(Vec<Milestone>),
+}
+impl Default for Curve {
+    fn default () -> Self { Curve::Periodic }
+}
+
+/// Point of a `Curve::Milestones` vesting.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Milestone {
+    /// How many seconds after `start_at` this milestone is reached
+    pub at:     Seconds,
+    /// Total amount unlocked once this milestone is reached
+    pub amount: Uint128,
+}
+impl Milestone {
+    pub fn new (at: Seconds, amount: u128) -> Self {
+        Self { at, amount: amount.into() }
+    }
 }
@@ -11,8 +11,48 @@ impl<A: Clone> Schedule<A> {
         }
         self.err_pool_not_found(pool_name)
     }
+    /// Stop the vesting of a revocable account at `elapsed` and return the unvested remainder.
+    /// If `release` is `true`, the remainder is removed from the pool and schedule totals.
+    /// Otherwise it is returned to the pool's unallocated funds.
+    pub fn revoke_account (
+        &mut self, pool_name: &str, account_name: &str, elapsed: Seconds, release: bool
+    ) -> StdResult<u128> {
+        for pool in self.pools.iter_mut() {
+            if pool.name == pool_name {
+                let remainder = pool.revoke_account(account_name, elapsed, release)?;
+                if release {
+                    self.total = (self.total - Uint128(remainder))?;
+                }
+                return Ok(remainder)
+            }
+        }
+        self.err_pool_not_found(pool_name)
+    }
 }
 impl<A: Clone> Pool<A> {
+    pub fn revoke_account (
+        &mut self, account_name: &str, elapsed: Seconds, release: bool
+    ) -> StdResult<u128> {
+        let account = match self.accounts.iter_mut().find(|a| a.name == account_name) {
+            Some(account) => account,
+            None => return self.err_account_not_found(account_name)
+        };
+        if !account.revocable {
+            return account.err_not_revocable()
+        }
+        if account.revoked_at.is_some() {
+            return account.err_already_revoked()
+        }
+        account.revoked_at = Some(elapsed);
+        let remainder = account.amount.u128() - account.allocated();
+        if release {
+            self.total = (self.total - Uint128(remainder))?;
+        } else if remainder > 0 {
+            self.partial = true
+        }
+        self.validate()?;
+        Ok(remainder)
+    }
     pub fn add_account (&mut self, account: Account<A>) -> UsuallyOk {
         if !self.partial {
             return self.err_pool_full()
@@ -33,7 +73,7 @@ impl<A: Clone> Pool<A> {
 mod tests {
     #![allow(non_snake_case)]
     use fadroma::cosmwasm_std::HumanAddr;
-    use crate::{Schedule, Pool, Account};
+    use crate::{Schedule, Pool, Account, validate::Validation, vesting::Vesting};
     #[test] fn test_add_to_full () {
         let Alice = HumanAddr::from("Alice");
         let Bob   = HumanAddr::from("Bob");
@@ -57,6 +97,44 @@ mod tests {
         assert_eq!(P.add_account(Account::immediate("C", &Carol, 1)),
                    P.err_pool_full());
     }
+    #[test] fn test_revoke () {
+        let Alice = HumanAddr::from("Alice");
+        let Bob   = HumanAddr::from("Bob");
+        let A = Account::periodic("A", &Alice, 100, 0, 0, 10, 100).revocable();
+        let B = Account::periodic("B", &Bob, 100, 0, 0, 10, 100);
+        let mut S = Schedule::new(&[Pool::full("P", &[A.clone(), B.clone()])]);
+        assert_eq!(S.revoke_account("P", "B", 50, false),
+                   B.err_not_revocable());
+        assert_eq!(S.revoke_account("P", "C", 50, false),
+                   S.pools.get(0).unwrap().err_account_not_found("C"));
+        assert_eq!(S.revoke_account("Q", "A", 50, false),
+                   S.err_pool_not_found("Q"));
+        assert_eq!(S.revoke_account("P", "A", 55, false),
+                   Ok(40));
+        assert_eq!(S.unlocked(55, &Alice),
+                   60);
+        assert_eq!(S.unlocked(1000, &Alice),
+                   60);
+        assert_eq!(S.revoke_account("P", "A", 60, false),
+                   A.err_already_revoked());
+        let P = S.pools.get(0).unwrap();
+        assert_eq!(P.partial, true);
+        assert_eq!(P.unallocated(), 40);
+        assert_eq!(S.total.u128(), 200);
+        assert_eq!(S.validate(), Ok(()));
+    }
+    #[test] fn test_revoke_and_release () {
+        let Alice = HumanAddr::from("Alice");
+        let A = Account::periodic("A", &Alice, 100, 0, 0, 10, 100).revocable();
+        let mut S = Schedule::new(&[Pool::full("P", &[A])]);
+        assert_eq!(S.revoke_account("P", "A", 35, true),
+                   Ok(60));
+        let P = S.pools.get(0).unwrap();
+        assert_eq!(P.partial, false);
+        assert_eq!(P.total.u128(), 40);
+        assert_eq!(S.total.u128(), 40);
+        assert_eq!(S.validate(), Ok(()));
+    }
     #[test] fn test_add_to_schedule () {
         let Alice = HumanAddr::from("Alice");
         let Bob   = HumanAddr::from("Bob");
@@ -55,9 +55,32 @@ impl<A:Clone> Validation for Account<A> {
         if self.cliff > self.amount {
             return self.err_cliff_too_big()
         }
+        if self.revoked_at.is_some() && !self.revocable {
+            return self.err_not_revocable()
+        }
+        match &self.curve {
+            Curve::Periodic | Curve::Linear => {},
+            Curve::Exponential { exponent } => if *exponent == 0 || self.interval == 0 {
+                return self.err_invalid_exponential()
+            },
+            Curve::Milestones(milestones) => {
+                let ordered = milestones.windows(2).all(|pair|
+                    pair[0].at < pair[1].at && pair[0].amount <= pair[1].amount);
+                let valid = ordered && match (milestones.first(), milestones.last()) {
+                    (Some(first), Some(last)) =>
+                        first.amount >= self.cliff &&
+                        last.amount == self.amount &&
+                        last.at == self.duration,
+                    _ => false
+                };
+                if !valid {
+                    return self.err_invalid_milestones()
+                }
+            }
+        }
         if self.amount.u128() != (
             self.cliff.u128() +
-            self.portion_size() * self.portion_count() as u128 +
+            self.cumulative(self.portion_count()) +
             self.remainder()
         ) {
             return self.err_does_not_add_up()
@@ -70,7 +93,7 @@ impl<A:Clone> Validation for Account<A> {
 mod tests {
     #![allow(non_snake_case)]
     use fadroma::cosmwasm_std::HumanAddr;
-    use crate::{Schedule, Pool, Account, validate::Validation};
+    use crate::{Schedule, Pool, Account, Curve, Milestone, validate::Validation};
     #[test] fn test_amount_eq_zero () {
         let A = Account::periodic("A", &HumanAddr::from(""), 0, 0, 0, 0, 0);
         assert_eq!(A.validate(),
@@ -93,6 +116,43 @@ mod tests {
         assert_eq!(Schedule::new(&[Pool::partial("P", 1, &[A.clone()])]).validate(),
                    A.err_cliff_too_big());
     }
+    #[test] fn test_invalid_curves () {
+        let A = Account::periodic("A", &HumanAddr::from(""), 100, 0, 0, 0, 100)
+            .with_curve(Curve::Exponential { exponent: 2 });
+        assert_eq!(A.validate(),
+                   A.err_invalid_exponential());
+        let A = Account::periodic("A", &HumanAddr::from(""), 100, 10, 0, 0, 100)
+            .with_curve(Curve::Milestones(vec![]));
+        assert_eq!(A.validate(),
+                   A.err_invalid_milestones());
+        for milestones in &[
+            vec![Milestone::new(0, 5),  Milestone::new(100, 100)], // first < cliff
+            vec![Milestone::new(50, 50), Milestone::new(50, 100)], // time not increasing
+            vec![Milestone::new(10, 60), Milestone::new(50, 50), Milestone::new(100, 100)], // amount decreasing
+            vec![Milestone::new(10, 10), Milestone::new(100, 90)], // doesn't end at amount
+            vec![Milestone::new(10, 10), Milestone::new(90, 100)], // doesn't end at duration
+        ] {
+            let A = A.clone().with_curve(Curve::Milestones(milestones.clone()));
+            assert_eq!(A.validate(),
+                       A.err_invalid_milestones());
+        }
+        let A = A.with_curve(Curve::Milestones(vec![Milestone::new(0, 10), Milestone::new(100, 100)]));
+        assert_eq!(A.validate(),
+                   Ok(()));
+    }
+    #[test] fn test_uneven_curves () {
+        let A = Account::periodic("A", &HumanAddr::from(""), 100, 0, 0, 0, 30)
+            .with_curve(Curve::Linear);
+        assert_eq!(A.validate(),
+                   Ok(()));
+        let A = Account::periodic("A", &HumanAddr::from(""), 100, 1, 0, 7, 30)
+            .with_curve(Curve::Exponential { exponent: 3 });
+        assert_eq!(A.validate(),
+                   Ok(()));
+        let A = Account::periodic("A", &HumanAddr::from(""), 100, 1, 0, 7, 30);
+        assert_eq!(A.validate(),
+                   Ok(()));
+    }
     #[test] fn test_account_gt_pool () {
         let A = Account::periodic("A", &HumanAddr::from(""), 2, 0, 0, 0, 0);
         let P = Pool{
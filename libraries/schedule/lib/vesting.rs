@@ -29,23 +29,59 @@ impl<A:Clone+PartialEq> Vesting<A> for Account<A> {
     fn unlocked (&self, elapsed: Seconds, address: &A) -> u128 {
         if *address != self.address { // if asking about someone else
             0
-        } else if elapsed < self.start_at { // if asking about a moment before the start
+        } else {
+            self.vested(elapsed)
+        }
+    }
+}
+impl<A> Account<A> {
+    /// Amount vested at a point in time, which stops increasing once the account is revoked
+    pub fn vested (&self, elapsed: Seconds) -> u128 {
+        let elapsed = match self.revoked_at {
+            Some(revoked_at) => u64::min(elapsed, revoked_at),
+            None => elapsed
+        };
+        if elapsed < self.start_at { // if asking about a moment before the start
             0
         } else if elapsed >= self.end() { // at the end the full amount must've been vested
             self.amount.u128()
         } else {
             u128::min(
                 match self.most_recent_portion(elapsed) {
-                    Some(n) => self.cliff.u128() + (n as u128) * self.portion_size(),
+                    Some(n) => self.cliff.u128() + self.cumulative(n),
                     None => 0
                 },
                 self.amount.u128()
             )
         }
     }
-}
-impl<A> Account<A> {
-    /// Size of regular (non-cliff) portions.
+    /// Amount unlocked after the cliff once `n` portions have vested
+    /// (not counting the remainder of a periodic curve).
+    pub fn cumulative (&self, n: u64) -> u128 {
+        let count = self.portion_count() as u128;
+        let n = u128::min(n as u128, count);
+        match &self.curve {
+            Curve::Periodic => n * self.portion_size(),
+            Curve::Linear => if count > 0 { self.amount_after_cliff() * n / count } else { 0 },
+            Curve::Exponential { exponent } => if count > 0 {
+                (0..*exponent).fold(self.amount_after_cliff(), |amount, _| amount * n / count)
+            } else {
+                0
+            },
+            Curve::Milestones(milestones) => match n {
+                0 => 0,
+                n => milestones[n as usize - 1].amount.u128().saturating_sub(self.cliff.u128())
+            }
+        }
+    }
+    /// Funds that this account will release in total
+    pub fn allocated (&self) -> u128 {
+        match self.revoked_at {
+            Some(revoked_at) => self.vested(revoked_at),
+            None => self.amount.u128()
+        }
+    }
+    /// Size of regular (non-cliff) portions of a periodic curve.
     pub fn portion_size (&self) -> u128 {
         if self.portion_count() > 0 {
             self.amount_after_cliff() / self.portion_count() as u128
@@ -58,18 +94,22 @@ impl<A> Account<A> {
         assert!(self.amount >= self.cliff);
         self.amount.u128() - self.cliff.u128()
     }
-    /// Number of non-cliff portions.
+    /// Number of non-cliff portions. With a linear curve, every second is a portion.
     pub fn portion_count (&self) -> u64 {
-        if self.interval > 0 {
-            (self.duration / self.interval) as u64
-        } else {
-            0
+        match &self.curve {
+            Curve::Linear => self.duration,
+            Curve::Milestones(milestones) => milestones.len() as u64,
+            _ => if self.interval > 0 {
+                (self.duration / self.interval) as u64
+            } else {
+                0
+            }
         }
     }
     /// If `(amount-cliff)` doesn't divide evenly by `portion_size`,
     /// the remainder is added to the last portion.
     pub fn remainder (&self) -> u128 {
-        self.amount_after_cliff() - self.portion_size() * self.portion_count() as u128
+        self.amount_after_cliff() - self.cumulative(self.portion_count())
     }
     /// Timestamp of last vesting (when remainder is received)
     pub fn end (&self) -> Seconds {
@@ -86,14 +126,50 @@ impl<A> Account<A> {
     /// Most recent portion vested at time `t`
     pub fn most_recent_portion (&self, t: Seconds) -> Option<u64> {
         self.elapsed(t).map(|elapsed| u64::min(
-            elapsed / self.interval + match self.cliff.u128() { 0 => 1, _ => 0 },
+            match &self.curve {
+                Curve::Linear => elapsed,
+                Curve::Milestones(milestones) =>
+                    milestones.iter().filter(|m| m.at <= elapsed).count() as u64,
+                _ => elapsed / self.interval + match self.cliff.u128() { 0 => 1, _ => 0 },
+            },
             self.portion_count()
         ))
     }
+    /// Moments at which the unlocked amount changes, with the total unlocked at each of them.
+    /// Linear curves are sampled every `interval`, or every day if `interval` is 0.
+    pub fn timeline (&self) -> Vec<(Seconds, u128)> {
+        let mut moments = vec![self.start_at];
+        match &self.curve {
+            Curve::Milestones(milestones) =>
+                moments.extend(milestones.iter().map(|m| self.start_at + m.at)),
+            Curve::Linear => {
+                let step = if self.interval > 0 { self.interval } else { 86400 };
+                moments.extend((1..=self.duration / step).map(|k| self.start_at + k * step))
+            },
+            _ => if self.interval > 0 {
+                moments.extend((1..=self.portion_count()).map(|n| self.start_at + n * self.interval))
+            }
+        }
+        moments.push(self.end());
+        moments.sort();
+        moments.dedup();
+        let mut timeline: Vec<(Seconds, u128)> = vec![];
+        for t in moments {
+            let unlocked = self.vested(t);
+            if unlocked > 0 && timeline.last().map_or(true, |&(_, last)| last != unlocked) {
+                timeline.push((t, unlocked))
+            }
+        }
+        timeline
+    }
     /// Whether a portion is unlocked at the exact moment specified
     pub fn vests_at (&self, t: Seconds) -> bool {
         match self.elapsed(t) {
-            Some(elapsed) => elapsed % self.interval == 0,
+            Some(elapsed) => match &self.curve {
+                Curve::Linear => true,
+                Curve::Milestones(milestones) => milestones.iter().any(|m| m.at == elapsed),
+                _ => elapsed % self.interval == 0
+            },
             None => false
         }
     }
@@ -103,7 +179,7 @@ impl<A> Account<A> {
 mod tests {
     #![allow(non_snake_case)]
     use fadroma::cosmwasm_std::HumanAddr;
-    use crate::{Schedule, Pool, Account, vesting::Vesting};
+    use crate::{Schedule, Pool, Account, Curve, Milestone, vesting::Vesting};
     #[test] fn blank () {
         // some imaginary people:
         let Alice = HumanAddr::from("Alice");
@@ -288,4 +364,57 @@ mod tests {
         assert_eq!(a, A.amount.u128());
         assert_eq!(b, 0);
     }
+    #[test] fn timeline () {
+        let Alice = HumanAddr::from("Alice");
+        let A = Account::periodic("", &Alice, 100, 42, 7, 12, 70);
+        assert_eq!(A.timeline(), vec![(7, 42), (19, 53), (31, 64), (43, 75), (55, 86), (67, 97), (77, 100)]);
+        let A = Account::periodic("", &Alice, 92, 0, 20, 11, 90);
+        let timeline = A.timeline();
+        assert_eq!(timeline.first(), Some(&(20, 11)));
+        assert_eq!(timeline.last(),  Some(&(110, 92)));
+        // every change of the brute-forced unlocked amount is in the timeline
+        let mut last = 0;
+        for t in 0..200 {
+            let unlocked = A.vested(t);
+            if unlocked != last {
+                assert!(timeline.contains(&(t, unlocked)), "t={}", t);
+                last = unlocked;
+            }
+        }
+        let A = Account::immediate("", &Alice, 100);
+        assert_eq!(A.timeline(), vec![(0, 100)]);
+    }
+    #[test] fn vest_linear () {
+        let Alice = HumanAddr::from("Alice");
+        let A = Account::periodic("", &Alice, 100, 20, 10, 0, 80).with_curve(Curve::Linear);
+        assert_eq!(A.portion_count(), 80);
+        assert_eq!(A.remainder(),      0);
+        for (t, unlocked) in &[(0, 0), (9, 0), (10, 20), (11, 21), (50, 60), (89, 99), (90, 100), (200, 100)] {
+            assert_eq!(A.unlocked(*t, &Alice), *unlocked, "t={}", t);
+        }
+    }
+    #[test] fn vest_exponential () {
+        let Alice = HumanAddr::from("Alice");
+        let A = Account::periodic("", &Alice, 100, 0, 0, 10, 40)
+            .with_curve(Curve::Exponential { exponent: 2 });
+        assert_eq!(A.portion_count(), 4);
+        assert_eq!(A.remainder(),     0);
+        for (t, unlocked) in &[(0, 6), (9, 6), (10, 25), (20, 56), (30, 100), (40, 100)] {
+            assert_eq!(A.unlocked(*t, &Alice), *unlocked, "t={}", t);
+        }
+    }
+    #[test] fn vest_milestones () {
+        let Alice = HumanAddr::from("Alice");
+        let A = Account::periodic("", &Alice, 100, 10, 5, 0, 100)
+            .with_curve(Curve::Milestones(vec![
+                Milestone::new(0, 10), Milestone::new(30, 40), Milestone::new(100, 100)
+            ]));
+        assert_eq!(A.portion_count(), 3);
+        assert_eq!(A.remainder(),     0);
+        for (t, unlocked) in &[(4, 0), (5, 10), (34, 10), (35, 40), (104, 40), (105, 100)] {
+            assert_eq!(A.unlocked(*t, &Alice), *unlocked, "t={}", t);
+        }
+        assert!(A.vests_at(35));
+        assert!(!A.vests_at(36));
+    }
 }